@@ -0,0 +1,114 @@
+//! Decoding the `tests/list.rs` fixture with and without a [`DecodeContext`]
+//!
+//! Run with `cargo bench --bench lookup`, it reports time and heap allocations per lookup.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use ubus::*;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+use common::Replay;
+
+/// Counts allocations made through the system allocator
+struct Counting;
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// The messages of `ubus list`, as captured for `tests/list.rs`
+const LIST: &[&[u8]] = &[
+    include_bytes!("../tests/corpus/hello.bin"),
+    include_bytes!("../tests/corpus/list_data_0.bin"),
+    include_bytes!("../tests/corpus/list_data_1.bin"),
+    include_bytes!("../tests/corpus/list_data_2.bin"),
+    include_bytes!("../tests/corpus/list_data_hostapd.bin"),
+    include_bytes!("../tests/corpus/list_data_4.bin"),
+    include_bytes!("../tests/corpus/list_data_5.bin"),
+    include_bytes!("../tests/corpus/list_data_6.bin"),
+    include_bytes!("../tests/corpus/list_data_7.bin"),
+    include_bytes!("../tests/corpus/list_data_8.bin"),
+    include_bytes!("../tests/corpus/list_data_9.bin"),
+    include_bytes!("../tests/corpus/list_data_10.bin"),
+    include_bytes!("../tests/corpus/list_data_11.bin"),
+    include_bytes!("../tests/corpus/list_data_12.bin"),
+    include_bytes!("../tests/corpus/list_data_13.bin"),
+    include_bytes!("../tests/corpus/list_data_14.bin"),
+    include_bytes!("../tests/corpus/list_data_15.bin"),
+    include_bytes!("../tests/corpus/list_data_16.bin"),
+    include_bytes!("../tests/corpus/list_data_17.bin"),
+    include_bytes!("../tests/corpus/list_data_18.bin"),
+    include_bytes!("../tests/corpus/list_data_19.bin"),
+    include_bytes!("../tests/corpus/list_data_20.bin"),
+    include_bytes!("../tests/corpus/list_data_21.bin"),
+    include_bytes!("../tests/corpus/list_data_22.bin"),
+    include_bytes!("../tests/corpus/list_data_23.bin"),
+    include_bytes!("../tests/corpus/list_data_24.bin"),
+    include_bytes!("../tests/corpus/list_data_25.bin"),
+    include_bytes!("../tests/corpus/list_status.bin"),
+];
+
+const ROUNDS: usize = 2000;
+
+/// Run `lookup` on a fresh connection `ROUNDS` times and report the cost of one
+fn bench(name: &str, stream: &[u8], mut lookup: impl FnMut(&mut Connection<Replay>) -> usize) {
+    // Warm up, e.g. the context's pools
+    let mut connection = Connection::new(Replay(stream)).unwrap();
+    let methods = lookup(&mut connection);
+
+    let mut elapsed = 0.0;
+    let mut allocations = 0;
+    for _ in 0..ROUNDS {
+        let mut connection = Connection::new(Replay(stream)).unwrap();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        assert_eq!(black_box(lookup(&mut connection)), methods);
+        elapsed += start.elapsed().as_secs_f64();
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+    }
+    println!(
+        "{:<12} {:>8.1} µs/lookup {:>8} allocations/lookup ({} methods)",
+        name,
+        elapsed * 1e6 / ROUNDS as f64,
+        allocations / ROUNDS,
+        methods
+    );
+}
+
+fn main() {
+    let stream = LIST.concat();
+
+    bench("lookup", &stream, |connection| {
+        let mut methods = 0;
+        connection
+            .lookup("", |obj| methods += obj.methods.len())
+            .unwrap();
+        methods
+    });
+
+    let mut context = DecodeContext::new();
+    bench("lookup_in", &stream, |connection| {
+        let mut methods = 0;
+        connection
+            .lookup_in(&mut context, "", |obj| methods += obj.methods.len())
+            .unwrap();
+        methods
+    });
+}
@@ -1,6 +1,6 @@
-use std::{convert::TryInto, path::Path};
+use std::path::Path;
 
-use ubus::{BlobMsg, UbusObject};
+use ubus::BlobMsgValue;
 
 fn main() {
     let obj_path = "network.device";
@@ -15,30 +15,19 @@ fn main() {
             err
         })
         .unwrap();
-    let mut obj_json = String::new();
+    let mut obj = None;
     connection
-        .lookup(obj_path, |obj| {
-            obj_json = serde_json::to_string_pretty(&obj).unwrap();
-        })
+        .lookup(obj_path, |o| obj = Some(o.to_owned_value()))
         .unwrap();
-    let obj: UbusObject = serde_json::from_str(&obj_json).unwrap();
-    let args = obj.args_from_json(method, args).unwrap();
-    let mut json_str = String::new();
+    let obj = obj.unwrap();
+    let args = obj.as_object().args_from_json(method, args).unwrap();
+    let mut reply = None;
     connection
         .invoke(obj.id, method, &args, |bi| {
-            json_str = "{\n".to_string();
-            let mut first = true;
-            for x in bi {
-                if !first {
-                    json_str += ",\n";
-                }
-                //json_str += &format!("{:?}", x);
-                let msg: BlobMsg = x.try_into().unwrap();
-                json_str += &format!("{}", msg);
-                first = false;
-            }
-            json_str += "\n}";
+            reply = Some(BlobMsgValue::try_from(bi).unwrap());
         })
         .unwrap();
-    println!("{}", json_str);
+    if let Some(reply) = reply {
+        println!("{}", reply);
+    }
 }
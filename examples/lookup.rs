@@ -1,7 +1,5 @@
 use std::{env, path::Path};
 
-use ubus::UbusObject;
-
 fn main() {
     let args: Vec<String> = env::args().collect();
     let mut obj_path = "";
@@ -17,12 +15,11 @@ fn main() {
             return;
         }
     };
-    let mut obj_json = String::new();
+    let mut objs = Vec::new();
     connection
-        .lookup(obj_path, |obj| {
-            obj_json = serde_json::to_string_pretty(&obj).unwrap();
-        })
+        .lookup(obj_path, |obj| objs.push(obj.to_owned_value()))
         .unwrap();
-    let obj: UbusObject = serde_json::from_str(&obj_json).unwrap();
-    println!("{:?}", obj);
+    for obj in objs {
+        println!("{:?}", obj);
+    }
 }
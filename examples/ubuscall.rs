@@ -1,14 +1,33 @@
-use serde_json::{Value, to_string_pretty};
 use std::env;
 use std::path::Path;
+use ubus::{BlobMsg, BlobMsgPath, BlobMsgPayload, BlobMsgTable, JsonStyle, UbusError};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let mut style = JsonStyle::Indent;
+    if args.get(1).map(String::as_str) == Some("-S") {
+        style = JsonStyle::Simple;
+        args.remove(1);
+    }
+    let mut query = None;
+    if args.get(1).map(String::as_str) == Some("-q") && args.len() > 2 {
+        match BlobMsgPath::parse(&args[2]) {
+            Ok(path) => query = Some(path),
+            Err(err) => {
+                eprintln!("{}: {}", args[2], err);
+                return;
+            }
+        }
+        args.drain(1..3);
+    }
     let mut obj_path = "";
     let mut method = "";
     let mut data = "";
     if args.len() < 2 || args.len() > 4 {
-        eprintln!("{} <object> <method> [arguments as json]", args[0]);
+        eprintln!(
+            "{} [-S] [-q <path>] <object> <method> [arguments as json]",
+            args[0]
+        );
         return;
     } else if args.len() >= 3 {
         obj_path = &args[1];
@@ -27,8 +46,38 @@ fn main() {
             return;
         }
     };
-    let json = connection.call(obj_path, method, data).unwrap();
-    let parsed: Value = serde_json::from_str(&json).unwrap();
-    let pretty_json = to_string_pretty(&parsed).unwrap();
-    println!("{}", pretty_json);
+    let Some(path) = query else {
+        let json = connection
+            .call_with_style(obj_path, method, data, style)
+            .unwrap();
+        println!("{}", json);
+        return;
+    };
+
+    // Like jsonfilter, print every match on its own line with strings unquoted
+    let mut result = Ok(());
+    connection
+        .call_with(obj_path, method, data, |reply| {
+            let table: Result<BlobMsgTable, UbusError> = reply
+                .map(|blob| {
+                    let msg: BlobMsg = blob?.try_into()?;
+                    Ok((msg.name, msg.data))
+                })
+                .collect();
+            let reply = match table {
+                Ok(table) => BlobMsgPayload::Table(table),
+                Err(err) => {
+                    result = Err(err);
+                    return;
+                }
+            };
+            for value in path.select(&reply) {
+                match value {
+                    BlobMsgPayload::String(s) => println!("{}", s),
+                    value => println!("{}", value.to_json(style)),
+                }
+            }
+        })
+        .unwrap();
+    result.unwrap();
 }
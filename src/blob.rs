@@ -1,10 +1,9 @@
-use crate::{BlobMsg, BlobMsgPayload, BlobMsgType, UbusError};
+use crate::{BlobMsg, BlobMsgPayload, BlobMsgTable, BlobMsgType, DecodeContext, UbusError};
 
 use core::convert::{TryFrom, TryInto};
 use core::marker::PhantomData;
 use core::mem::{align_of, size_of, transmute};
 use core::str;
-use std::collections::HashMap;
 use std::vec::Vec;
 use storage_endian::BEu32;
 
@@ -13,11 +12,13 @@ use storage_endian::BEu32;
 pub struct BlobTag(BEu32);
 impl BlobTag {
     pub const SIZE: usize = size_of::<Self>();
+    /// Largest size a blob can announce
+    pub const MAX_SIZE: usize = Self::LEN_MASK as usize;
     const ID_MASK: u32 = 0x7f;
     const ID_SHIFT: u32 = 24;
     const LEN_MASK: u32 = 0xff_ff_ff;
     const EXTENDED_BIT: u32 = 1 << 31;
-    const ALIGNMENT: usize = align_of::<Self>();
+    pub(crate) const ALIGNMENT: usize = align_of::<Self>();
 
     pub fn new(id: u32, len: usize, extended: bool) -> Result<Self, UbusError> {
         if id > Self::ID_MASK || len < Self::SIZE || len > Self::LEN_MASK as usize {
@@ -50,15 +51,16 @@ impl BlobTag {
         u32::from(self.0 & Self::LEN_MASK) as usize
     }
 
-    pub fn set_size(&mut self, size: usize) {
-        *self = Self::new(self.id(), size, self.is_extended()).unwrap();
+    pub fn set_size(&mut self, size: usize) -> Result<(), UbusError> {
+        *self = Self::new(self.id(), size, self.is_extended())?;
+        Ok(())
     }
     /// Number of padding bytes between this blob and the next blob
     fn padding(&self) -> usize {
         Self::ALIGNMENT.wrapping_sub(self.size()) & (Self::ALIGNMENT - 1)
     }
     /// Number of bytes to the next tag
-    fn next_tag(&self) -> usize {
+    pub(crate) fn next_tag(&self) -> usize {
         self.size() + self.padding()
     }
     /// Total number of bytes following the tag (extended header + data)
@@ -85,12 +87,30 @@ impl core::fmt::Debug for BlobTag {
 
 pub struct BlobBuilder<'a> {
     buffer: &'a mut [u8],
+    start: usize,
     offset: usize,
+    /// Offsets of the nested attributes still open, innermost last
+    open: Vec<usize>,
 }
 
 impl<'a> BlobBuilder<'a> {
     pub fn from_bytes(buffer: &'a mut [u8]) -> Self {
-        Self { buffer, offset: 0 }
+        Self::from_bytes_at(buffer, 0)
+    }
+
+    /// Append attributes to `buffer` after the first `offset` bytes
+    pub(crate) fn from_bytes_at(buffer: &'a mut [u8], offset: usize) -> Self {
+        Self {
+            buffer,
+            start: offset,
+            offset,
+            open: Vec::new(),
+        }
+    }
+
+    /// Give back the buffer and the offset up to which it has been written
+    pub(crate) fn into_parts(self) -> (&'a mut [u8], usize) {
+        (self.buffer, self.offset)
     }
 
     pub fn push_u32(&mut self, id: u32, data: u32) -> Result<(), UbusError> {
@@ -110,23 +130,123 @@ impl<'a> BlobBuilder<'a> {
         id: u32,
         data: impl IntoIterator<Item = &'b u8>,
     ) -> Result<(), UbusError> {
-        let iter = data.into_iter();
-        let buffer = &mut self.buffer[self.offset..];
+        let start = self.begin(id, None)?;
+        self.extend(data)?;
+        self.end(start)
+    }
 
-        let mut offset = BlobTag::SIZE;
-        for b in iter {
-            if offset >= buffer.len() {
-                return Err(UbusError::InvalidData("BlobBuilder overflow!"));
+    /// Push a blobmsg attribute, nested tables and arrays are written in place
+    pub fn push_blobmsg(&mut self, msg: &BlobMsg) -> Result<(), UbusError> {
+        self.push_payload(msg.name, &msg.data)
+    }
+
+    pub(crate) fn push_payload(
+        &mut self,
+        name: &str,
+        payload: &BlobMsgPayload,
+    ) -> Result<(), UbusError> {
+        let start = self.begin(payload.ty().value(), Some(name))?;
+        match payload {
+            BlobMsgPayload::Array(list) => {
+                self.open.push(start);
+                for item in list {
+                    self.push_payload(item.name, &item.data)?;
+                }
+                return self.close();
             }
-            buffer[offset] = *b;
-            offset += 1;
+            BlobMsgPayload::Table(table) => {
+                self.open.push(start);
+                for (name, data) in table.iter() {
+                    self.push_payload(name, data)?;
+                }
+                return self.close();
+            }
+            BlobMsgPayload::String(s) => self.extend(s.as_bytes().iter().chain([0u8].iter()))?,
+            BlobMsgPayload::Bytes(bytes) => self.extend(bytes.iter().chain([0u8].iter()))?,
+            BlobMsgPayload::Int64(num) => self.extend(&num.to_be_bytes())?,
+            BlobMsgPayload::Int32(num) => self.extend(&num.to_be_bytes())?,
+            BlobMsgPayload::Int16(num) => self.extend(&num.to_be_bytes())?,
+            BlobMsgPayload::Int8(num) => self.extend(&num.to_be_bytes())?,
+            BlobMsgPayload::Bool(b) => self.extend(&[*b as u8])?,
+            BlobMsgPayload::Double(num) => self.extend(&num.to_be_bytes())?,
+            BlobMsgPayload::Unknown(_, bytes) => self.extend(*bytes)?,
         }
+        self.end(start)
+    }
 
-        let tag = BlobTag::new(id, offset, false)?;
-        let pad = tag.padding();
-        buffer[..4].copy_from_slice(&tag.to_bytes());
+    /// Open a nested attribute, everything pushed until [`Self::close`] goes inside it
+    pub fn open(&mut self, id: u32) -> Result<(), UbusError> {
+        let start = self.begin(id, None)?;
+        self.open.push(start);
+        Ok(())
+    }
+
+    /// Open a blobmsg table like `blobmsg_open_table`
+    pub fn open_table(&mut self, name: &str) -> Result<(), UbusError> {
+        let start = self.begin(BlobMsgType::TABLE.value(), Some(name))?;
+        self.open.push(start);
+        Ok(())
+    }
 
-        self.offset += offset + pad;
+    /// Open a blobmsg array like `blobmsg_open_array`
+    pub fn open_array(&mut self, name: &str) -> Result<(), UbusError> {
+        let start = self.begin(BlobMsgType::ARRAY.value(), Some(name))?;
+        self.open.push(start);
+        Ok(())
+    }
+
+    /// Close the innermost open attribute
+    pub fn close(&mut self) -> Result<(), UbusError> {
+        let start = self
+            .open
+            .pop()
+            .ok_or(UbusError::InvalidData("No open attribute to close"))?;
+        self.end(start)
+    }
+
+    /// Number of attributes opened but not closed yet
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Write the tag and extended name header of a new attribute
+    fn begin(&mut self, id: u32, name: Option<&str>) -> Result<usize, UbusError> {
+        let start = self.offset;
+        let tag = BlobTag::new(id, BlobTag::SIZE, name.is_some())?;
+        self.extend(&tag.to_bytes())?;
+        if let Some(name) = name {
+            let len = u16::try_from(name.len())
+                .map_err(|_| UbusError::InvalidData("Blobmsg name too long"))?;
+            self.extend(&len.to_be_bytes())?;
+            self.extend(name.as_bytes().iter().chain([0u8].iter()))?;
+            self.pad()?;
+        }
+        Ok(start)
+    }
+
+    /// Update the size of the attribute starting at `start` and pad after it
+    fn end(&mut self, start: usize) -> Result<(), UbusError> {
+        let bytes = self.buffer[start..].first_chunk::<{ BlobTag::SIZE }>();
+        let tag = BlobTag::from_bytes(*bytes.ok_or(UbusError::InvalidData("No tag to update"))?);
+        let tag = BlobTag::new(tag.id(), self.offset - start, tag.is_extended())?;
+        self.buffer[start..start + BlobTag::SIZE].copy_from_slice(&tag.to_bytes());
+        self.pad()
+    }
+
+    fn pad(&mut self) -> Result<(), UbusError> {
+        let len = self.offset - self.start;
+        let padding = BlobTag::ALIGNMENT.wrapping_sub(len) & (BlobTag::ALIGNMENT - 1);
+        self.extend(&[0; BlobTag::ALIGNMENT][..padding])
+    }
+
+    fn extend<'b>(&mut self, data: impl IntoIterator<Item = &'b u8>) -> Result<(), UbusError> {
+        for b in data {
+            let Some(slot) = self.buffer.get_mut(self.offset) else {
+                return Err(UbusError::InvalidData("BlobBuilder overflow!"));
+            };
+            *slot = *b;
+            self.offset += 1;
+        }
         Ok(())
     }
 
@@ -135,7 +255,7 @@ impl<'a> BlobBuilder<'a> {
     }
 
     pub fn len(&self) -> usize {
-        self.offset
+        self.offset - self.start
     }
 }
 
@@ -147,10 +267,11 @@ pub struct Blob<'a> {
 
 impl<'a> Blob<'a> {
     pub fn from_bytes(data: &'a [u8]) -> Result<Self, UbusError> {
-        valid_data!(data.len() >= BlobTag::SIZE, "Blob too short");
         // Read the blob's tag
-        let (tag, data) = data.split_at(BlobTag::SIZE);
-        let tag = BlobTag::from_bytes(tag.try_into().unwrap());
+        let Some((tag, data)) = data.split_first_chunk() else {
+            return Err(UbusError::InvalidData("Blob too short"));
+        };
+        let tag = BlobTag::from_bytes(*tag);
         Self::from_tag_and_data(tag, data)
     }
     pub fn from_tag_and_data(tag: BlobTag, data: &'a [u8]) -> Result<Self, UbusError> {
@@ -164,46 +285,230 @@ impl<'a> Blob<'a> {
     }
 }
 
-impl<'a> TryInto<BlobMsg<'a>> for Blob<'a> {
-    type Error = UbusError;
-    fn try_into(self) -> Result<BlobMsg<'a>, Self::Error> {
+impl<'a> Blob<'a> {
+    /// Split an extended (blobmsg) blob into its name and the aligned payload following it
+    pub fn name_and_payload(&self) -> Result<(&'a str, &'a [u8]), UbusError> {
         if !self.tag.is_extended() {
             return Err(UbusError::InvalidData("Not an extended blob"));
         }
-        let (len_bytes, data) = self.data.split_at(size_of::<u16>());
-        let name_len = u16::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
-        // Get the string
-        if name_len > data.len() {
-            //eprintln!("name_len:{}, data:{:?}", name_len, data);
+        let Some((len_bytes, data)) = self.data.split_first_chunk::<2>() else {
+            return Err(UbusError::InvalidData(
+                "Extended blob too short for name length",
+            ));
+        };
+        let name_len = u16::from_be_bytes(*len_bytes) as usize;
+        // Get the string and its nul terminator
+        if name_len >= data.len() {
             return Err(UbusError::InvalidData("name lenth > data lenth"));
         }
         let (name_bytes, data) = data.split_at(name_len);
-        let name = str::from_utf8(name_bytes).unwrap();
-        // Get the nul terminator (implicit)
-        let name_len = name_len + 1;
+        let name = str::from_utf8(name_bytes)?;
         let (terminator, data) = data.split_at(1);
         valid_data!(terminator[0] == b'\0', "No extended name nul terminator");
         // Ensure the rest of the payload is aligned
-        let name_total_len = size_of::<u16>() + name_len;
+        let name_total_len = size_of::<u16>() + name_len + 1;
         let name_padding =
             BlobTag::ALIGNMENT.wrapping_sub(name_total_len) & (BlobTag::ALIGNMENT - 1);
-        let payload = Payload::from(&data[name_padding..]);
-        let data = match self.tag.id().into() {
-            BlobMsgType::ARRAY => BlobMsgPayload::Array(payload.try_into()?),
-            BlobMsgType::TABLE => BlobMsgPayload::Table(payload.try_into()?),
-            BlobMsgType::STRING => BlobMsgPayload::String(payload.try_into()?),
+        // An attribute without payload may omit the padding after its name
+        Ok((name, data.get(name_padding..).unwrap_or_default()))
+    }
+}
+
+impl<'a> Blob<'a> {
+    /// Decode this blob as a blobmsg, enforcing the given limits
+    pub fn decode_with(&self, limits: &DecodeLimits) -> Result<BlobMsg<'a>, UbusError> {
+        self.decode_nested(&mut Budget::new(limits))
+    }
+
+    fn decode_nested(&self, budget: &mut Budget) -> Result<BlobMsg<'a>, UbusError> {
+        let (name, payload) = self.name_and_payload()?;
+        let data = Payload::from(payload).decode_nested(self.tag.id().into(), budget)?;
+        Ok(BlobMsg { name, data })
+    }
+}
+
+impl<'a> TryInto<BlobMsg<'a>> for Blob<'a> {
+    type Error = UbusError;
+    fn try_into(self) -> Result<BlobMsg<'a>, Self::Error> {
+        self.decode_with(&DecodeLimits::default())
+    }
+}
+
+/// Bounds on what the decoder accepts from a peer
+///
+/// Nested tables and arrays are decoded recursively, so without a depth limit a
+/// crafted message can exhaust the stack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum nesting of tables and arrays
+    pub max_depth: usize,
+    /// Maximum number of attributes decoded into one value tree
+    pub max_attrs: usize,
+    /// Maximum size of a ubus message including its header
+    pub max_message_len: usize,
+}
+
+impl DecodeLimits {
+    /// Same maximum message size as `UBUS_MAX_MSGLEN` in ubusd
+    pub const DEFAULT: Self = Self {
+        max_depth: 32,
+        max_attrs: 16 * 1024,
+        max_message_len: 1024 * 1024,
+    };
+
+    /// No limits besides the size of the receive buffer
+    pub const UNLIMITED: Self = Self {
+        max_depth: usize::MAX,
+        max_attrs: usize::MAX,
+        max_message_len: usize::MAX,
+    };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What is left of the limits while decoding one value tree
+pub(crate) struct Budget<'l> {
+    limits: &'l DecodeLimits,
+    depth: usize,
+    attrs: usize,
+    /// Emptied vectors to decode tables and arrays into
+    pool: Option<&'l mut DecodeContext>,
+}
+
+impl<'l> Budget<'l> {
+    fn new(limits: &'l DecodeLimits) -> Self {
+        Self {
+            limits,
+            depth: 0,
+            attrs: 0,
+            pool: None,
+        }
+    }
+
+    pub(crate) fn pooled(limits: &'l DecodeLimits, pool: &'l mut DecodeContext) -> Self {
+        Self {
+            pool: Some(pool),
+            ..Self::new(limits)
+        }
+    }
+
+    fn list<'a>(&mut self) -> Vec<BlobMsg<'a>> {
+        self.pool
+            .as_mut()
+            .map(|pool| pool.list())
+            .unwrap_or_default()
+    }
+
+    fn table<'a>(&mut self) -> Vec<(&'a str, BlobMsgPayload<'a>)> {
+        self.pool
+            .as_mut()
+            .map(|pool| pool.table())
+            .unwrap_or_default()
+    }
+
+    fn enter(&mut self) -> Result<(), UbusError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(UbusError::NestingTooDeep(self.limits.max_depth));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn count(&mut self) -> Result<(), UbusError> {
+        if self.attrs >= self.limits.max_attrs {
+            return Err(UbusError::TooManyAttributes(self.limits.max_attrs));
+        }
+        self.attrs += 1;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Payload<'a>(&'a [u8]);
+impl<'a> Payload<'a> {
+    /// Decode the payload of a blobmsg of the given type
+    pub fn decode(self, ty: BlobMsgType) -> Result<BlobMsgPayload<'a>, UbusError> {
+        self.decode_with(ty, &DecodeLimits::default())
+    }
+
+    /// Decode the payload of a blobmsg of the given type, enforcing the given limits
+    pub fn decode_with(
+        self,
+        ty: BlobMsgType,
+        limits: &DecodeLimits,
+    ) -> Result<BlobMsgPayload<'a>, UbusError> {
+        self.decode_nested(ty, &mut Budget::new(limits))
+    }
+
+    /// Decode the payload of a table, enforcing the given limits
+    pub fn table_with(self, limits: &DecodeLimits) -> Result<BlobMsgTable<'a>, UbusError> {
+        self.table(&mut Budget::new(limits))
+    }
+
+    pub(crate) fn decode_nested(
+        self,
+        ty: BlobMsgType,
+        budget: &mut Budget,
+    ) -> Result<BlobMsgPayload<'a>, UbusError> {
+        let payload = self;
+        Ok(match ty {
+            BlobMsgType::ARRAY => BlobMsgPayload::Array(payload.list(budget)?),
+            BlobMsgType::TABLE => BlobMsgPayload::Table(payload.table(budget)?),
+            BlobMsgType::STRING => {
+                // Strings are arbitrary bytes on the wire (e.g. SSIDs), keep them all
+                let bytes: &[u8] = payload.into();
+                let bytes = bytes.strip_suffix(b"\0").unwrap_or(bytes);
+                match str::from_utf8(bytes) {
+                    Ok(s) => BlobMsgPayload::String(s),
+                    Err(_) => BlobMsgPayload::Bytes(bytes),
+                }
+            }
             BlobMsgType::INT64 => BlobMsgPayload::Int64(payload.try_into()?),
             BlobMsgType::INT32 => BlobMsgPayload::Int32(payload.try_into()?),
             BlobMsgType::INT16 => BlobMsgPayload::Int16(payload.try_into()?),
-            BlobMsgType::INT8 => BlobMsgPayload::Int8(payload.try_into()?),
+            // INT8 shares type 7 with BOOL, which libubox reads and prints as a boolean
+            BlobMsgType::BOOL => BlobMsgPayload::Bool(payload.try_into()?),
             BlobMsgType::DOUBLE => BlobMsgPayload::Double(payload.try_into()?),
             id => BlobMsgPayload::Unknown(id.value(), payload.into()),
-        };
-        Ok(BlobMsg { name, data })
+        })
+    }
+
+    /// Decode the children of an array one level further down
+    fn list(self, budget: &mut Budget) -> Result<Vec<BlobMsg<'a>>, UbusError> {
+        let list = budget.list();
+        self.children(budget, list, |msg| msg)
+    }
+
+    /// Decode the entries of a table one level further down
+    fn table(self, budget: &mut Budget) -> Result<BlobMsgTable<'a>, UbusError> {
+        let table = budget.table();
+        let table = self.children(budget, table, |msg| (msg.name, msg.data))?;
+        Ok(BlobMsgTable::from_vec(table))
+    }
+
+    fn children<T>(
+        self,
+        budget: &mut Budget,
+        mut list: Vec<T>,
+        item: impl Fn(BlobMsg<'a>) -> T,
+    ) -> Result<Vec<T>, UbusError> {
+        budget.enter()?;
+        for blob in BlobIter::<Blob>::new(self.0) {
+            budget.count()?;
+            list.push(item(blob?.decode_nested(budget)?));
+        }
+        budget.leave();
+        Ok(list)
     }
 }
-#[derive(Clone, Debug)]
-pub struct Payload<'a>(&'a [u8]);
 impl<'a> From<&'a [u8]> for Payload<'a> {
     fn from(value: &'a [u8]) -> Self {
         Payload(value)
@@ -216,11 +521,12 @@ macro_rules! payload_try_into_number {
         impl<'a> TryInto<$ty> for Payload<'a>{
             type Error = UbusError;
             fn try_into(self) -> Result<$ty, Self::Error> {
-                let size = size_of::<$ty>();
-                if let Ok(bytes) = self.0[..size].try_into() {
-                    Ok(<$ty>::from_be_bytes(bytes))
-                } else {
-                    Err(UbusError::InvalidData(stringify!("Blob wrong size for " $ty)))
+                match self.0.first_chunk::<{ size_of::<$ty>() }>() {
+                    Some(bytes) => Ok(<$ty>::from_be_bytes(*bytes)),
+                    None => Err(UbusError::PayloadTooShort {
+                        needed: size_of::<$ty>(),
+                        found: self.0.len(),
+                    }),
                 }
             }
         }
@@ -230,8 +536,13 @@ payload_try_into_number!(u8, i8, u16, i16, u32, i32, u64, i64, f64,);
 impl<'a> TryInto<bool> for Payload<'a> {
     type Error = UbusError;
     fn try_into(self) -> Result<bool, Self::Error> {
-        let value: u8 = self.0[0];
-        Ok(value != 0)
+        match self.0.first() {
+            Some(value) => Ok(*value != 0),
+            None => Err(UbusError::PayloadTooShort {
+                needed: 1,
+                found: 0,
+            }),
+        }
     }
 }
 
@@ -250,25 +561,14 @@ impl<'a> TryInto<&'a str> for Payload<'a> {
 impl<'a> TryInto<Vec<BlobMsg<'a>>> for Payload<'a> {
     type Error = UbusError;
     fn try_into(self) -> Result<Vec<BlobMsg<'a>>, UbusError> {
-        let iter = BlobIter::<Blob>::new(self.into());
-        let mut list = Vec::new();
-        for item in iter {
-            list.push(item.try_into().map_err(UbusError::from)?);
-        }
-        Ok(list)
+        self.list(&mut Budget::new(&DecodeLimits::default()))
     }
 }
 
-impl<'a> TryInto<HashMap<&'a str, BlobMsgPayload<'a>>> for Payload<'a> {
+impl<'a> TryInto<BlobMsgTable<'a>> for Payload<'a> {
     type Error = UbusError;
-    fn try_into(self) -> Result<HashMap<&'a str, BlobMsgPayload<'a>>, UbusError> {
-        let mut map = HashMap::<&str, BlobMsgPayload>::new();
-        let iter = BlobIter::<Blob>::new(self.into());
-        for item in iter {
-            let item: BlobMsg = item.try_into()?;
-            map.insert(item.name, item.data);
-        }
-        Ok(map)
+    fn try_into(self) -> Result<BlobMsgTable<'a>, UbusError> {
+        self.table_with(&DecodeLimits::default())
     }
 }
 
@@ -291,7 +591,7 @@ impl<'a, T> Into<BlobIter<'a, T>> for Payload<'a> {
 }
 
 pub struct BlobIter<'a, T> {
-    data: &'a [u8],
+    pub(crate) data: &'a [u8],
     _phantom: PhantomData<T>,
 }
 impl<'a, T> BlobIter<'a, T> {
@@ -303,32 +603,56 @@ impl<'a, T> BlobIter<'a, T> {
     }
 }
 
-impl<'a, T: TryFrom<Blob<'a>>> Iterator for BlobIter<'a, T> {
-    type Item = T;
+/// Yields every attribute or the first decoding error, after which iteration stops
+impl<'a, T> Iterator for BlobIter<'a, T>
+where
+    T: TryFrom<Blob<'a>>,
+    UbusError: From<T::Error>,
+{
+    type Item = Result<T, UbusError>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.data.is_empty() {
             return None;
         }
-        if let Ok(blob) = Blob::from_bytes(self.data) {
-            // Advance the internal pointer to the next tag
-            self.data = &self.data[blob.tag.next_tag()..];
-            if let Ok(blob) = blob.try_into() {
-                //println!("{:?}", self.data);
-                return Some(blob);
+        let blob = match Blob::from_bytes(self.data) {
+            Ok(blob) => blob,
+            Err(e) => {
+                self.data = &[];
+                return Some(Err(e));
             }
+        };
+        // Advance the internal pointer to the next tag, the last one may be unpadded
+        self.data = self.data.get(blob.tag.next_tag()..).unwrap_or_default();
+        let item = T::try_from(blob).map_err(UbusError::from);
+        if item.is_err() {
+            self.data = &[];
         }
-        None
+        Some(item)
     }
 }
 
+impl<'a, T> core::iter::FusedIterator for BlobIter<'a, T>
+where
+    T: TryFrom<Blob<'a>>,
+    UbusError: From<T::Error>,
+{
+}
+
 impl<T> core::fmt::Debug for BlobIter<'_, T> {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(f, "BlobIter")
     }
 }
 
+/// Growable blobmsg writer
+///
+/// Tables and arrays are written in place: open them with [`Self::open_table`] or
+/// [`Self::open_array`], add their children and [`Self::close`] them again, like
+/// `blobmsg_open_table`/`blobmsg_close_table` in libubox.
 pub struct BlobMsgBuilder<'a> {
     buffer: Vec<u8>,
+    /// Offsets of the attributes still open, innermost last
+    open: Vec<usize>,
     _phantom: PhantomData<&'a mut [u8]>,
 }
 
@@ -336,100 +660,197 @@ impl<'a> TryFrom<BlobMsg<'a>> for BlobMsgBuilder<'a> {
     type Error = UbusError;
 
     fn try_from(blobmsg: BlobMsg<'a>) -> Result<Self, Self::Error> {
-        let name = blobmsg.name;
-        let blob = match blobmsg.data {
-            BlobMsgPayload::String(s) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::STRING.value(), name);
-                blob.push_str(s)?;
-                blob
-            }
-            BlobMsgPayload::Int64(num) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::INT64.value(), name);
-                blob.push_int64(num)?;
-                blob
-            }
-            BlobMsgPayload::Int32(num) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::INT32.value(), name);
-                blob.push_int32(num)?;
-                blob
-            }
-            BlobMsgPayload::Int16(num) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::INT16.value(), name);
-                blob.push_int16(num)?;
-                blob
-            }
-            BlobMsgPayload::Int8(num) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::INT8.value(), name);
-                blob.push_int8(num)?;
-                blob
-            }
-            BlobMsgPayload::Double(num) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::DOUBLE.value(), name);
-                blob.push_double(num)?;
-                blob
-            }
-            BlobMsgPayload::Bool(b) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::BOOL.value(), name);
-                blob.push_int8(b)?;
-                blob
-            }
-            BlobMsgPayload::Unknown(_typeid, _bytes) => {
-                //println!("\"type={} data={:?}\"", typeid, bytes);
-                unimplemented!()
-            }
-            BlobMsgPayload::Array(list) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::ARRAY.value(), name);
-                for item in list {
-                    let inner_blob = BlobMsgBuilder::try_from(item).unwrap();
-                    blob.push_bytes(inner_blob.data())?;
-                }
-                blob
-            }
-            BlobMsgPayload::Table(table) => {
-                let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::TABLE.value(), name);
-                for (name, data) in table {
-                    let inner_blobmsg = BlobMsg { name, data };
-                    let inner_blob = BlobMsgBuilder::try_from(inner_blobmsg).unwrap();
-                    //let inner_blob = inner_blob.build();
-                    blob.push_bytes(inner_blob.data())?;
-                }
-                blob
-            }
-        };
+        let mut blob = BlobMsgBuilder::new();
+        blob.add(&blobmsg)?;
         Ok(blob)
     }
 }
 
+impl Default for BlobMsgBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> BlobMsgBuilder<'a> {
+    /// Empty builder for a list of attributes without enclosing header, e.g. call arguments
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            open: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn from_bytes(bytes: &'a [u8]) -> Self {
         Self {
             buffer: Vec::from(bytes),
+            open: if bytes.len() >= BlobTag::SIZE {
+                Vec::from([0])
+            } else {
+                Vec::new()
+            },
             _phantom: PhantomData,
         }
     }
-    pub fn new_extended(id: u32, name: &str) -> Self {
-        let buffer = Vec::new();
-        let _phantom = PhantomData::<&mut [u8]>;
-        let mut blob = Self { buffer, _phantom };
-        //blob.buffer.extend(&[0u8; BlobTag::SIZE]);
-        let tag = BlobTag::new(id, BlobTag::SIZE, true).unwrap();
-        blob.buffer.extend(tag.to_bytes());
-        let len_bytes = u16::to_be_bytes(name.len() as u16);
-        blob.buffer.extend(len_bytes);
-        blob.buffer.extend_from_slice(name.as_bytes());
-        blob.buffer.push(b'\0');
-        let name_total_len = size_of::<u16>() + name.len() + 1;
-        let name_padding =
-            BlobTag::ALIGNMENT.wrapping_sub(name_total_len) & (BlobTag::ALIGNMENT - 1);
-        blob.buffer.resize(blob.buffer.len() + name_padding, 0u8);
-        let tag = BlobTag::new(id, blob.buffer.len(), true).unwrap();
-        blob.buffer[..4].copy_from_slice(&tag.to_bytes());
-        blob
+
+    /// Builder for a single attribute, its payload is added with the `push_*` methods
+    pub fn new_extended(id: u32, name: &str) -> Result<Self, UbusError> {
+        let mut blob = Self::new();
+        let start = blob.begin(id, name)?;
+        blob.open.push(start);
+        Ok(blob)
+    }
+
+    /// Open a table, following attributes are added to it until [`Self::close`]
+    pub fn open_table(&mut self, name: &str) -> Result<(), UbusError> {
+        let start = self.begin(BlobMsgType::TABLE.value(), name)?;
+        self.open.push(start);
+        Ok(())
+    }
+
+    /// Open an array, names of the following attributes are ignored by readers
+    pub fn open_array(&mut self, name: &str) -> Result<(), UbusError> {
+        let start = self.begin(BlobMsgType::ARRAY.value(), name)?;
+        self.open.push(start);
+        Ok(())
+    }
+
+    /// Close the innermost open table or array
+    pub fn close(&mut self) -> Result<(), UbusError> {
+        let start = self
+            .open
+            .pop()
+            .ok_or(UbusError::InvalidData("No open table or array to close"))?;
+        self.end(start)
+    }
+
+    /// Number of tables and arrays opened but not closed yet
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Add an attribute with a raw payload
+    pub fn add_field(&mut self, id: u32, name: &str, data: &[u8]) -> Result<(), UbusError> {
+        let start = self.begin(id, name)?;
+        self.buffer.extend_from_slice(data);
+        self.end(start)
+    }
+
+    pub fn add_string(&mut self, name: &str, data: &str) -> Result<(), UbusError> {
+        self.add_string_bytes(name, data.as_bytes())
+    }
+
+    /// Add a `STRING` holding arbitrary bytes, e.g. an SSID which is not valid UTF-8
+    pub fn add_string_bytes(&mut self, name: &str, data: &[u8]) -> Result<(), UbusError> {
+        let start = self.begin(BlobMsgType::STRING.value(), name)?;
+        self.buffer.extend_from_slice(data);
+        self.buffer.push(b'\0');
+        self.end(start)
+    }
+
+    pub fn add_int64(&mut self, name: &str, data: i64) -> Result<(), UbusError> {
+        self.add_field(BlobMsgType::INT64.value(), name, &data.to_be_bytes())
+    }
+
+    pub fn add_int32(&mut self, name: &str, data: i32) -> Result<(), UbusError> {
+        self.add_field(BlobMsgType::INT32.value(), name, &data.to_be_bytes())
+    }
+
+    pub fn add_int16(&mut self, name: &str, data: i16) -> Result<(), UbusError> {
+        self.add_field(BlobMsgType::INT16.value(), name, &data.to_be_bytes())
+    }
+
+    pub fn add_int8(&mut self, name: &str, data: i8) -> Result<(), UbusError> {
+        self.add_field(BlobMsgType::INT8.value(), name, &data.to_be_bytes())
+    }
+
+    pub fn add_double(&mut self, name: &str, data: f64) -> Result<(), UbusError> {
+        self.add_field(BlobMsgType::DOUBLE.value(), name, &data.to_be_bytes())
     }
 
-    pub fn tag(&self) -> BlobTag {
-        let tag_bytes: [u8; BlobTag::SIZE] = self.buffer[..4].try_into().unwrap();
-        BlobTag::from_bytes(tag_bytes)
+    pub fn add_bool(&mut self, name: &str, data: bool) -> Result<(), UbusError> {
+        self.add_field(BlobMsgType::BOOL.value(), name, &[data as u8])
+    }
+
+    /// Add a decoded blobmsg, nested tables and arrays are written in place
+    pub fn add(&mut self, msg: &BlobMsg) -> Result<(), UbusError> {
+        self.add_payload(msg.name, &msg.data)
+    }
+
+    fn add_payload(&mut self, name: &str, payload: &BlobMsgPayload) -> Result<(), UbusError> {
+        match payload {
+            BlobMsgPayload::Array(list) => {
+                self.open_array(name)?;
+                for item in list {
+                    self.add_payload(item.name, &item.data)?;
+                }
+                self.close()
+            }
+            BlobMsgPayload::Table(table) => {
+                self.open_table(name)?;
+                for (name, data) in table.iter() {
+                    self.add_payload(name, data)?;
+                }
+                self.close()
+            }
+            BlobMsgPayload::String(s) => self.add_string(name, s),
+            BlobMsgPayload::Bytes(bytes) => self.add_string_bytes(name, bytes),
+            BlobMsgPayload::Int64(num) => self.add_int64(name, *num),
+            BlobMsgPayload::Int32(num) => self.add_int32(name, *num),
+            BlobMsgPayload::Int16(num) => self.add_int16(name, *num),
+            // INT8 shares its type id with BOOL, only the payload differs
+            BlobMsgPayload::Int8(num) => self.add_int8(name, *num),
+            BlobMsgPayload::Bool(b) => self.add_bool(name, *b),
+            BlobMsgPayload::Double(num) => self.add_double(name, *num),
+            BlobMsgPayload::Unknown(typeid, bytes) => self.add_field(*typeid, name, bytes),
+        }
+    }
+
+    /// Write the tag and name header of a new attribute
+    fn begin(&mut self, id: u32, name: &str) -> Result<usize, UbusError> {
+        let start = self.buffer.len();
+        let len = u16::try_from(name.len())
+            .map_err(|_| UbusError::InvalidData("Blobmsg name too long"))?;
+        self.buffer
+            .extend(BlobTag::new(id, BlobTag::SIZE, true)?.to_bytes());
+        self.buffer.extend(len.to_be_bytes());
+        self.buffer.extend_from_slice(name.as_bytes());
+        self.buffer.push(b'\0');
+        self.pad();
+        Ok(start)
+    }
+
+    /// Update the size of the attribute starting at `start` and pad after it, the
+    /// enclosing open containers grow along
+    fn end(&mut self, start: usize) -> Result<(), UbusError> {
+        self.set_size(start, self.buffer.len() - start)?;
+        self.pad();
+        for i in 0..self.open.len() {
+            let outer = self.open[i];
+            if outer < start {
+                self.set_size(outer, self.buffer.len() - outer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_size(&mut self, start: usize, size: usize) -> Result<(), UbusError> {
+        let bytes = self.buffer[start..].first_chunk::<{ BlobTag::SIZE }>();
+        let tag = BlobTag::from_bytes(*bytes.ok_or(UbusError::InvalidData("No tag to update"))?);
+        let tag = BlobTag::new(tag.id(), size, tag.is_extended())?;
+        self.buffer[start..start + BlobTag::SIZE].copy_from_slice(&tag.to_bytes());
+        Ok(())
+    }
+
+    fn pad(&mut self) {
+        let padding = BlobTag::ALIGNMENT.wrapping_sub(self.buffer.len()) & (BlobTag::ALIGNMENT - 1);
+        self.buffer.resize(self.buffer.len() + padding, 0u8);
+    }
+
+    /// Tag of the first attribute
+    pub fn tag(&self) -> Option<BlobTag> {
+        self.buffer.first_chunk().copied().map(BlobTag::from_bytes)
     }
 
     pub fn push_bytes<'b>(
@@ -439,11 +860,11 @@ impl<'a> BlobMsgBuilder<'a> {
         for b in data {
             self.buffer.push(*b);
         }
-        let mut tag = self.tag();
-        tag.set_size(self.buffer.len());
-        self.buffer[..4].copy_from_slice(&tag.to_bytes());
-        self.buffer.resize(self.buffer.len() + tag.padding(), 0u8);
-        Ok(())
+        let start = *self
+            .open
+            .last()
+            .ok_or(UbusError::InvalidData("No open attribute to push to"))?;
+        self.end(start)
     }
 
     pub fn push_int64(&mut self, data: i64) -> Result<(), UbusError> {
@@ -494,10 +915,8 @@ impl<'a> BlobMsgBuilder<'a> {
         &self.buffer
     }
 
-    pub fn build(&'a self) -> Blob<'a> {
-        let data = self.data();
-        let tag = BlobTag::from_bytes(self.buffer[..4].try_into().unwrap());
-        let data = &data[4..];
-        Blob { tag, data }
+    /// The first attribute
+    pub fn build(&'a self) -> Result<Blob<'a>, UbusError> {
+        Blob::from_bytes(self.data())
     }
 }
@@ -0,0 +1,380 @@
+use crate::{Blob, BlobBuilder, BlobIter, UbusError, UbusStatus};
+use core::mem::size_of;
+use serde::{Deserialize, Serialize};
+use std::boxed::Box;
+use std::string::ToString;
+
+values!(pub BlobAttrType(u32) {
+    UNSPEC = 0,
+    NESTED = 1,
+    BINARY = 2,
+    STRING = 3,
+    INT8   = 4,
+    INT16  = 5,
+    INT32  = 6,
+    INT64  = 7,
+    DOUBLE = 8,
+});
+
+impl BlobAttrType {
+    /// Check a payload fits this type, like `blob_check_type()`
+    pub fn check(self, data: &[u8]) -> Result<(), UbusError> {
+        let len = match self {
+            BlobAttrType::INT8 => size_of::<u8>(),
+            BlobAttrType::INT16 => size_of::<u16>(),
+            BlobAttrType::INT32 => size_of::<u32>(),
+            BlobAttrType::INT64 => size_of::<u64>(),
+            BlobAttrType::DOUBLE => size_of::<f64>(),
+            BlobAttrType::STRING if data.last() != Some(&b'\0') => {
+                return Err(UbusError::InvalidData(
+                    "String attribute not nul terminated",
+                ));
+            }
+            _ => return Ok(()),
+        };
+        if data.len() != len {
+            return Err(UbusError::InvalidData(
+                "Attribute length does not match its type",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Payload of a raw blob attribute, decoded according to its expected type
+///
+/// Unlike blobmsg, plain `blob_attr` does not record the type of its payload: it is
+/// implied by the attribute id and has to come from a policy or schema.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlobAttrValue<'a> {
+    Unspec(&'a [u8]),
+    Nested(&'a [u8]),
+    Binary(&'a [u8]),
+    String(&'a str),
+    Int8(u8),
+    Int16(u16),
+    Int32(u32),
+    Int64(u64),
+    Double(f64),
+}
+
+impl<'a> BlobAttrValue<'a> {
+    pub fn decode(ty: BlobAttrType, data: &'a [u8]) -> Result<Self, UbusError> {
+        Ok(match ty {
+            BlobAttrType::NESTED => BlobAttrValue::Nested(data),
+            BlobAttrType::BINARY => BlobAttrValue::Binary(data),
+            BlobAttrType::STRING => BlobAttrValue::String(BlobAttrData::decode(data)?),
+            BlobAttrType::INT8 => BlobAttrValue::Int8(BlobAttrData::decode(data)?),
+            BlobAttrType::INT16 => BlobAttrValue::Int16(BlobAttrData::decode(data)?),
+            BlobAttrType::INT32 => BlobAttrValue::Int32(BlobAttrData::decode(data)?),
+            BlobAttrType::INT64 => BlobAttrValue::Int64(BlobAttrData::decode(data)?),
+            BlobAttrType::DOUBLE => BlobAttrValue::Double(BlobAttrData::decode(data)?),
+            _ => BlobAttrValue::Unspec(data),
+        })
+    }
+
+    pub fn ty(&self) -> BlobAttrType {
+        match self {
+            BlobAttrValue::Unspec(_) => BlobAttrType::UNSPEC,
+            BlobAttrValue::Nested(_) => BlobAttrType::NESTED,
+            BlobAttrValue::Binary(_) => BlobAttrType::BINARY,
+            BlobAttrValue::String(_) => BlobAttrType::STRING,
+            BlobAttrValue::Int8(_) => BlobAttrType::INT8,
+            BlobAttrValue::Int16(_) => BlobAttrType::INT16,
+            BlobAttrValue::Int32(_) => BlobAttrType::INT32,
+            BlobAttrValue::Int64(_) => BlobAttrType::INT64,
+            BlobAttrValue::Double(_) => BlobAttrType::DOUBLE,
+        }
+    }
+
+    pub fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        match self {
+            BlobAttrValue::Unspec(data) | BlobAttrValue::Nested(data) => {
+                builder.push_bytes(id, *data)
+            }
+            BlobAttrValue::Binary(data) => BlobAttrData::encode(data, builder, id),
+            BlobAttrValue::String(s) => BlobAttrData::encode(s, builder, id),
+            BlobAttrValue::Int8(num) => BlobAttrData::encode(num, builder, id),
+            BlobAttrValue::Int16(num) => BlobAttrData::encode(num, builder, id),
+            BlobAttrValue::Int32(num) => BlobAttrData::encode(num, builder, id),
+            BlobAttrValue::Int64(num) => BlobAttrData::encode(num, builder, id),
+            BlobAttrValue::Double(num) => BlobAttrData::encode(num, builder, id),
+        }
+    }
+}
+
+/// One attribute of a `blob_parse()` style policy
+#[derive(Copy, Clone, Debug)]
+pub struct BlobAttrPolicy {
+    pub id: u32,
+    /// Expected type, `UNSPEC` accepts any payload
+    pub ty: BlobAttrType,
+    pub required: bool,
+}
+
+impl BlobAttrPolicy {
+    pub const fn new(id: u32, ty: BlobAttrType) -> Self {
+        Self {
+            id,
+            ty,
+            required: false,
+        }
+    }
+
+    pub const fn required(id: u32, ty: BlobAttrType) -> Self {
+        Self {
+            id,
+            ty,
+            required: true,
+        }
+    }
+
+    /// Parse a headerless list of raw attributes in one pass, returning the attribute
+    /// matching each policy entry in the same position
+    ///
+    /// Like `blob_parse()`, ids not in the policy are ignored and the last attribute wins
+    /// for duplicates. Payloads which do not fit the expected type and missing required
+    /// attributes are reported.
+    pub fn parse<'a, const N: usize>(
+        policy: &[BlobAttrPolicy; N],
+        data: &'a [u8],
+    ) -> Result<[Option<Blob<'a>>; N], UbusError> {
+        let mut slots = [None; N];
+        for blob in BlobIter::<Blob>::new(data) {
+            let blob = blob?;
+            let id = blob.tag.id();
+            let Some(index) = policy.iter().position(|p| p.id == id) else {
+                continue;
+            };
+            policy[index]
+                .ty
+                .check(blob.data)
+                .map_err(|source| UbusError::InvalidAttribute {
+                    id,
+                    source: Box::new(source),
+                })?;
+            slots[index] = Some(blob);
+        }
+        for (p, slot) in policy.iter().zip(slots.iter()) {
+            if p.required && slot.is_none() {
+                return Err(UbusError::MissingAttribute(p.id.to_string()));
+            }
+        }
+        Ok(slots)
+    }
+}
+
+/// A Rust type stored as the payload of a raw blob attribute
+pub trait BlobAttrData<'a>: Sized {
+    const TYPE: BlobAttrType;
+    fn decode(data: &'a [u8]) -> Result<Self, UbusError>;
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError>;
+}
+
+macro_rules! blob_attr_number {
+    ( $( $ty:ty => $attr:ident , )* ) => { $(
+        impl BlobAttrData<'_> for $ty {
+            const TYPE: BlobAttrType = BlobAttrType::$attr;
+            fn decode(data: &[u8]) -> Result<Self, UbusError> {
+                BlobAttrType::$attr.check(data)?;
+                match data.first_chunk() {
+                    Some(bytes) => Ok(<$ty>::from_be_bytes(*bytes)),
+                    None => Err(UbusError::PayloadTooShort {
+                        needed: size_of::<$ty>(),
+                        found: data.len(),
+                    }),
+                }
+            }
+            fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+                builder.push_bytes(id, &self.to_be_bytes())
+            }
+        }
+    )* };
+}
+blob_attr_number!(
+    u8 => INT8, i8 => INT8, u16 => INT16, i16 => INT16, u32 => INT32, i32 => INT32,
+    u64 => INT64, i64 => INT64, f64 => DOUBLE,
+);
+
+impl BlobAttrData<'_> for bool {
+    const TYPE: BlobAttrType = BlobAttrType::INT8;
+    fn decode(data: &[u8]) -> Result<Self, UbusError> {
+        <u8 as BlobAttrData>::decode(data).map(|b| b != 0)
+    }
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        builder.push_bool(id, *self)
+    }
+}
+
+impl BlobAttrData<'_> for UbusStatus {
+    const TYPE: BlobAttrType = BlobAttrType::INT32;
+    fn decode(data: &[u8]) -> Result<Self, UbusError> {
+        <i32 as BlobAttrData>::decode(data).map(UbusStatus::from)
+    }
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        BlobAttrData::encode(&self.value(), builder, id)
+    }
+}
+
+impl<'a> BlobAttrData<'a> for &'a str {
+    const TYPE: BlobAttrType = BlobAttrType::STRING;
+    fn decode(data: &'a [u8]) -> Result<Self, UbusError> {
+        BlobAttrType::STRING.check(data)?;
+        Ok(str::from_utf8(&data[..data.len() - 1])?)
+    }
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        builder.push_str(id, self)
+    }
+}
+
+impl<'a> BlobAttrData<'a> for &'a [u8] {
+    const TYPE: BlobAttrType = BlobAttrType::BINARY;
+    fn decode(data: &'a [u8]) -> Result<Self, UbusError> {
+        Ok(data)
+    }
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        builder.push_bytes(id, *self)
+    }
+}
+
+/// Nested attributes left for the caller to iterate
+impl<'a> BlobAttrData<'a> for BlobIter<'a, Blob<'a>> {
+    const TYPE: BlobAttrType = BlobAttrType::NESTED;
+    fn decode(data: &'a [u8]) -> Result<Self, UbusError> {
+        Ok(BlobIter::new(data))
+    }
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        builder.push_bytes(id, self.data)
+    }
+}
+
+/// A nested attribute holding the fields of another schema
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Nested<T>(pub T);
+
+impl<'a, T: BlobSchema<'a>> BlobAttrData<'a> for Nested<T> {
+    const TYPE: BlobAttrType = BlobAttrType::NESTED;
+    fn decode(data: &'a [u8]) -> Result<Self, UbusError> {
+        <T as BlobSchema>::decode(data).map(Nested)
+    }
+    fn encode(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        builder.open(id)?;
+        BlobSchema::encode(&self.0, builder)?;
+        builder.close()
+    }
+}
+
+/// A field of a [`blob_schema!`] struct, `Option` marks it as not required
+pub trait BlobField<'a>: Sized {
+    const TYPE: BlobAttrType;
+    fn from_slot(slot: Option<Blob<'a>>, name: &str) -> Result<Self, UbusError>;
+    fn encode_field(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError>;
+}
+
+impl<'a, T: BlobAttrData<'a>> BlobField<'a> for T {
+    const TYPE: BlobAttrType = <T as BlobAttrData<'a>>::TYPE;
+    fn from_slot(slot: Option<Blob<'a>>, name: &str) -> Result<Self, UbusError> {
+        let blob = slot.ok_or_else(|| UbusError::MissingAttribute(name.to_string()))?;
+        <T as BlobAttrData>::decode(blob.data).map_err(|source| UbusError::InvalidAttribute {
+            id: blob.tag.id(),
+            source: Box::new(source),
+        })
+    }
+    fn encode_field(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        BlobAttrData::encode(self, builder, id)
+    }
+}
+
+impl<'a, T: BlobAttrData<'a>> BlobField<'a> for Option<T> {
+    const TYPE: BlobAttrType = <T as BlobAttrData<'a>>::TYPE;
+    fn from_slot(slot: Option<Blob<'a>>, name: &str) -> Result<Self, UbusError> {
+        slot.map(|blob| <T as BlobField>::from_slot(Some(blob), name))
+            .transpose()
+    }
+    fn encode_field(&self, builder: &mut BlobBuilder, id: u32) -> Result<(), UbusError> {
+        match self {
+            Some(value) => BlobAttrData::encode(value, builder, id),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Id of a field in a [`blob_schema!`] struct
+pub trait BlobFieldId {
+    fn id(self) -> u32;
+}
+
+impl BlobFieldId for u32 {
+    fn id(self) -> u32 {
+        self
+    }
+}
+
+/// A struct decoded from and encoded to a list of raw blob attributes, usually
+/// implemented with [`blob_schema!`]
+pub trait BlobSchema<'a>: Sized {
+    fn decode(data: &'a [u8]) -> Result<Self, UbusError>;
+    fn encode(&self, builder: &mut BlobBuilder) -> Result<(), UbusError>;
+}
+
+/// Define a struct mapping raw blob attribute ids to fields
+///
+/// ```
+/// ubus::blob_schema! {
+///     #[derive(Debug)]
+///     pub struct Instance<'a> {
+///         1 => pub name: &'a str,
+///         2 => pub pid: Option<u32>,
+///     }
+/// }
+/// ```
+///
+/// Ids are a `u32` or a [`BlobFieldId`] such as [`crate::BlobAttrId`]. Fields which are not
+/// an `Option` are required. The field types pick the expected
+/// [`BlobAttrType`] through [`BlobAttrData`], a [`Nested`] schema decodes a nested
+/// attribute into another struct.
+#[macro_export]
+macro_rules! blob_schema {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident <$lt:lifetime> {
+            $( $id:expr => $fvis:vis $field:ident : $ty:ty ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name<$lt> {
+            $( $fvis $field: $ty, )*
+        }
+        $crate::blob_schema!(@impl $lt, $name<$lt>, $( $id => $field : $ty ),*);
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $( $id:expr => $fvis:vis $field:ident : $ty:ty ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $( $fvis $field: $ty, )*
+        }
+        $crate::blob_schema!(@impl 'blob, $name, $( $id => $field : $ty ),*);
+    };
+    (@impl $lt:lifetime, $self:ty, $( $id:expr => $field:ident : $ty:ty ),*) => {
+        impl<$lt> $crate::BlobSchema<$lt> for $self {
+            fn decode(data: &$lt [u8]) -> Result<Self, $crate::UbusError> {
+                let policy = [$(
+                    $crate::BlobAttrPolicy::new($crate::BlobFieldId::id($id), <$ty as $crate::BlobField<$lt>>::TYPE),
+                )*];
+                let [$( $field ),*] = $crate::BlobAttrPolicy::parse(&policy, data)?;
+                Ok(Self {
+                    $( $field: $crate::BlobField::from_slot($field, stringify!($field))?, )*
+                })
+            }
+
+            fn encode(&self, builder: &mut $crate::BlobBuilder) -> Result<(), $crate::UbusError> {
+                $( $crate::BlobField::encode_field(&self.$field, builder, $crate::BlobFieldId::id($id))?; )*
+                Ok(())
+            }
+        }
+    };
+}
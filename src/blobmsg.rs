@@ -1,8 +1,14 @@
-use std::collections::HashMap;
+use crate::{Blob, BlobIter, BlobMsgBuilder, JsonStyle, JsonWriter, UbusError};
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use core::ops::Index;
 use std::fmt;
+use std::string::{String, ToString};
 use std::vec::Vec;
 
-use serde::{Deserialize, Serialize};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 values!(pub BlobMsgType(u32) {
     UNSPEC = 0,
@@ -13,80 +19,146 @@ values!(pub BlobMsgType(u32) {
     INT32  = 5,
     INT16  = 6,
     BOOL   = 7,
-    INT8   = 7,
     DOUBLE = 8,
 });
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl BlobMsgType {
+    /// libubox defines `BLOBMSG_TYPE_INT8` as an alias of `BLOBMSG_TYPE_BOOL`, both are
+    /// encoded as type 7 on the wire and `blobmsg_json` always renders them as booleans.
+    pub const INT8: Self = Self::BOOL;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BlobMsgPayload<'a> {
     Array(Vec<BlobMsg<'a>>),
-    Table(HashMap<&'a str, BlobMsgPayload<'a>>),
+    Table(BlobMsgTable<'a>),
     String(&'a str),
+    /// A `STRING` which is not valid UTF-8, without its nul terminator
+    Bytes(&'a [u8]),
     Int64(i64),
     Int32(i32),
     Int16(i16),
+    /// Encoded as type 7 like `Bool`, which is what decoding always gives. The raw byte
+    /// is available from [`crate::BlobMsgView::as_i8`].
     Int8(i8),
-    Bool(i8),
+    Bool(bool),
     Double(f64),
     Unknown(u32, &'a [u8]),
 }
 
 impl fmt::Display for BlobMsgPayload<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            BlobMsgPayload::Array(list) => write!(f, "{}", List(list)),
-            BlobMsgPayload::Table(dict) => write!(f, "{}", Dict(dict)),
-            BlobMsgPayload::String(s) => write!(f, "\"{}\"", s),
-            BlobMsgPayload::Int64(num) => write!(f, "{}", num),
-            BlobMsgPayload::Int32(num) => write!(f, "{}", num),
-            BlobMsgPayload::Int16(num) => write!(f, "{}", num),
-            BlobMsgPayload::Int8(num) => write!(f, "{}", num),
-            BlobMsgPayload::Bool(num) => write!(f, "{}", *num == 1),
-            BlobMsgPayload::Double(num) => write!(f, "{}", num),
-            BlobMsgPayload::Unknown(typeid, bytes) => {
-                write!(f, "\"type={} data={:?}\"", typeid, bytes)
-            }
-        }
+        JsonWriter::new(f, JsonStyle::Compact).write_payload(self)
     }
 }
 
-struct List<'a>(&'a Vec<BlobMsg<'a>>);
-impl<'a> fmt::Display for List<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        let mut first = true;
-        for msg in self.0 {
-            if !first {
-                write!(f, ", ")?;
-            } else {
-                first = false;
-            }
-            write!(f, "{}", msg.data)?;
+/// Named blobmsg values of a table, kept in wire order
+///
+/// Some services emit the same key more than once, so keys are not unique. Lookups
+/// return the last value for a key, like `blobmsg_parse()` does.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BlobMsgTable<'a>(Vec<(&'a str, BlobMsgPayload<'a>)>);
+
+impl<'a> BlobMsgTable<'a> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Append a value, keeping any existing value with the same key
+    pub fn push(&mut self, key: &'a str, value: BlobMsgPayload<'a>) {
+        self.0.push((key, value));
+    }
+
+    pub fn get(&self, key: &str) -> Option<&BlobMsgPayload<'a>> {
+        self.0.iter().rev().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// All values stored under `key`, in wire order
+    pub fn get_all<'b>(&'b self, key: &'b str) -> impl Iterator<Item = &'b BlobMsgPayload<'a>> {
+        self.iter().filter(move |(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.iter().any(|(k, _)| k == key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &BlobMsgPayload<'a>)> {
+        self.0.iter().map(|(k, v)| (*k, v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn from_vec(entries: Vec<(&'a str, BlobMsgPayload<'a>)>) -> Self {
+        Self(entries)
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<(&'a str, BlobMsgPayload<'a>)> {
+        self.0
+    }
+}
+
+impl<'a> Index<&str> for BlobMsgTable<'a> {
+    type Output = BlobMsgPayload<'a>;
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).expect("key not found in BlobMsgTable")
+    }
+}
+
+impl<'a> FromIterator<(&'a str, BlobMsgPayload<'a>)> for BlobMsgTable<'a> {
+    fn from_iter<T: IntoIterator<Item = (&'a str, BlobMsgPayload<'a>)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for BlobMsgTable<'a> {
+    type Item = (&'a str, BlobMsgPayload<'a>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Serialize for BlobMsgTable<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self.iter() {
+            map.serialize_entry(k, v)?;
         }
-        write!(f, "]")?;
-        Ok(())
+        map.end()
     }
 }
 
-struct Dict<'a>(&'a HashMap<&'a str, BlobMsgPayload<'a>>);
-impl<'a> fmt::Display for Dict<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{{")?;
-        let mut first = true;
-        for (k, v) in self.0 {
-            if first {
-                first = false;
-            } else {
-                write!(f, ", ")?;
+impl<'de: 'a, 'a> Deserialize<'de> for BlobMsgTable<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TableVisitor<'a>(PhantomData<BlobMsgTable<'a>>);
+        impl<'de: 'a, 'a> Visitor<'de> for TableVisitor<'a> {
+            type Value = BlobMsgTable<'a>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a blobmsg table")
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut table = BlobMsgTable::new();
+                while let Some((k, v)) = access.next_entry()? {
+                    table.push(k, v);
+                }
+                Ok(table)
             }
-            write!(f, "\"{}\": {}", k, v)?;
         }
-        write!(f, "}}")?;
-        Ok(())
+        deserializer.deserialize_map(TableVisitor(PhantomData))
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BlobMsg<'a> {
     pub name: &'a str,
     pub data: BlobMsgPayload<'a>,
@@ -94,10 +166,418 @@ pub struct BlobMsg<'a> {
 
 impl fmt::Display for BlobMsg<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.name.len() > 0 {
-            write!(f, "\"{}\": {}", self.name, self.data)
+        let mut json = JsonWriter::new(f, JsonStyle::Compact);
+        if self.name.is_empty() {
+            json.write_payload(&self.data)
         } else {
-            write!(f, "{}", self.data)
+            json.write_entry(self.name, &self.data)
         }
     }
 }
+
+/// Owned counterpart of [`BlobMsgPayload`], for values which need to outlive the buffer
+/// they were decoded from
+///
+/// Array items are stored without their names, which blobmsg leaves empty for arrays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BlobMsgValue {
+    Array(Vec<BlobMsgValue>),
+    Table(Vec<(String, BlobMsgValue)>),
+    String(String),
+    /// A `STRING` which is not valid UTF-8, without its nul terminator
+    Bytes(Vec<u8>),
+    Int64(i64),
+    Int32(i32),
+    Int16(i16),
+    Int8(i8),
+    Bool(bool),
+    Double(f64),
+    Unknown(u32, Vec<u8>),
+}
+
+impl BlobMsgValue {
+    /// Look up a table entry, the last one wins for duplicate keys
+    pub fn get(&self, key: &str) -> Option<&BlobMsgValue> {
+        match self {
+            BlobMsgValue::Table(table) => {
+                table.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    /// Borrow this value as a [`BlobMsgPayload`], e.g. to encode it
+    pub fn as_payload(&self) -> BlobMsgPayload<'_> {
+        match self {
+            BlobMsgValue::Array(list) => BlobMsgPayload::Array(
+                list.iter()
+                    .map(|item| BlobMsg {
+                        name: "",
+                        data: item.as_payload(),
+                    })
+                    .collect(),
+            ),
+            BlobMsgValue::Table(table) => BlobMsgPayload::Table(
+                table
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_payload()))
+                    .collect(),
+            ),
+            BlobMsgValue::String(s) => BlobMsgPayload::String(s),
+            BlobMsgValue::Bytes(bytes) => BlobMsgPayload::Bytes(bytes),
+            BlobMsgValue::Int64(num) => BlobMsgPayload::Int64(*num),
+            BlobMsgValue::Int32(num) => BlobMsgPayload::Int32(*num),
+            BlobMsgValue::Int16(num) => BlobMsgPayload::Int16(*num),
+            BlobMsgValue::Int8(num) => BlobMsgPayload::Int8(*num),
+            BlobMsgValue::Bool(b) => BlobMsgPayload::Bool(*b),
+            BlobMsgValue::Double(num) => BlobMsgPayload::Double(*num),
+            BlobMsgValue::Unknown(typeid, bytes) => BlobMsgPayload::Unknown(*typeid, bytes),
+        }
+    }
+
+    /// An `UNSPEC` attribute without data, written as `null` in JSON
+    pub fn null() -> Self {
+        BlobMsgValue::Unknown(BlobMsgType::UNSPEC.value(), Vec::new())
+    }
+
+    /// Encode the entries of a table without header (e.g. the arguments of a call)
+    pub fn to_bytes(&self) -> Result<Vec<u8>, UbusError> {
+        let BlobMsgValue::Table(table) = self else {
+            return Err(UbusError::InvalidData("Only a table encodes as attributes"));
+        };
+        let mut builder = BlobMsgBuilder::new();
+        for (name, value) in table {
+            builder.add(&BlobMsg {
+                name,
+                data: value.as_payload(),
+            })?;
+        }
+        Ok(builder.data().to_vec())
+    }
+}
+
+macro_rules! value_from {
+    ($( $ty:ty => $variant:ident ),* $(,)?) => {
+        $(
+            impl From<$ty> for BlobMsgValue {
+                fn from(value: $ty) -> Self {
+                    BlobMsgValue::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+value_from! {
+    i64 => Int64,
+    i32 => Int32,
+    i16 => Int16,
+    // INT8 shares type 7 with BOOL and would read back as a boolean
+    i8 => Int16,
+    bool => Bool,
+    f64 => Double,
+    f32 => Double,
+    &str => String,
+    String => String,
+}
+
+impl<T: Into<BlobMsgValue>> From<Vec<T>> for BlobMsgValue {
+    fn from(list: Vec<T>) -> Self {
+        BlobMsgValue::Array(list.into_iter().map(Into::into).collect())
+    }
+}
+
+/// `None` becomes [`BlobMsgValue::null`]
+impl<T: Into<BlobMsgValue>> From<Option<T>> for BlobMsgValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or_else(BlobMsgValue::null, Into::into)
+    }
+}
+
+impl fmt::Display for BlobMsgValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_payload())
+    }
+}
+
+impl BlobMsgPayload<'_> {
+    /// Wire type of this payload
+    pub fn ty(&self) -> BlobMsgType {
+        match self {
+            BlobMsgPayload::Array(_) => BlobMsgType::ARRAY,
+            BlobMsgPayload::Table(_) => BlobMsgType::TABLE,
+            BlobMsgPayload::String(_) | BlobMsgPayload::Bytes(_) => BlobMsgType::STRING,
+            BlobMsgPayload::Int64(_) => BlobMsgType::INT64,
+            BlobMsgPayload::Int32(_) => BlobMsgType::INT32,
+            BlobMsgPayload::Int16(_) => BlobMsgType::INT16,
+            BlobMsgPayload::Int8(_) => BlobMsgType::INT8,
+            BlobMsgPayload::Bool(_) => BlobMsgType::BOOL,
+            BlobMsgPayload::Double(_) => BlobMsgType::DOUBLE,
+            BlobMsgPayload::Unknown(id, _) => BlobMsgType::from(*id),
+        }
+    }
+
+    /// Bytes of a `STRING`, whether or not it is valid UTF-8
+    pub fn as_string_bytes(&self) -> Option<&[u8]> {
+        match self {
+            BlobMsgPayload::String(s) => Some(s.as_bytes()),
+            BlobMsgPayload::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Copy this payload out of the receive buffer
+    pub fn to_owned_value(&self) -> BlobMsgValue {
+        match self {
+            BlobMsgPayload::Array(list) => {
+                BlobMsgValue::Array(list.iter().map(|item| item.data.to_owned_value()).collect())
+            }
+            BlobMsgPayload::Table(table) => BlobMsgValue::Table(
+                table
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_owned_value()))
+                    .collect(),
+            ),
+            BlobMsgPayload::String(s) => BlobMsgValue::String(s.to_string()),
+            BlobMsgPayload::Bytes(bytes) => BlobMsgValue::Bytes(bytes.to_vec()),
+            BlobMsgPayload::Int64(num) => BlobMsgValue::Int64(*num),
+            BlobMsgPayload::Int32(num) => BlobMsgValue::Int32(*num),
+            BlobMsgPayload::Int16(num) => BlobMsgValue::Int16(*num),
+            BlobMsgPayload::Int8(num) => BlobMsgValue::Int8(*num),
+            BlobMsgPayload::Bool(b) => BlobMsgValue::Bool(*b),
+            BlobMsgPayload::Double(num) => BlobMsgValue::Double(*num),
+            BlobMsgPayload::Unknown(typeid, bytes) => {
+                BlobMsgValue::Unknown(*typeid, bytes.to_vec())
+            }
+        }
+    }
+}
+
+/// Owned counterpart of [`BlobMsg`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedBlobMsg {
+    pub name: String,
+    pub data: BlobMsgValue,
+}
+
+impl OwnedBlobMsg {
+    pub fn as_blobmsg(&self) -> BlobMsg<'_> {
+        BlobMsg {
+            name: &self.name,
+            data: self.data.as_payload(),
+        }
+    }
+}
+
+impl fmt::Display for OwnedBlobMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_blobmsg())
+    }
+}
+
+impl BlobMsg<'_> {
+    /// Copy this message out of the receive buffer
+    pub fn to_owned_value(&self) -> OwnedBlobMsg {
+        OwnedBlobMsg {
+            name: self.name.to_string(),
+            data: self.data.to_owned_value(),
+        }
+    }
+}
+
+/// Collect the attributes of a blobmsg table without header (e.g. the `DATA` of a reply)
+/// into an owned table
+impl<'a> TryFrom<BlobIter<'a, Blob<'a>>> for BlobMsgValue {
+    type Error = UbusError;
+    fn try_from(iter: BlobIter<'a, Blob<'a>>) -> Result<Self, Self::Error> {
+        let mut table = Vec::new();
+        for blob in iter {
+            let msg: BlobMsg = blob?.try_into()?;
+            table.push((msg.name.to_string(), msg.data.to_owned_value()));
+        }
+        Ok(BlobMsgValue::Table(table))
+    }
+}
+
+/// A literal in [`blobmsg!`], integers without suffix are picked up by [`BlobMsgInteger`]
+#[doc(hidden)]
+pub struct BlobMsgLiteral<T>(pub T);
+
+#[doc(hidden)]
+pub trait BlobMsgInteger {
+    fn into_value(self) -> BlobMsgValue;
+}
+
+/// Only an integer without suffix can become `i128`, there is no `From<i128>`
+impl BlobMsgInteger for &&BlobMsgLiteral<i128> {
+    fn into_value(self) -> BlobMsgValue {
+        let num = self.0;
+        match i32::try_from(num) {
+            Ok(num) => BlobMsgValue::Int32(num),
+            // `blobmsg_literal_fits` already rejected anything larger at compile time
+            Err(_) => BlobMsgValue::Int64(num as i64),
+        }
+    }
+}
+
+/// Whether the source text of a literal in [`blobmsg!`] is anything but an integer
+/// without suffix that does not fit `INT64`
+#[doc(hidden)]
+pub const fn blobmsg_literal_fits(text: &str) -> bool {
+    let text = text.as_bytes();
+    let mut i = 0;
+    let negative = !text.is_empty() && text[0] == b'-';
+    if negative {
+        i += 1;
+        while i < text.len() && text[i] == b' ' {
+            i += 1;
+        }
+    }
+    if i == text.len() || !text[i].is_ascii_digit() {
+        return true;
+    }
+    let radix: u128 = match (text[i], if i + 1 < text.len() { text[i + 1] } else { 0 }) {
+        (b'0', b'x') => 16,
+        (b'0', b'o') => 8,
+        (b'0', b'b') => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        i += 2;
+    }
+    let mut num: u128 = 0;
+    while i < text.len() {
+        let digit = match text[i] {
+            b'_' => {
+                i += 1;
+                continue;
+            }
+            c @ b'0'..=b'9' => (c - b'0') as u128,
+            c @ b'a'..=b'f' if radix == 16 => (c - b'a' + 10) as u128,
+            c @ b'A'..=b'F' if radix == 16 => (c - b'A' + 10) as u128,
+            // A float or a suffix, which the compiler checks
+            _ => return true,
+        };
+        if digit >= radix {
+            return true;
+        }
+        num = match num.checked_mul(radix) {
+            Some(num) => match num.checked_add(digit) {
+                Some(num) => num,
+                None => return false,
+            },
+            None => return false,
+        };
+        i += 1;
+    }
+    let max = i64::MAX as u128;
+    num <= if negative { max + 1 } else { max }
+}
+
+#[doc(hidden)]
+pub trait BlobMsgOther {
+    fn into_value(self) -> BlobMsgValue;
+}
+
+impl<T: Copy + Into<BlobMsgValue>> BlobMsgOther for &BlobMsgLiteral<T> {
+    fn into_value(self) -> BlobMsgValue {
+        self.0.into()
+    }
+}
+
+/// Build a [`BlobMsgValue`] from JSON-like syntax, like `serde_json::json!`
+///
+/// Integer literals without suffix are `INT32` when they fit and `INT64` otherwise, `null`
+/// is `UNSPEC` and anything else goes through `BlobMsgValue::from`. Keys are any expression
+/// converting into a `String`, a table encodes as call arguments with
+/// [`BlobMsgValue::to_bytes`].
+///
+/// ```
+/// let args = ubus::blobmsg!({ "name": "eth0", "vlans": [1, 2], "opts": { "mtu": 1500 } });
+/// assert_eq!(args.to_string(), r#"{"name": "eth0", "vlans": [1, 2], "opts": {"mtu": 1500}}"#);
+///
+/// let key = "tx";
+/// assert_eq!(ubus::blobmsg!({ key: 5000000000 }).to_string(), r#"{"tx": 5000000000}"#);
+/// ```
+///
+/// An integer literal which does not fit `INT64` fails to compile:
+///
+/// ```compile_fail
+/// ubus::blobmsg!([18446744073709551615]);
+/// ```
+#[macro_export]
+macro_rules! blobmsg {
+    (@array [$($done:expr,)*]) => {
+        $crate::BlobMsgValue::Array(
+            ::core::iter::IntoIterator::into_iter([$($done),*]).collect(),
+        )
+    };
+    (@array [$($done:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@array [$($done,)* $crate::blobmsg!(null),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] [ $($item:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@array [$($done,)* $crate::blobmsg!([$($item)*]),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] { $($item:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@array [$($done,)* $crate::blobmsg!({$($item)*}),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] $item:literal $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@array [$($done,)* $crate::blobmsg!($item),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] $item:expr $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@array [$($done,)* $crate::blobmsg!($item),] $($($rest)*)?)
+    };
+
+    (@object [$($done:expr,)*]) => {
+        $crate::BlobMsgValue::Table(
+            ::core::iter::IntoIterator::into_iter([$($done),*]).collect(),
+        )
+    };
+    (@object [$($done:expr,)*] $($rest:tt)+) => {
+        $crate::blobmsg!(@key [$($done,)*] () $($rest)+)
+    };
+    // Collect the tokens of a key up to its `:`
+    (@key [$($done:expr,)*] ($($key:tt)+) : null $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@object [$($done,)* $crate::blobmsg!(@entry ($($key)+), null),] $($($rest)*)?)
+    };
+    (@key [$($done:expr,)*] ($($key:tt)+) : [ $($value:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@object [$($done,)* $crate::blobmsg!(@entry ($($key)+), [$($value)*]),] $($($rest)*)?)
+    };
+    (@key [$($done:expr,)*] ($($key:tt)+) : { $($value:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@object [$($done,)* $crate::blobmsg!(@entry ($($key)+), {$($value)*}),] $($($rest)*)?)
+    };
+    (@key [$($done:expr,)*] ($($key:tt)+) : $value:literal $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@object [$($done,)* $crate::blobmsg!(@entry ($($key)+), $value),] $($($rest)*)?)
+    };
+    (@key [$($done:expr,)*] ($($key:tt)+) : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::blobmsg!(@object [$($done,)* $crate::blobmsg!(@entry ($($key)+), $value),] $($($rest)*)?)
+    };
+    (@key [$($done:expr,)*] ($($key:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::blobmsg!(@key [$($done,)*] ($($key)* $next) $($rest)*)
+    };
+    (@entry ($($key:tt)+), $($value:tt)+) => {
+        (::core::convert::Into::into($($key)+), $crate::blobmsg!($($value)+))
+    };
+
+    (null) => {
+        $crate::BlobMsgValue::null()
+    };
+    ([ $($items:tt)* ]) => {
+        $crate::blobmsg!(@array [] $($items)*)
+    };
+    ({ $($entries:tt)* }) => {
+        $crate::blobmsg!(@object [] $($entries)*)
+    };
+    ($value:literal) => {{
+        const _: () = ::core::assert!(
+            $crate::blobmsg_literal_fits(::core::stringify!($value)),
+            "integer literal does not fit INT64"
+        );
+        #[allow(unused_imports)]
+        use $crate::{BlobMsgInteger as _, BlobMsgOther as _};
+        (&&$crate::BlobMsgLiteral($value)).into_value()
+    }};
+    ($value:expr) => {
+        $crate::BlobMsgValue::from($value)
+    };
+}
@@ -0,0 +1,487 @@
+use crate::{Blob, BlobIter, BlobMsgBuilder, BlobMsgPayload, BlobMsgType, BlobMsgView};
+use crate::{DecodeLimits, UbusError};
+use core::str;
+use std::io;
+use std::vec::Vec;
+
+/// Binary formats [`BinaryWriter`] writes and [`BlobMsgBuilder::add_binary`] reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryFormat {
+    MsgPack,
+    /// CBOR as of RFC 8949
+    Cbor,
+}
+
+/// Kinds of length prefixed items
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    Bytes,
+    Array,
+    Map,
+}
+
+/// Streams blobmsg data as MessagePack or CBOR into an [`io::Write`]
+///
+/// Integers keep their blobmsg width: an `INT16` becomes an int 16 in MessagePack and an
+/// integer with a two byte argument in CBOR. Doubles stay 64 bit, strings which are not
+/// valid UTF-8 become binary strings and unknown types become nil. Table entries keep
+/// their wire order, duplicates included.
+pub struct BinaryWriter<W> {
+    out: W,
+    format: BinaryFormat,
+}
+
+impl<W: io::Write> BinaryWriter<W> {
+    pub fn new(out: W, format: BinaryFormat) -> Self {
+        Self { out, format }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Write attributes without header (e.g. the `DATA` of a reply) as a map, straight
+    /// from the wire without decoding them into memory
+    pub fn write_table(&mut self, iter: BlobIter<Blob>) -> Result<(), UbusError> {
+        self.write_view(BlobMsgView::from(iter))
+    }
+
+    /// Write a value straight from the wire
+    pub fn write_view(&mut self, view: BlobMsgView) -> Result<(), UbusError> {
+        self.view(view, 0)
+    }
+
+    fn view(&mut self, view: BlobMsgView, depth: usize) -> Result<(), UbusError> {
+        if !view.is_table() && !view.is_array() {
+            return self.write_payload(&view.decode()?);
+        }
+        let max = DecodeLimits::DEFAULT.max_depth;
+        if depth >= max {
+            return Err(UbusError::NestingTooDeep(max));
+        }
+        // Both formats put the number of children in front
+        let mut len = 0;
+        for child in view.iter() {
+            child?;
+            len += 1;
+        }
+        let kind = if view.is_table() {
+            Kind::Map
+        } else {
+            Kind::Array
+        };
+        self.head(kind, len)?;
+        for child in view.iter() {
+            let child = child?;
+            if view.is_table() {
+                self.head(Kind::Text, child.name().len())?;
+                self.put(child.name().as_bytes())?;
+            }
+            self.view(child, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Write a decoded value, the names of array items are dropped
+    pub fn write_payload(&mut self, payload: &BlobMsgPayload) -> Result<(), UbusError> {
+        match payload {
+            BlobMsgPayload::Array(list) => {
+                self.head(Kind::Array, list.len())?;
+                for item in list {
+                    self.write_payload(&item.data)?;
+                }
+                Ok(())
+            }
+            BlobMsgPayload::Table(table) => {
+                self.head(Kind::Map, table.len())?;
+                for (name, value) in table.iter() {
+                    self.head(Kind::Text, name.len())?;
+                    self.put(name.as_bytes())?;
+                    self.write_payload(value)?;
+                }
+                Ok(())
+            }
+            BlobMsgPayload::String(s) => {
+                self.head(Kind::Text, s.len())?;
+                self.put(s.as_bytes())
+            }
+            BlobMsgPayload::Bytes(bytes) => {
+                self.head(Kind::Bytes, bytes.len())?;
+                self.put(bytes)
+            }
+            BlobMsgPayload::Int64(num) => self.int(*num, 8),
+            BlobMsgPayload::Int32(num) => self.int((*num).into(), 4),
+            BlobMsgPayload::Int16(num) => self.int((*num).into(), 2),
+            BlobMsgPayload::Int8(num) => self.int((*num).into(), 1),
+            BlobMsgPayload::Bool(b) => match self.format {
+                BinaryFormat::MsgPack => self.put(&[0xc2 | *b as u8]),
+                BinaryFormat::Cbor => self.put(&[0xf4 | *b as u8]),
+            },
+            BlobMsgPayload::Double(num) => {
+                let marker = match self.format {
+                    BinaryFormat::MsgPack => 0xcb,
+                    BinaryFormat::Cbor => 0xfb,
+                };
+                self.put(&[marker])?;
+                self.put(&num.to_be_bytes())
+            }
+            BlobMsgPayload::Unknown(_, _) => match self.format {
+                BinaryFormat::MsgPack => self.put(&[0xc0]),
+                BinaryFormat::Cbor => self.put(&[0xf6]),
+            },
+        }
+    }
+
+    fn put(&mut self, data: &[u8]) -> Result<(), UbusError> {
+        Ok(self.out.write_all(data)?)
+    }
+
+    /// An integer of `width` bytes
+    fn int(&mut self, num: i64, width: usize) -> Result<(), UbusError> {
+        match self.format {
+            BinaryFormat::MsgPack => {
+                let marker = 0xd0 + width.trailing_zeros() as u8;
+                self.put(&[marker])?;
+                self.put(&num.to_be_bytes()[8 - width..])
+            }
+            BinaryFormat::Cbor if num < 0 => self.cbor_head(1, !num as u64, width),
+            BinaryFormat::Cbor => self.cbor_head(0, num as u64, width),
+        }
+    }
+
+    /// The type and length in front of a string, array or map
+    fn head(&mut self, kind: Kind, len: usize) -> Result<(), UbusError> {
+        let (fix, markers) = match (self.format, kind) {
+            (BinaryFormat::Cbor, kind) => {
+                let major = match kind {
+                    Kind::Bytes => 2,
+                    Kind::Text => 3,
+                    Kind::Array => 4,
+                    Kind::Map => 5,
+                };
+                return self.cbor_head(major, len as u64, 0);
+            }
+            (BinaryFormat::MsgPack, Kind::Text) => ((0xa0, 32), [0xd9, 0xda, 0xdb]),
+            (BinaryFormat::MsgPack, Kind::Bytes) => ((0, 0), [0xc4, 0xc5, 0xc6]),
+            (BinaryFormat::MsgPack, Kind::Array) => ((0x90, 16), [0, 0xdc, 0xdd]),
+            (BinaryFormat::MsgPack, Kind::Map) => ((0x80, 16), [0, 0xde, 0xdf]),
+        };
+        let (marker, width) = match len {
+            _ if len < fix.1 => return self.put(&[fix.0 | len as u8]),
+            ..=0xff if markers[0] != 0 => (markers[0], 1),
+            ..=0xffff => (markers[1], 2),
+            0x1_0000..=0xffff_ffff => (markers[2], 4),
+            _ => return Err(UbusError::InvalidData("Too long for MessagePack")),
+        };
+        self.put(&[marker])?;
+        self.put(&(len as u64).to_be_bytes()[8 - width..])
+    }
+
+    /// A CBOR initial byte and argument, `width` 0 picks the shortest form
+    fn cbor_head(&mut self, major: u8, arg: u64, width: usize) -> Result<(), UbusError> {
+        let width = match (width, arg) {
+            (0, ..=23) => return self.put(&[major << 5 | arg as u8]),
+            (0, ..=0xff) => 1,
+            (0, ..=0xffff) => 2,
+            (0, ..=0xffff_ffff) => 4,
+            (0, _) => 8,
+            (width, _) => width,
+        };
+        self.put(&[major << 5 | (24 + width.trailing_zeros() as u8)])?;
+        self.put(&arg.to_be_bytes()[8 - width..])
+    }
+}
+
+impl BlobMsgPayload<'_> {
+    /// Encode this payload as MessagePack or CBOR
+    pub fn to_binary(&self, format: BinaryFormat) -> Result<Vec<u8>, UbusError> {
+        let mut writer = BinaryWriter::new(Vec::new(), format);
+        writer.write_payload(self)?;
+        Ok(writer.into_inner())
+    }
+}
+
+/// A decoded MessagePack or CBOR item, containers only with their length
+enum Item<'a> {
+    Null,
+    Bool(bool),
+    /// Value and the width in bytes it was encoded with, 0 for the single byte forms
+    Int(i128, usize),
+    Double(f64),
+    Text(&'a [u8]),
+    Bytes(&'a [u8]),
+    /// `None` for indefinite length CBOR
+    Array(Option<usize>),
+    Map(Option<usize>),
+    /// End of an indefinite length CBOR array or map
+    Break,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: BinaryFormat,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: u64) -> Result<&'a [u8], UbusError> {
+        let data = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.data.get(self.pos..self.pos.checked_add(len)?))
+            .ok_or(UbusError::InvalidData("Truncated binary data"))?;
+        self.pos += data.len();
+        Ok(data)
+    }
+
+    fn uint(&mut self, width: usize) -> Result<u64, UbusError> {
+        let mut bytes = [0u8; 8];
+        bytes[8 - width..].copy_from_slice(self.take(width as u64)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn len(&mut self, width: usize) -> Result<usize, UbusError> {
+        usize::try_from(self.uint(width)?).map_err(|_| UbusError::InvalidData("Length too large"))
+    }
+
+    fn item(&mut self) -> Result<Item<'a>, UbusError> {
+        match self.format {
+            BinaryFormat::MsgPack => self.msgpack(),
+            BinaryFormat::Cbor => self.cbor(),
+        }
+    }
+
+    fn msgpack(&mut self) -> Result<Item<'a>, UbusError> {
+        let marker = self.take(1)?[0];
+        Ok(match marker {
+            0x00..=0x7f => Item::Int(marker.into(), 0),
+            0x80..=0x8f => Item::Map(Some((marker & 0x0f).into())),
+            0x90..=0x9f => Item::Array(Some((marker & 0x0f).into())),
+            0xa0..=0xbf => Item::Text(self.take((marker & 0x1f).into())?),
+            0xc0 => Item::Null,
+            0xc2 => Item::Bool(false),
+            0xc3 => Item::Bool(true),
+            0xc4..=0xc6 => {
+                let len = self.uint(1 << (marker - 0xc4))?;
+                Item::Bytes(self.take(len)?)
+            }
+            0xca => Item::Double(f32::from_bits(self.uint(4)? as u32).into()),
+            0xcb => Item::Double(f64::from_bits(self.uint(8)?)),
+            0xcc..=0xcf => {
+                let width = 1 << (marker - 0xcc);
+                Item::Int(self.uint(width)?.into(), width)
+            }
+            0xd0..=0xd3 => {
+                let width = 1 << (marker - 0xd0);
+                let shift = 64 - 8 * width;
+                let num = (self.uint(width)? << shift) as i64 >> shift;
+                Item::Int(num.into(), width)
+            }
+            0xd9..=0xdb => {
+                let len = self.uint(1 << (marker - 0xd9))?;
+                Item::Text(self.take(len)?)
+            }
+            0xdc | 0xdd => Item::Array(Some(self.len(2 << (marker - 0xdc))?)),
+            0xde | 0xdf => Item::Map(Some(self.len(2 << (marker - 0xde))?)),
+            0xe0..=0xff => Item::Int((marker as i8).into(), 0),
+            _ => return Err(UbusError::InvalidData("Unsupported MessagePack type")),
+        })
+    }
+
+    fn cbor(&mut self) -> Result<Item<'a>, UbusError> {
+        // Tags (e.g. for timestamps) only annotate the item which follows, they are skipped
+        // in a loop so that a long run of them can not exhaust the stack
+        let (major, info, arg, width) = loop {
+            let initial = self.take(1)?[0];
+            let (major, info) = (initial >> 5, initial & 0x1f);
+            let (arg, width) = match info {
+                0..=23 => (info.into(), 0),
+                24..=27 => {
+                    let width = 1 << (info - 24);
+                    (self.uint(width)?, width)
+                }
+                31 => (0, 0),
+                _ => return Err(UbusError::InvalidData("Malformed CBOR")),
+            };
+            if major != 6 || info == 31 {
+                break (major, info, arg, width);
+            }
+        };
+        let indefinite = info == 31;
+        let len = || usize::try_from(arg).map_err(|_| UbusError::InvalidData("Length too large"));
+        Ok(match (major, info) {
+            (0..=3 | 6, 31) => {
+                return Err(UbusError::InvalidData("Unsupported CBOR item"));
+            }
+            (0, _) => Item::Int(arg.into(), width),
+            (1, _) => Item::Int(-1 - i128::from(arg), width),
+            (2, _) => Item::Bytes(self.take(arg)?),
+            (3, _) => Item::Text(self.take(arg)?),
+            (4, _) => Item::Array((!indefinite).then(len).transpose()?),
+            (5, _) => Item::Map((!indefinite).then(len).transpose()?),
+            (_, 20) => Item::Bool(false),
+            (_, 21) => Item::Bool(true),
+            (_, 22 | 23) => Item::Null,
+            (_, 25) => Item::Double(half_to_f64(arg as u16)),
+            (_, 26) => Item::Double(f32::from_bits(arg as u32).into()),
+            (_, 27) => Item::Double(f64::from_bits(arg)),
+            (_, 31) => Item::Break,
+            _ => return Err(UbusError::InvalidData("Unsupported CBOR item")),
+        })
+    }
+
+    /// Add the value of the next item
+    fn value(
+        &mut self,
+        builder: &mut BlobMsgBuilder,
+        name: &str,
+        depth: usize,
+    ) -> Result<(), UbusError> {
+        let item = self.item()?;
+        self.add(builder, name, item, depth)
+    }
+
+    fn add(
+        &mut self,
+        builder: &mut BlobMsgBuilder,
+        name: &str,
+        item: Item,
+        depth: usize,
+    ) -> Result<(), UbusError> {
+        match item {
+            Item::Null => builder.add_field(BlobMsgType::UNSPEC.value(), name, &[]),
+            Item::Bool(b) => builder.add_bool(name, b),
+            Item::Int(num, width) => add_int(builder, name, num, width),
+            Item::Double(num) => builder.add_double(name, num),
+            Item::Text(s) | Item::Bytes(s) => builder.add_string_bytes(name, s),
+            Item::Array(len) => {
+                Self::enter(depth)?;
+                builder.open_array(name)?;
+                let mut count = 0;
+                while Some(count) != len {
+                    match self.item()? {
+                        Item::Break if len.is_none() => break,
+                        item => self.add(builder, "", item, depth + 1)?,
+                    }
+                    count += 1;
+                }
+                builder.close()
+            }
+            Item::Map(len) => {
+                Self::enter(depth)?;
+                builder.open_table(name)?;
+                self.entries(builder, len, depth + 1)?;
+                builder.close()
+            }
+            Item::Break => Err(UbusError::InvalidData("Unexpected CBOR break")),
+        }
+    }
+
+    /// Add the entries of a map as attributes
+    fn entries(
+        &mut self,
+        builder: &mut BlobMsgBuilder,
+        len: Option<usize>,
+        depth: usize,
+    ) -> Result<(), UbusError> {
+        let mut count = 0;
+        while Some(count) != len {
+            let name = match self.item()? {
+                Item::Break if len.is_none() => break,
+                Item::Text(name) => str::from_utf8(name)?,
+                _ => return Err(UbusError::InvalidData("Map keys must be strings")),
+            };
+            self.value(builder, name, depth)?;
+            count += 1;
+        }
+        Ok(())
+    }
+
+    fn enter(depth: usize) -> Result<(), UbusError> {
+        let max = DecodeLimits::DEFAULT.max_depth;
+        if depth >= max {
+            return Err(UbusError::NestingTooDeep(max));
+        }
+        Ok(())
+    }
+}
+
+/// The narrowest blobmsg integer which is at least as wide as the encoded one and holds
+/// the value, the single byte forms count as `INT32` like JSON numbers do
+///
+/// One byte integers become `INT16`, as `INT8` shares type 7 with `BOOL` and would read
+/// back as a boolean.
+fn add_int(
+    builder: &mut BlobMsgBuilder,
+    name: &str,
+    num: i128,
+    width: usize,
+) -> Result<(), UbusError> {
+    let width = if width == 0 { 4 } else { width };
+    if let (..=2, Ok(num)) = (width, i16::try_from(num)) {
+        return builder.add_int16(name, num);
+    }
+    if let (..=4, Ok(num)) = (width, i32::try_from(num)) {
+        return builder.add_int32(name, num);
+    }
+    let num = i64::try_from(num).map_err(|_| UbusError::InvalidData("Integer out of range"))?;
+    builder.add_int64(name, num)
+}
+
+/// IEEE 754 half precision, which CBOR encoders use for short floats
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = u64::from((bits >> 10) & 0x1f);
+    let mantissa = u64::from(bits & 0x3ff);
+    let value = match exponent {
+        0 => mantissa as f64 / (1u64 << 24) as f64,
+        0x1f => f64::from_bits((0x7ff << 52) | (mantissa << 42)),
+        _ => f64::from_bits(((exponent + 1008) << 52) | (mantissa << 42)),
+    };
+    if bits & 0x8000 != 0 { -value } else { value }
+}
+
+impl BlobMsgBuilder<'_> {
+    /// Add the next MessagePack or CBOR value in `data`, returns the number of bytes it
+    /// took up
+    ///
+    /// Integers get the blobmsg type of the width they were encoded with, widened when the
+    /// value does not fit. Binary strings become `STRING`s, nil `UNSPEC` and map keys have
+    /// to be strings.
+    pub fn add_binary(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        format: BinaryFormat,
+    ) -> Result<usize, UbusError> {
+        let mut reader = Reader {
+            data,
+            pos: 0,
+            format,
+        };
+        reader.value(self, name, 0)?;
+        Ok(reader.pos)
+    }
+
+    /// Add the entries of the MessagePack or CBOR map in `data` as attributes, e.g. to use
+    /// it as call arguments, returns the number of bytes it took up
+    pub fn add_binary_map(
+        &mut self,
+        data: &[u8],
+        format: BinaryFormat,
+    ) -> Result<usize, UbusError> {
+        let mut reader = Reader {
+            data,
+            pos: 0,
+            format,
+        };
+        let Item::Map(len) = reader.item()? else {
+            return Err(UbusError::InvalidData("Expected a map"));
+        };
+        reader.entries(self, len, 0)?;
+        Ok(reader.pos)
+    }
+}
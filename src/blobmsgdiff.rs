@@ -0,0 +1,183 @@
+use crate::{BlobMsgPath, BlobMsgPayload, BlobMsgTable, BlobMsgType, BlobMsgValue, PathStep};
+use core::fmt;
+use std::collections::BTreeMap;
+use std::string::ToString;
+use std::vec::Vec;
+
+/// One difference found by [`BlobMsgPayload::diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlobMsgChange<'b, 'a> {
+    Added {
+        path: BlobMsgPath,
+        new: &'b BlobMsgPayload<'a>,
+    },
+    Removed {
+        path: BlobMsgPath,
+        old: &'b BlobMsgPayload<'a>,
+    },
+    /// A different value or wire type, e.g. an `INT32` which became an `INT64`
+    Changed {
+        path: BlobMsgPath,
+        old: &'b BlobMsgPayload<'a>,
+        new: &'b BlobMsgPayload<'a>,
+    },
+}
+
+impl BlobMsgChange<'_, '_> {
+    pub fn path(&self) -> &BlobMsgPath {
+        match self {
+            BlobMsgChange::Added { path, .. }
+            | BlobMsgChange::Removed { path, .. }
+            | BlobMsgChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// One line like `diff -u` would show it, `+`, `-` or `~` followed by the path
+impl fmt::Display for BlobMsgChange<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobMsgChange::Added { path, new } => write!(f, "+ {}: {}", path, new),
+            BlobMsgChange::Removed { path, old } => write!(f, "- {}: {}", path, old),
+            BlobMsgChange::Changed { path, old, new } => {
+                write!(f, "~ {}: {} -> {}", path, old, new)
+            }
+        }
+    }
+}
+
+impl<'a> BlobMsgPayload<'a> {
+    /// Every path where `new` differs from `self`
+    ///
+    /// Tables are compared by key, the last one winning for duplicate keys, arrays by
+    /// index. Keys of `self` come first in their order, keys only `new` has follow in
+    /// its order. An empty result means both trees hold the same data.
+    pub fn diff<'b>(&'b self, new: &'b BlobMsgPayload<'a>) -> Vec<BlobMsgChange<'b, 'a>> {
+        let mut changes = Vec::new();
+        diff(&mut Vec::new(), self, new, &mut changes);
+        changes
+    }
+
+    /// A null in JSON, which blobmsg represents as an `UNSPEC` attribute
+    pub fn is_null(&self) -> bool {
+        matches!(self, BlobMsgPayload::Unknown(ty, _) if *ty == BlobMsgType::UNSPEC.value())
+    }
+
+    /// A copy of this tree with a JSON merge patch (RFC 7396) applied, see
+    /// [`BlobMsgValue::merge_patch`]
+    pub fn patched(&self, patch: &BlobMsgPayload) -> BlobMsgValue {
+        let mut value = self.to_owned_value();
+        value.merge_patch(patch);
+        value
+    }
+}
+
+fn diff<'b, 'a>(
+    path: &mut Vec<PathStep>,
+    old: &'b BlobMsgPayload<'a>,
+    new: &'b BlobMsgPayload<'a>,
+    changes: &mut Vec<BlobMsgChange<'b, 'a>>,
+) {
+    match (old, new) {
+        (BlobMsgPayload::Table(old), BlobMsgPayload::Table(new)) => {
+            let (old_keys, old) = last_values(old);
+            let (new_keys, new) = last_values(new);
+            for key in old_keys {
+                path.push(PathStep::Key(key.to_string()));
+                match new.get(key) {
+                    Some(new) => diff(path, old[key], new, changes),
+                    None => changes.push(BlobMsgChange::Removed {
+                        path: path.clone().into(),
+                        old: old[key],
+                    }),
+                }
+                path.pop();
+            }
+            for key in new_keys {
+                if old.contains_key(key) {
+                    continue;
+                }
+                path.push(PathStep::Key(key.to_string()));
+                changes.push(BlobMsgChange::Added {
+                    path: path.clone().into(),
+                    new: new[key],
+                });
+                path.pop();
+            }
+        }
+        (BlobMsgPayload::Array(old), BlobMsgPayload::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                path.push(PathStep::Index(i as i64));
+                match (old.get(i), new.get(i)) {
+                    (Some(old), Some(new)) => diff(path, &old.data, &new.data, changes),
+                    (Some(old), None) => changes.push(BlobMsgChange::Removed {
+                        path: path.clone().into(),
+                        old: &old.data,
+                    }),
+                    (None, Some(new)) => changes.push(BlobMsgChange::Added {
+                        path: path.clone().into(),
+                        new: &new.data,
+                    }),
+                    (None, None) => {}
+                }
+                path.pop();
+            }
+        }
+        // Doubles compare bit for bit, so a NaN is not a change against itself
+        (BlobMsgPayload::Double(a), BlobMsgPayload::Double(b)) if a.to_bits() == b.to_bits() => {}
+        _ if old != new => changes.push(BlobMsgChange::Changed {
+            path: path.clone().into(),
+            old,
+            new,
+        }),
+        _ => {}
+    }
+}
+
+/// The keys of `table` in the order they first appear, and the last value of each like
+/// [`BlobMsgTable::get`]
+fn last_values<'b, 'a>(
+    table: &'b BlobMsgTable<'a>,
+) -> (Vec<&'a str>, BTreeMap<&'a str, &'b BlobMsgPayload<'a>>) {
+    let mut keys = Vec::new();
+    let mut values = BTreeMap::new();
+    for (key, value) in table.iter() {
+        if values.insert(key, value).is_none() {
+            keys.push(key);
+        }
+    }
+    (keys, values)
+}
+
+impl BlobMsgValue {
+    /// Apply a JSON merge patch (RFC 7396)
+    ///
+    /// A table in `patch` updates the matching table entries one by one, a null removes an
+    /// entry, anything else replaces the value as a whole. Arrays are never merged.
+    pub fn merge_patch(&mut self, patch: &BlobMsgPayload) {
+        let BlobMsgPayload::Table(patch) = patch else {
+            *self = patch.to_owned_value();
+            return;
+        };
+        if !matches!(self, BlobMsgValue::Table(_)) {
+            *self = BlobMsgValue::Table(Vec::new());
+        }
+        let BlobMsgValue::Table(table) = self else {
+            unreachable!()
+        };
+        for (key, value) in patch.iter() {
+            if value.is_null() {
+                table.retain(|(k, _)| k != key);
+                continue;
+            }
+            match table.iter().rposition(|(k, _)| k == key) {
+                Some(i) => table[i].1.merge_patch(value),
+                None => {
+                    let mut entry = BlobMsgValue::null();
+                    entry.merge_patch(value);
+                    table.push((key.to_string(), entry));
+                }
+            }
+        }
+    }
+}
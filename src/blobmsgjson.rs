@@ -0,0 +1,250 @@
+use crate::{Blob, BlobIter, BlobMsg, BlobMsgPayload, UbusError};
+use core::fmt;
+use std::io;
+use std::string::String;
+
+/// `blobmsg_format_json_indent()` never indents deeper than this many tabs
+const MAX_INDENT: usize = 20;
+
+/// Layout of the JSON written by [`JsonWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonStyle {
+    /// One line with a space after `:` and `,`, as used by `Display`
+    #[default]
+    Compact,
+    /// Tab indented like `blobmsg_format_json_indent()` and `ubus call`
+    Indent,
+    /// One line without spaces like `ubus -S call`
+    Simple,
+}
+
+impl JsonStyle {
+    fn colon(self) -> &'static str {
+        match self {
+            JsonStyle::Simple => ":",
+            JsonStyle::Compact | JsonStyle::Indent => ": ",
+        }
+    }
+}
+
+/// Streams blobmsg data as JSON into a [`fmt::Write`]
+///
+/// The output follows libubox `blobmsg_format_json` but is always valid JSON: table
+/// entries without a name get an empty key, and unknown types as well as non-finite
+/// doubles are written as `null`. Strings which are not valid UTF-8 use the escapes of
+/// [`JsonWriter::write_bytes`].
+pub struct JsonWriter<W> {
+    out: W,
+    style: JsonStyle,
+    level: usize,
+}
+
+impl<W: fmt::Write> JsonWriter<W> {
+    pub fn new(out: W, style: JsonStyle) -> Self {
+        Self {
+            out,
+            style,
+            level: 0,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Write a value, the names of array items are dropped like `blobmsg_format_json` does
+    pub fn write_payload(&mut self, payload: &BlobMsgPayload) -> fmt::Result {
+        match payload {
+            BlobMsgPayload::Array(list) => {
+                self.open('[')?;
+                for (i, item) in list.iter().enumerate() {
+                    self.next(i)?;
+                    self.write_payload(&item.data)?;
+                }
+                self.close(']')
+            }
+            BlobMsgPayload::Table(table) => {
+                self.open('{')?;
+                for (i, (name, value)) in table.iter().enumerate() {
+                    self.next(i)?;
+                    self.write_entry(name, value)?;
+                }
+                self.close('}')
+            }
+            BlobMsgPayload::String(s) => self.write_str(s),
+            BlobMsgPayload::Bytes(bytes) => self.write_bytes(bytes),
+            BlobMsgPayload::Int64(num) => write!(self.out, "{}", num),
+            BlobMsgPayload::Int32(num) => write!(self.out, "{}", num),
+            BlobMsgPayload::Int16(num) => write!(self.out, "{}", num),
+            BlobMsgPayload::Int8(num) => write!(self.out, "{}", num),
+            BlobMsgPayload::Bool(b) => self.out.write_str(if *b { "true" } else { "false" }),
+            // printf("%lf")
+            BlobMsgPayload::Double(num) if num.is_finite() => write!(self.out, "{:.6}", num),
+            BlobMsgPayload::Double(_) | BlobMsgPayload::Unknown(_, _) => self.out.write_str("null"),
+        }
+    }
+
+    /// Write `"name": value`, an entry of a table
+    pub fn write_entry(&mut self, name: &str, payload: &BlobMsgPayload) -> fmt::Result {
+        self.write_str(name)?;
+        self.out.write_str(self.style.colon())?;
+        self.write_payload(payload)
+    }
+
+    /// Write attributes without header (e.g. the `DATA` of a reply) as an object, decoding
+    /// one attribute at a time
+    ///
+    /// Stops at the first attribute which fails to decode, leaving the object unterminated.
+    pub fn write_table(&mut self, iter: BlobIter<Blob>) -> Result<(), UbusError> {
+        self.open('{')?;
+        for (i, blob) in iter.enumerate() {
+            let msg: BlobMsg = blob?.try_into()?;
+            self.next(i)?;
+            self.write_entry(msg.name, &msg.data)?;
+        }
+        self.close('}')?;
+        Ok(())
+    }
+
+    /// Write a quoted string, escaped like `blobmsg_format_string()`
+    pub fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes())
+    }
+
+    /// Write a quoted string from arbitrary bytes
+    ///
+    /// Bytes which are not valid UTF-8 are escaped as the lone surrogates `\udc80` to
+    /// `\udcff` like Python's `surrogateescape`. Valid UTF-8 never holds a surrogate, so
+    /// the escapes can not be confused with real characters and give back the exact bytes.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        self.out.write_char('"')?;
+        for chunk in bytes.utf8_chunks() {
+            self.escape(chunk.valid())?;
+            for byte in chunk.invalid() {
+                write!(self.out, "\\udc{:02x}", byte)?;
+            }
+        }
+        self.out.write_char('"')
+    }
+
+    fn escape(&mut self, s: &str) -> fmt::Result {
+        let mut last = 0;
+        for (i, byte) in s.bytes().enumerate() {
+            let escape = match byte {
+                b'\x08' => Some("\\b"),
+                b'\n' => Some("\\n"),
+                b'\t' => Some("\\t"),
+                b'\r' => Some("\\r"),
+                b'"' => Some("\\\""),
+                b'\\' => Some("\\\\"),
+                b'/' => Some("\\/"),
+                0..0x20 => None,
+                _ => continue,
+            };
+            // Only ASCII is escaped, so `i` is always on a character boundary
+            self.out.write_str(&s[last..i])?;
+            last = i + 1;
+            match escape {
+                Some(escape) => self.out.write_str(escape)?,
+                None => write!(self.out, "\\u{:04x}", byte)?,
+            }
+        }
+        self.out.write_str(&s[last..])
+    }
+
+    fn open(&mut self, bracket: char) -> fmt::Result {
+        self.out.write_char(bracket)?;
+        self.level += 1;
+        self.newline()
+    }
+
+    fn next(&mut self, index: usize) -> fmt::Result {
+        if index == 0 {
+            return Ok(());
+        }
+        self.out.write_char(',')?;
+        match self.style {
+            JsonStyle::Compact => self.out.write_char(' '),
+            JsonStyle::Indent => self.newline(),
+            JsonStyle::Simple => Ok(()),
+        }
+    }
+
+    fn close(&mut self, bracket: char) -> fmt::Result {
+        self.level -= 1;
+        self.newline()?;
+        self.out.write_char(bracket)
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        if self.style == JsonStyle::Indent {
+            self.out.write_char('\n')?;
+            for _ in 0..self.level.min(MAX_INDENT) {
+                self.out.write_char('\t')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adapts an [`io::Write`] to the [`fmt::Write`] taken by [`JsonWriter`]
+///
+/// `fmt::Error` carries no details, the I/O error behind a failed write is kept until
+/// [`IoWrite::take_error`].
+pub struct IoWrite<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWrite<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWrite<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+impl BlobMsgPayload<'_> {
+    /// Write this payload as JSON
+    pub fn write_json<W: fmt::Write>(&self, out: W, style: JsonStyle) -> fmt::Result {
+        JsonWriter::new(out, style).write_payload(self)
+    }
+
+    /// Write this payload as JSON to a stream
+    pub fn write_json_io<W: io::Write>(&self, out: W, style: JsonStyle) -> io::Result<()> {
+        let mut writer = JsonWriter::new(IoWrite::new(out), style);
+        writer.write_payload(self).map_err(|_| {
+            writer
+                .out
+                .take_error()
+                .unwrap_or_else(|| io::Error::other("formatter error"))
+        })
+    }
+
+    /// Render this payload as JSON
+    pub fn to_json(&self, style: JsonStyle) -> String {
+        let mut json = String::new();
+        // Writing to a String never fails
+        let _ = self.write_json(&mut json, style);
+        json
+    }
+}
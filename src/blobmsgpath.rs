@@ -0,0 +1,254 @@
+use crate::{BlobMsgPayload, UbusError};
+use core::fmt;
+use core::str::FromStr;
+use std::string::String;
+use std::vec::Vec;
+
+/// One step of a [`BlobMsgPath`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathStep {
+    /// Table entry by key, the last one wins for duplicate keys. On an array a key made of
+    /// digits selects that index, so JSON Pointer segments work on both.
+    Key(String),
+    /// Array item by index, negative indices count from the end
+    Index(i64),
+    /// Every item of an array or entry of a table
+    Wildcard,
+}
+
+/// Selects sub-values of a blobmsg tree, like OpenWrt's `jsonfilter` does for JSON
+///
+/// Two spellings are understood:
+/// - `jsonfilter` style, `@.ipv4-address[0].address`, `@["ipv4-address"][*]` or
+///   `route[-1].*`. The leading `@` (or `$`) and the first dot are optional.
+/// - JSON Pointer, `/ipv4-address/0/address`, with `~1` for `/`, `~0` for `~` and `*`
+///   as a wildcard.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BlobMsgPath(Vec<PathStep>);
+
+impl BlobMsgPath {
+    pub fn parse(path: &str) -> Result<Self, UbusError> {
+        if let Some(pointer) = path.strip_prefix('/') {
+            return Self::parse_pointer(pointer);
+        }
+        let mut parser = Parser {
+            path,
+            position: 0,
+            steps: Vec::new(),
+        };
+        parser.parse().map_err(|problem| UbusError::Path {
+            position: parser.position,
+            problem,
+        })?;
+        Ok(Self(parser.steps))
+    }
+
+    fn parse_pointer(pointer: &str) -> Result<Self, UbusError> {
+        let mut steps = Vec::new();
+        let mut position = 1;
+        for segment in pointer.split('/') {
+            let step = match segment {
+                "*" => PathStep::Wildcard,
+                _ => {
+                    let mut key = String::with_capacity(segment.len());
+                    let mut chars = segment.char_indices();
+                    while let Some((i, c)) = chars.next() {
+                        if c != '~' {
+                            key.push(c);
+                            continue;
+                        }
+                        match chars.next() {
+                            Some((_, '0')) => key.push('~'),
+                            Some((_, '1')) => key.push('/'),
+                            _ => {
+                                return Err(UbusError::Path {
+                                    position: position + i,
+                                    problem: "'~' must be followed by 0 or 1",
+                                });
+                            }
+                        }
+                    }
+                    PathStep::Key(key)
+                }
+            };
+            steps.push(step);
+            position += segment.len() + 1;
+        }
+        Ok(Self(steps))
+    }
+
+    pub fn steps(&self) -> &[PathStep] {
+        &self.0
+    }
+
+    /// Every value the path selects below `root`, in wire order
+    pub fn select<'b, 'a>(&self, root: &'b BlobMsgPayload<'a>) -> Vec<&'b BlobMsgPayload<'a>> {
+        let mut current = Vec::from([root]);
+        for step in &self.0 {
+            let mut next = Vec::new();
+            for value in current {
+                match (step, value) {
+                    (PathStep::Key(key), BlobMsgPayload::Table(table)) => {
+                        next.extend(table.get(key));
+                    }
+                    (PathStep::Key(key), BlobMsgPayload::Array(list)) => {
+                        let index = key.bytes().all(|b| b.is_ascii_digit());
+                        let index = index.then(|| key.parse::<usize>().ok()).flatten();
+                        next.extend(index.and_then(|i| list.get(i)).map(|item| &item.data));
+                    }
+                    (PathStep::Index(index), BlobMsgPayload::Array(list)) => {
+                        let index = match usize::try_from(*index) {
+                            Ok(index) => Some(index),
+                            Err(_) => usize::try_from(index.unsigned_abs())
+                                .ok()
+                                .and_then(|back| list.len().checked_sub(back)),
+                        };
+                        next.extend(index.and_then(|i| list.get(i)).map(|item| &item.data));
+                    }
+                    (PathStep::Wildcard, BlobMsgPayload::Table(table)) => {
+                        next.extend(table.iter().map(|(_, v)| v));
+                    }
+                    (PathStep::Wildcard, BlobMsgPayload::Array(list)) => {
+                        next.extend(list.iter().map(|item| &item.data));
+                    }
+                    _ => {}
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+impl From<Vec<PathStep>> for BlobMsgPath {
+    fn from(steps: Vec<PathStep>) -> Self {
+        Self(steps)
+    }
+}
+
+/// Written in `jsonfilter` style, keys which would not parse back as a bare member are
+/// quoted
+impl fmt::Display for BlobMsgPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("@")?;
+        for step in &self.0 {
+            match step {
+                PathStep::Key(key)
+                    if !key.is_empty() && key != "*" && !key.contains(['.', '[']) =>
+                {
+                    write!(f, ".{}", key)?
+                }
+                PathStep::Key(key) => {
+                    f.write_str("['")?;
+                    for c in key.chars() {
+                        if c == '\\' || c == '\'' {
+                            f.write_str("\\")?;
+                        }
+                        write!(f, "{}", c)?;
+                    }
+                    f.write_str("']")?
+                }
+                PathStep::Index(index) => write!(f, "[{}]", index)?,
+                PathStep::Wildcard => f.write_str("[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BlobMsgPath {
+    type Err = UbusError;
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::parse(path)
+    }
+}
+
+struct Parser<'a> {
+    path: &'a str,
+    position: usize,
+    steps: Vec<PathStep>,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.path[self.position..]
+    }
+
+    fn parse(&mut self) -> Result<(), &'static str> {
+        if self.rest().starts_with(['@', '$']) {
+            self.position += 1;
+        } else if !self.rest().is_empty() && !self.rest().starts_with(['.', '[']) {
+            self.member()?;
+        }
+        while let Some(c) = self.rest().chars().next() {
+            self.position += 1;
+            match c {
+                '.' => self.member()?,
+                '[' => self.subscript()?,
+                _ => {
+                    self.position -= 1;
+                    return Err("expected '.' or '['");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A bare key or `*`, up to the next `.` or `[`
+    fn member(&mut self) -> Result<(), &'static str> {
+        let rest = self.rest();
+        let len = rest.find(['.', '[']).unwrap_or(rest.len());
+        let step = match &rest[..len] {
+            "" => return Err("empty key"),
+            "*" => PathStep::Wildcard,
+            key => PathStep::Key(key.into()),
+        };
+        self.steps.push(step);
+        self.position += len;
+        Ok(())
+    }
+
+    /// The inside of `[...]`: `*`, an index or a quoted key
+    fn subscript(&mut self) -> Result<(), &'static str> {
+        let rest = self.rest();
+        let (step, len) = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let mut key = String::new();
+                let mut chars = rest.char_indices().skip(1);
+                let end = loop {
+                    match chars.next().ok_or("unterminated quote")? {
+                        (i, c) if c == quote => break i + 1,
+                        (_, '\\') => key.push(chars.next().ok_or("unterminated quote")?.1),
+                        (_, c) => key.push(c),
+                    }
+                };
+                (PathStep::Key(key), end)
+            }
+            Some('*') => (PathStep::Wildcard, 1),
+            _ => {
+                let len = rest.find(']').unwrap_or(rest.len());
+                let index = rest[..len].parse().map_err(|_| "invalid index")?;
+                (PathStep::Index(index), len)
+            }
+        };
+        self.position += len;
+        if !self.rest().starts_with(']') {
+            return Err("expected ']'");
+        }
+        self.position += 1;
+        self.steps.push(step);
+        Ok(())
+    }
+}
+
+impl<'a> BlobMsgPayload<'a> {
+    /// Parse `path` as a [`BlobMsgPath`] and select the values it matches
+    pub fn query(&self, path: &str) -> Result<Vec<&BlobMsgPayload<'a>>, UbusError> {
+        Ok(BlobMsgPath::parse(path)?.select(self))
+    }
+
+    /// The first value `path` selects, if the path is valid and matches anything
+    pub fn pointer(&self, path: &str) -> Option<&BlobMsgPayload<'a>> {
+        self.query(path).ok()?.into_iter().next()
+    }
+}
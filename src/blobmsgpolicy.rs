@@ -0,0 +1,98 @@
+use crate::{BlobIter, BlobMsgType, BlobMsgView, UbusError};
+use core::mem::size_of;
+use std::string::ToString;
+
+/// One attribute of a `blobmsg_parse()` style policy
+#[derive(Copy, Clone, Debug)]
+pub struct BlobMsgPolicy<'p> {
+    pub name: &'p str,
+    /// Expected type, `UNSPEC` accepts any type
+    pub ty: BlobMsgType,
+    pub required: bool,
+}
+
+impl<'p> BlobMsgPolicy<'p> {
+    pub const fn new(name: &'p str, ty: BlobMsgType) -> Self {
+        Self {
+            name,
+            ty,
+            required: false,
+        }
+    }
+
+    pub const fn required(name: &'p str, ty: BlobMsgType) -> Self {
+        Self {
+            name,
+            ty,
+            required: true,
+        }
+    }
+
+    /// Parse a headerless list of attributes (e.g. invoke arguments or reply data) in one
+    /// pass, returning the attribute matching each policy entry in the same position
+    ///
+    /// Like libubox, attributes not named in the policy are ignored and the last one wins
+    /// for duplicates. Unlike libubox, type mismatches and missing required attributes
+    /// are reported instead of silently leaving the slot empty.
+    pub fn parse<'a, const N: usize>(
+        policy: &[BlobMsgPolicy; N],
+        data: &'a [u8],
+    ) -> Result<[Option<BlobMsgView<'a>>; N], UbusError> {
+        let mut slots = [None; N];
+        Self::parse_into(policy, data, &mut slots)?;
+        Ok(slots)
+    }
+
+    /// Same as [`BlobMsgPolicy::parse`] for policies only known at runtime, `slots` must
+    /// be at least as long as `policy`
+    pub fn parse_into<'a>(
+        policy: &[BlobMsgPolicy],
+        data: &'a [u8],
+        slots: &mut [Option<BlobMsgView<'a>>],
+    ) -> Result<(), UbusError> {
+        if slots.len() < policy.len() {
+            return Err(UbusError::InvalidData("Not enough policy slots"));
+        }
+        slots.iter_mut().for_each(|slot| *slot = None);
+
+        for attr in BlobIter::<BlobMsgView>::new(data) {
+            let attr = attr?;
+            let Some(index) = policy.iter().position(|p| p.name == attr.name()) else {
+                continue;
+            };
+            let expected = policy[index].ty;
+            if expected != BlobMsgType::UNSPEC && attr.ty() != expected {
+                return Err(UbusError::AttributeType {
+                    name: attr.name().to_string(),
+                    expected,
+                    found: attr.ty(),
+                });
+            }
+            if !Self::check_len(&attr) {
+                return Err(UbusError::InvalidData("Attribute payload too short"));
+            }
+            slots[index] = Some(attr);
+        }
+
+        for (p, slot) in policy.iter().zip(slots.iter()) {
+            if p.required && slot.is_none() {
+                return Err(UbusError::MissingAttribute(p.name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the payload is long enough for its type, like `blobmsg_check_attr()`
+    fn check_len(attr: &BlobMsgView) -> bool {
+        let len = attr.data().len();
+        match attr.ty() {
+            BlobMsgType::BOOL => len >= size_of::<u8>(),
+            BlobMsgType::INT16 => len >= size_of::<u16>(),
+            BlobMsgType::INT32 => len >= size_of::<u32>(),
+            BlobMsgType::INT64 => len >= size_of::<u64>(),
+            BlobMsgType::DOUBLE => len >= size_of::<f64>(),
+            BlobMsgType::STRING => attr.data().last() == Some(&b'\0'),
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{
+    Blob, BlobIter, BlobMsg, BlobMsgPayload, BlobMsgType, DecodeLimits, Payload, UbusError,
+};
+use core::convert::TryFrom;
+use core::str;
+
+/// Borrowed, lazily decoded view of a blobmsg value
+///
+/// Nothing is decoded or allocated until a value is accessed, which makes it cheap to
+/// pick a few fields out of a large reply.
+#[derive(Copy, Clone, Debug)]
+pub struct BlobMsgView<'a> {
+    ty: BlobMsgType,
+    name: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> BlobMsgView<'a> {
+    /// View a headerless list of attributes as a table, e.g. the `DATA` of a reply
+    pub fn table(data: &'a [u8]) -> Self {
+        Self {
+            ty: BlobMsgType::TABLE,
+            name: "",
+            data,
+        }
+    }
+
+    /// View a headerless list of attributes as an array
+    pub fn array(data: &'a [u8]) -> Self {
+        Self {
+            ty: BlobMsgType::ARRAY,
+            name: "",
+            data,
+        }
+    }
+
+    pub fn ty(&self) -> BlobMsgType {
+        self.ty
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Raw payload bytes
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn is_table(&self) -> bool {
+        self.ty == BlobMsgType::TABLE
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.ty == BlobMsgType::ARRAY
+    }
+
+    /// Iterate over the children of a table or array, stopping at the first malformed one
+    pub fn iter(&self) -> BlobIter<'a, BlobMsgView<'a>> {
+        if self.is_table() || self.is_array() {
+            BlobIter::new(self.data)
+        } else {
+            BlobIter::new(&[])
+        }
+    }
+
+    /// Number of well-formed children of a table or array
+    pub fn len(&self) -> usize {
+        self.children().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Look up a table entry, the last one wins for duplicate keys
+    pub fn get(&self, key: &str) -> Option<BlobMsgView<'a>> {
+        if !self.is_table() {
+            return None;
+        }
+        self.children().filter(|child| child.name == key).last()
+    }
+
+    /// Get the n-th child of an array (or table)
+    pub fn nth(&self, index: usize) -> Option<BlobMsgView<'a>> {
+        self.children().nth(index)
+    }
+
+    /// Children up to the first malformed one
+    fn children(&self) -> impl Iterator<Item = BlobMsgView<'a>> {
+        self.iter().map_while(Result::ok)
+    }
+
+    fn bytes<const N: usize>(&self, ty: BlobMsgType) -> Option<[u8; N]> {
+        if self.ty != ty {
+            return None;
+        }
+        self.data.get(..N)?.try_into().ok()
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        if self.ty != BlobMsgType::STRING {
+            return None;
+        }
+        let data = self.data.strip_suffix(b"\0").unwrap_or(self.data);
+        str::from_utf8(data).ok()
+    }
+
+    /// Bytes of a `STRING` without its nul terminator, whether or not it is valid UTF-8
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        if self.ty != BlobMsgType::STRING {
+            return None;
+        }
+        Some(self.data.strip_suffix(b"\0").unwrap_or(self.data))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        self.bytes::<1>(BlobMsgType::BOOL).map(|b| b[0] != 0)
+    }
+
+    pub fn as_i8(&self) -> Option<i8> {
+        self.bytes(BlobMsgType::INT8).map(i8::from_be_bytes)
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        self.bytes(BlobMsgType::INT16).map(i16::from_be_bytes)
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        self.bytes(BlobMsgType::INT32).map(i32::from_be_bytes)
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.bytes(BlobMsgType::INT64).map(i64::from_be_bytes)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.bytes(BlobMsgType::DOUBLE).map(f64::from_be_bytes)
+    }
+
+    /// Any integer type widened to `i64`
+    pub fn as_int(&self) -> Option<i64> {
+        match self.ty {
+            BlobMsgType::INT8 => self.as_i8().map(i64::from),
+            BlobMsgType::INT16 => self.as_i16().map(i64::from),
+            BlobMsgType::INT32 => self.as_i32().map(i64::from),
+            BlobMsgType::INT64 => self.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Fully decode this value
+    pub fn decode(&self) -> Result<BlobMsgPayload<'a>, UbusError> {
+        Payload::from(self.data).decode(self.ty)
+    }
+
+    /// Fully decode this value, enforcing the given limits
+    pub fn decode_with(&self, limits: &DecodeLimits) -> Result<BlobMsgPayload<'a>, UbusError> {
+        Payload::from(self.data).decode_with(self.ty, limits)
+    }
+}
+
+impl<'a> TryFrom<Blob<'a>> for BlobMsgView<'a> {
+    type Error = UbusError;
+    fn try_from(blob: Blob<'a>) -> Result<Self, Self::Error> {
+        let (name, data) = blob.name_and_payload()?;
+        Ok(Self {
+            ty: blob.tag.id().into(),
+            name,
+            data,
+        })
+    }
+}
+
+impl<'a, T> From<BlobIter<'a, T>> for BlobMsgView<'a> {
+    fn from(iter: BlobIter<'a, T>) -> Self {
+        Self::table(iter.data)
+    }
+}
+
+impl<'a> TryFrom<BlobMsgView<'a>> for BlobMsg<'a> {
+    type Error = UbusError;
+    fn try_from(view: BlobMsgView<'a>) -> Result<Self, Self::Error> {
+        Ok(BlobMsg {
+            name: view.name,
+            data: view.decode()?,
+        })
+    }
+}
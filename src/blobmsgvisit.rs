@@ -0,0 +1,186 @@
+use crate::{BlobMsgPayload, BlobMsgType, BlobTag, DecodeLimits, IO, Payload, UbusError};
+use core::str;
+use std::vec::Vec;
+
+/// Receives blobmsg data one event at a time, see [`visit_blobmsg`]
+///
+/// Every table or array entered is matched by one [`Self::leave`]. Returning an error
+/// from any method stops decoding with that error.
+pub trait BlobMsgVisitor {
+    fn enter_table(&mut self, name: &str) -> Result<(), UbusError>;
+    fn enter_array(&mut self, name: &str) -> Result<(), UbusError>;
+    fn leave(&mut self) -> Result<(), UbusError>;
+    /// Any value besides a table or array, borrowed from the scratch buffer
+    fn scalar(&mut self, name: &str, value: BlobMsgPayload) -> Result<(), UbusError>;
+}
+
+/// A table or array still being read
+struct Frame {
+    /// Bytes left in the enclosing container after this one
+    remaining: usize,
+    /// Padding to read after this container
+    padding: usize,
+}
+
+/// Decode `len` bytes of attributes without header (e.g. the `DATA` of a message) straight
+/// from `io` into `visitor`
+///
+/// Only the name and payload of one attribute are held at a time, in `scratch`, so
+/// memory use does not grow with the size of the data. A value which does not fit into
+/// `scratch` is an error, as is breaking `limits`. After any error which did not come from
+/// `io` itself, the rest of the `len` bytes is read and dropped so `io` stays in step.
+pub fn visit_blobmsg<T: IO>(
+    io: &mut T,
+    len: usize,
+    scratch: &mut [u8],
+    limits: &DecodeLimits,
+    visitor: &mut impl BlobMsgVisitor,
+) -> Result<(), UbusError> {
+    let mut io = Counted::new(io);
+    let result = visit(&mut io, len, scratch, limits, visitor);
+    if result.is_err() && !io.failed {
+        discard(io.io, len - io.read)?;
+    }
+    result
+}
+
+/// Counts the bytes read through it, to find the rest of the data after an error
+pub(crate) struct Counted<'a, T> {
+    io: &'a mut T,
+    read: usize,
+    /// Whether reading failed, after which the stream can not be trusted
+    pub(crate) failed: bool,
+}
+
+impl<'a, T> Counted<'a, T> {
+    pub(crate) fn new(io: &'a mut T) -> Self {
+        Self {
+            io,
+            read: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<T: IO> IO for Counted<'_, T> {
+    type Error = T::Error;
+    fn put(&mut self, data: &[u8]) -> Result<(), UbusError> {
+        self.io.put(data)
+    }
+    fn get(&mut self, data: &mut [u8]) -> Result<(), UbusError> {
+        let result = self.io.get(data);
+        self.failed |= result.is_err();
+        self.read += data.len();
+        result
+    }
+}
+
+fn visit<T: IO>(
+    io: &mut T,
+    len: usize,
+    scratch: &mut [u8],
+    limits: &DecodeLimits,
+    visitor: &mut impl BlobMsgVisitor,
+) -> Result<(), UbusError> {
+    let mut open: Vec<Frame> = Vec::new();
+    let mut remaining = len;
+    let mut attrs = 0;
+    loop {
+        if remaining == 0 {
+            let Some(frame) = open.pop() else {
+                return Ok(());
+            };
+            skip(io, frame.padding)?;
+            remaining = frame.remaining;
+            visitor.leave()?;
+            continue;
+        }
+
+        valid_data!(remaining >= BlobTag::SIZE, "Blob too short");
+        let mut tag = [0u8; BlobTag::SIZE];
+        io.get(&mut tag)?;
+        let tag = BlobTag::from_bytes(tag);
+        tag.is_valid()?;
+        valid_data!(tag.size() <= remaining, "Blob too short");
+        let padded = tag.next_tag().min(remaining);
+        remaining -= padded;
+        if attrs >= limits.max_attrs {
+            return Err(UbusError::TooManyAttributes(limits.max_attrs));
+        }
+        attrs += 1;
+
+        // The name, its nul terminator and the padding after them
+        let (prefix, name_len, header) = if tag.is_extended() {
+            let mut len = [0u8; 2];
+            valid_data!(
+                tag.inner_len() >= len.len(),
+                "Extended blob too short for name length"
+            );
+            io.get(&mut len)?;
+            let name_len = u16::from_be_bytes(len) as usize;
+            valid_data!(
+                name_len + len.len() < tag.inner_len(),
+                "name lenth > data lenth"
+            );
+            // An attribute without payload may omit the padding after its name
+            let header = (len.len() + name_len + 1)
+                .next_multiple_of(BlobTag::ALIGNMENT)
+                .min(tag.inner_len());
+            (len.len(), name_len, header - len.len())
+        } else {
+            (0, 0, 0)
+        };
+        let payload_len = tag.inner_len() - prefix - header;
+        let ty = BlobMsgType::from(tag.id());
+        let nested = ty == BlobMsgType::TABLE || ty == BlobMsgType::ARRAY;
+        // Children of tables and arrays are read one by one
+        let needed = if nested { header } else { header + payload_len };
+        let Some(buffer) = scratch.get_mut(..needed) else {
+            return Err(UbusError::InvalidData(
+                "Attribute larger than scratch buffer",
+            ));
+        };
+        let (head, data) = buffer.split_at_mut(header);
+        io.get(head)?;
+        if tag.is_extended() {
+            valid_data!(head[name_len] == b'\0', "No extended name nul terminator");
+        }
+        let name = str::from_utf8(&head[..name_len])?;
+
+        if nested {
+            if open.len() >= limits.max_depth {
+                return Err(UbusError::NestingTooDeep(limits.max_depth));
+            }
+            match ty {
+                BlobMsgType::TABLE => visitor.enter_table(name)?,
+                _ => visitor.enter_array(name)?,
+            }
+            open.push(Frame {
+                remaining,
+                padding: padded - tag.size(),
+            });
+            remaining = payload_len;
+        } else {
+            io.get(data)?;
+            visitor.scalar(name, Payload::from(&*data).decode(ty)?)?;
+            skip(io, padded - tag.size())?;
+        }
+    }
+}
+
+/// Read and drop `len` bytes
+fn discard<T: IO>(io: &mut T, mut len: usize) -> Result<(), UbusError> {
+    let mut buffer = [0u8; 64];
+    while len > 0 {
+        let chunk = len.min(buffer.len());
+        io.get(&mut buffer[..chunk])?;
+        len -= chunk;
+    }
+    Ok(())
+}
+
+/// Read and drop up to three bytes of padding
+fn skip<T: IO>(io: &mut T, len: usize) -> Result<(), UbusError> {
+    let mut padding = [0u8; BlobTag::ALIGNMENT];
+    io.get(&mut padding[..len])
+}
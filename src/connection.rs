@@ -1,10 +1,12 @@
 use crate::*;
 
-use core::panic;
-use std::collections::HashMap;
 extern crate alloc;
+use alloc::boxed::Box;
 use alloc::string::String;
-use std::format;
+use alloc::vec::Vec;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use ubuserror::*;
 
 #[derive(Copy, Clone)]
@@ -22,7 +24,50 @@ impl core::fmt::Debug for ObjectResult<'_> {
 pub struct SignatureResult<'a> {
     pub object: ObjectResult<'a>,
     pub name: &'a str,
-    pub args: HashMap<&'a str, BlobMsgType>,
+    pub args: OrderedMap<&'a str, BlobMsgType>,
+}
+
+/// Decode the argument policy of a method signature, a table of `name: type` pairs, into
+/// the empty map `args`
+fn signature_policy<'a>(
+    method: &str,
+    policy: &BlobMsgPayload<'a>,
+    mut args: OrderedMap<&'a str, BlobMsgType>,
+) -> Result<OrderedMap<&'a str, BlobMsgType>, UbusError> {
+    let BlobMsgPayload::Table(table) = policy else {
+        return Err(UbusError::AttributeType {
+            name: method.into(),
+            expected: BlobMsgType::TABLE,
+            found: policy.ty(),
+        });
+    };
+    for (k, v) in table.iter() {
+        match *v {
+            BlobMsgPayload::Int32(typeid) => args.insert(k, BlobMsgType::from(typeid as u32)),
+            ref v => {
+                return Err(UbusError::AttributeType {
+                    name: k.into(),
+                    expected: BlobMsgType::INT32,
+                    found: v.ty(),
+                });
+            }
+        };
+    }
+    Ok(args)
+}
+
+/// Wrap an error with what was being called
+fn call_error(
+    path: Option<&str>,
+    obj: Option<u32>,
+    method: &str,
+) -> impl FnOnce(UbusError) -> UbusError {
+    move |source| UbusError::Call {
+        path: path.map(String::from),
+        obj,
+        method: method.into(),
+        source: Box::new(source),
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -30,6 +75,7 @@ pub struct Connection<T: IO> {
     io: T,
     peer: u32,
     sequence: u16,
+    limits: DecodeLimits,
     buffer: [u8; 64 * 1024],
 }
 
@@ -40,6 +86,7 @@ impl<T: IO> Connection<T> {
             io,
             peer: 0,
             sequence: 0,
+            limits: DecodeLimits::default(),
             buffer: [0u8; 64 * 1024],
         };
 
@@ -59,7 +106,7 @@ impl<T: IO> Connection<T> {
     }
 
     fn header_by_obj_cmd(&mut self, obj_id: u32, cmd: UbusCmdType) -> UbusMsgHeader {
-        self.sequence += 1;
+        self.sequence = self.sequence.wrapping_add(1);
         UbusMsgHeader {
             version: UbusMsgVersion::CURRENT,
             cmd_type: cmd,
@@ -68,13 +115,53 @@ impl<T: IO> Connection<T> {
         }
     }
 
+    /// Limits applied to messages received from now on
+    pub fn set_limits(&mut self, limits: DecodeLimits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
     // Get next message from ubus channel (blocking!)
     pub fn next_message(&mut self) -> Result<UbusMsg, UbusError> {
-        UbusMsg::from_io(&mut self.io, &mut self.buffer)
+        UbusMsg::from_io_with(&mut self.io, &mut self.buffer, &self.limits)
     }
 
     pub fn send(&mut self, message: UbusMsgBuilder) -> Result<(), UbusError> {
-        self.io.put(message.into())
+        self.io.put(message.finish()?)
+    }
+
+    /// Send `cmd` to `peer`, the returned header has the sequence number replies carry
+    pub fn send_cmd(&mut self, peer: u32, cmd: &UbusCmd) -> Result<UbusMsgHeader, UbusError> {
+        let mut buffer = [0u8; 1024];
+        let header = self.header_by_obj_cmd(peer, cmd.cmd_type());
+        self.send(cmd.to_message(&mut buffer, &header)?)?;
+        Ok(header)
+    }
+
+    fn send_invoke(
+        &mut self,
+        obj: u32,
+        method: &str,
+        args: &[u8],
+    ) -> Result<UbusMsgHeader, UbusError> {
+        let invoke = InvokeMsg {
+            obj,
+            method,
+            data: Some(args),
+            user: None,
+            group: None,
+        };
+        self.send_cmd(obj, &UbusCmd::Invoke(invoke))
+    }
+
+    fn send_lookup(&mut self, obj_path: &str) -> Result<UbusMsgHeader, UbusError> {
+        let lookup = LookupMsg {
+            path: (!obj_path.is_empty()).then_some(obj_path),
+        };
+        self.send_cmd(0, &UbusCmd::Lookup(lookup))
     }
 
     pub fn invoke(
@@ -82,85 +169,273 @@ impl<T: IO> Connection<T> {
         obj: u32,
         method: &str,
         args: &[u8],
-        mut on_result: impl FnMut(BlobIter<Blob>),
+        on_result: impl FnMut(BlobIter<Blob>),
     ) -> Result<(), UbusError> {
-        let mut buffer = [0u8; 1024];
-        let header = self.header_by_obj_cmd(obj, UbusCmdType::INVOKE);
-        let mut message = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
-        message.put(UbusMsgAttr::ObjId(obj))?;
-        message.put(UbusMsgAttr::Method(method))?;
-
-        message.put(UbusMsgAttr::Data(&args))?;
+        self.invoke_replies(obj, method, args, on_result)
+            .map_err(call_error(None, Some(obj), method))
+    }
 
-        self.send(message)?;
-        'message: loop {
+    fn invoke_replies(
+        &mut self,
+        obj: u32,
+        method: &str,
+        args: &[u8],
+        mut on_result: impl FnMut(BlobIter<Blob>),
+    ) -> Result<(), UbusError> {
+        let header = self.send_invoke(obj, method, args)?;
+        loop {
             let message = self.next_message()?;
             if message.header.sequence != header.sequence {
                 continue;
             }
+            match UbusCmd::try_from(message)? {
+                UbusCmd::Status(status) => return status.result(),
+                UbusCmd::Data(reply) => on_result(BlobIter::new(reply.data)),
+                UbusCmd::LookupReply(_) | UbusCmd::ObjectAdded(_) => {
+                    return Err(UbusError::InvalidData("Invalid data message"));
+                }
+                _ => continue,
+            }
+        }
+    }
 
-            let attrs = BlobIter::<UbusMsgAttr>::new(message.blob.data);
+    /// Like [`Connection::invoke`], but the `DATA` of every reply is decoded straight off
+    /// the socket into `visitor`
+    ///
+    /// Replies may be larger than the receive buffer, which only has to hold one value.
+    pub fn invoke_visit(
+        &mut self,
+        obj: u32,
+        method: &str,
+        args: &[u8],
+        visitor: &mut impl BlobMsgVisitor,
+    ) -> Result<(), UbusError> {
+        self.invoke_visit_replies(obj, method, args, visitor)
+            .map_err(call_error(None, Some(obj), method))
+    }
 
-            match message.header.cmd_type {
-                UbusCmdType::STATUS => {
-                    for attr in attrs {
-                        if let UbusMsgAttr::Status(0) = attr {
-                            return Ok(());
-                        } else if let UbusMsgAttr::Status(status) = attr {
-                            return Err(UbusError::Status(status));
+    fn invoke_visit_replies(
+        &mut self,
+        obj: u32,
+        method: &str,
+        args: &[u8],
+        visitor: &mut impl BlobMsgVisitor,
+    ) -> Result<(), UbusError> {
+        let header = self.send_invoke(obj, method, args)?;
+
+        loop {
+            let mut head = [0u8; UbusMsgHeader::SIZE + BlobTag::SIZE];
+            self.io.get(&mut head)?;
+            let (reply, tag) = head.split_at(UbusMsgHeader::SIZE);
+            let reply = UbusMsgHeader::from_bytes(reply.try_into().unwrap());
+            let tag = BlobTag::from_bytes(tag.try_into().unwrap());
+            valid_data!(reply.version == UbusMsgVersion::CURRENT, "Wrong version");
+            tag.is_valid()?;
+            let size = UbusMsgHeader::SIZE + tag.size();
+            if size > self.limits.max_message_len {
+                return Err(UbusError::MessageTooLarge {
+                    size,
+                    max: self.limits.max_message_len,
+                });
+            }
+
+            let ours = reply.sequence == header.sequence;
+            let mut status = None;
+            let mut remaining = tag.inner_len();
+            while remaining > 0 {
+                valid_data!(remaining >= BlobTag::SIZE, "Blob too short");
+                let mut attr = [0u8; BlobTag::SIZE];
+                self.io.get(&mut attr)?;
+                let attr = BlobTag::from_bytes(attr);
+                attr.is_valid()?;
+                valid_data!(attr.size() <= remaining, "Blob too short");
+                let padded = attr.next_tag().min(remaining);
+                remaining -= padded;
+                let padding = padded - attr.size();
+                match (reply.cmd_type, BlobAttrId::from(attr.id())) {
+                    (UbusCmdType::DATA, BlobAttrId::DATA) if ours => {
+                        let len = attr.inner_len();
+                        let mut io = Counted::new(&mut self.io);
+                        let visited =
+                            visit_blobmsg(&mut io, len, &mut self.buffer, &self.limits, visitor);
+                        if let Err(err) = visited {
+                            // Drop the rest of the message so the next call starts in step
+                            if !io.failed {
+                                self.discard(padding + remaining)?;
+                            }
+                            return Err(err);
                         }
+                        self.discard(padding)?;
                     }
-                    return Err(UbusError::InvalidData("Invalid status message"));
-                }
-                UbusCmdType::DATA => {
-                    for attr in attrs {
-                        if let UbusMsgAttr::Data(data) = attr {
-                            on_result(BlobIter::new(data));
-                            continue 'message;
-                        }
+                    (UbusCmdType::STATUS, BlobAttrId::STATUS) if ours => {
+                        let mut value = [0u8; 4];
+                        valid_data!(attr.inner_len() == value.len(), "Invalid status message");
+                        self.io.get(&mut value)?;
+                        self.discard(padding)?;
+                        status = Some(UbusStatus::from(i32::from_be_bytes(value)));
                     }
-                    return Err(UbusError::InvalidData("Invalid data message"));
+                    _ => self.discard(attr.inner_len() + padding)?,
                 }
-                unknown => {
-                    std::dbg!(unknown);
+            }
+            match status {
+                Some(UbusStatus::OK) => return Ok(()),
+                Some(status) => return Err(UbusError::Status(status)),
+                None if ours && reply.cmd_type == UbusCmdType::STATUS => {
+                    return Err(UbusError::InvalidData("Invalid status message"));
                 }
+                None => {}
             }
         }
     }
 
+    /// Read and drop `len` bytes
+    fn discard(&mut self, mut len: usize) -> Result<(), UbusError> {
+        while len > 0 {
+            let chunk = len.min(self.buffer.len());
+            self.io.get(&mut self.buffer[..chunk])?;
+            len -= chunk;
+        }
+        Ok(())
+    }
+
     pub fn call<'a>(
         &'a mut self,
         obj_path: &'a str,
         method: &'a str,
         args: &'a str,
     ) -> Result<String, UbusError> {
-        let obj_json = self.lookup_object_json(obj_path)?;
-        let obj: UbusObject = serde_json::from_str(&obj_json)?;
-        let args = obj.args_from_json(method, args).unwrap();
-        let mut json = String::new();
-        self.invoke(obj.id, method, &args, |bi| {
-            json += "{\n";
-            let mut first = true;
-            for x in bi {
-                if !first {
-                    json += ",\n";
-                }
-                //json_str += &format!("{:?}", x);
-                let msg: BlobMsg = x.try_into().unwrap();
-                json += &format!("\t{}", msg);
-                first = false;
+        self.call_with_style(obj_path, method, args, JsonStyle::Indent)
+    }
+
+    /// Like [`Connection::call`], with the reply rendered in `style`
+    pub fn call_with_style(
+        &mut self,
+        obj_path: &str,
+        method: &str,
+        args: &str,
+        style: JsonStyle,
+    ) -> Result<String, UbusError> {
+        let mut json = JsonWriter::new(String::new(), style);
+        let mut result = Ok(());
+        self.call_with(obj_path, method, args, |bi| {
+            if result.is_ok() {
+                result = json.write_table(bi);
             }
-            json += "\n}";
         })?;
-        Ok(json)
+        result.map(|()| json.into_inner())
+    }
+
+    /// Look up `obj_path`, convert the JSON `args` for `method` and pass every reply to
+    /// `on_result`
+    pub fn call_with(
+        &mut self,
+        obj_path: &str,
+        method: &str,
+        args: &str,
+        on_result: impl FnMut(BlobIter<Blob>),
+    ) -> Result<(), UbusError> {
+        self.call_built(
+            obj_path,
+            method,
+            |obj| obj.args_from_json(method, args),
+            on_result,
+        )
+    }
+
+    /// Like [`Connection::call`], with arguments given as a value tree and every reply
+    /// collected into an owned table, in order
+    ///
+    /// The arguments are built with [`UbusObject::args_from_payload`].
+    pub fn call_value(
+        &mut self,
+        obj_path: &str,
+        method: &str,
+        args: &BlobMsgPayload,
+    ) -> Result<Vec<BlobMsgValue>, UbusError> {
+        self.call_replies(obj_path, method, |obj| obj.args_from_payload(method, args))
+    }
+
+    /// Like [`Connection::call`], with arguments serialized from `args` and the replies
+    /// deserialized into `R`, e.g. a [`serde_json::Value`]
+    ///
+    /// Entries of all replies are merged into one object, later ones replacing earlier
+    /// ones of the same name.
+    pub fn call_as<R: DeserializeOwned>(
+        &mut self,
+        obj_path: &str,
+        method: &str,
+        args: &(impl Serialize + ?Sized),
+    ) -> Result<R, UbusError> {
+        let args = serde_json::to_value(args)
+            .map_err(UbusError::ParseArguments)
+            .map_err(call_error(Some(obj_path), None, method))?;
+        let replies = self.call_replies(obj_path, method, |obj| {
+            obj.args_from_value(method, &args, Coercion::STRICT)
+        })?;
+        let mut entries = Vec::new();
+        for reply in replies {
+            // The DATA of a reply always decodes as a table
+            if let BlobMsgValue::Table(table) = reply {
+                entries.extend(table);
+            }
+        }
+        serde_json::from_value(Value::from(BlobMsgValue::Table(entries).as_payload()))
+            .map_err(UbusError::ParseReply)
+            .map_err(call_error(Some(obj_path), None, method))
+    }
+
+    /// The `DATA` of every reply to a call with the arguments built by `args`, in order
+    fn call_replies(
+        &mut self,
+        obj_path: &str,
+        method: &str,
+        args: impl FnOnce(UbusObject) -> Result<Vec<u8>, UbusError>,
+    ) -> Result<Vec<BlobMsgValue>, UbusError> {
+        let mut replies = Vec::new();
+        let mut result = Ok(());
+        self.call_built(obj_path, method, args, |bi| {
+            if result.is_err() {
+                return;
+            }
+            match BlobMsgValue::try_from(bi) {
+                Ok(reply) => replies.push(reply),
+                Err(e) => result = Err(e),
+            }
+        })?;
+        result
+            .map(|()| replies)
+            .map_err(call_error(Some(obj_path), None, method))
+    }
+
+    /// Look up `obj_path`, build the arguments from the object found and invoke `method`
+    fn call_built(
+        &mut self,
+        obj_path: &str,
+        method: &str,
+        args: impl FnOnce(UbusObject) -> Result<Vec<u8>, UbusError>,
+        on_result: impl FnMut(BlobIter<Blob>),
+    ) -> Result<(), UbusError> {
+        let mut obj = None;
+        self.lookup(obj_path, |o| obj = Some(o.to_owned_value()))
+            .map_err(call_error(Some(obj_path), None, method))?;
+        let obj = obj
+            .ok_or(UbusError::Status(UbusStatus::NOT_FOUND))
+            .map_err(call_error(Some(obj_path), None, method))?;
+        let context = call_error(Some(obj_path), Some(obj.id), method);
+        match args(obj.as_object()) {
+            Ok(args) => self
+                .invoke_replies(obj.id, method, &args, on_result)
+                .map_err(context),
+            Err(e) => Err(context(e)),
+        }
     }
 
     pub fn lookup_object_json<'a>(&'a mut self, obj_path: &'a str) -> Result<String, UbusError> {
-        let mut obj_json = String::new();
+        let mut obj_json = Ok(String::new());
         self.lookup(obj_path, |obj| {
-            obj_json = serde_json::to_string_pretty(&obj).unwrap();
+            obj_json = serde_json::to_string_pretty(&obj).map_err(UbusError::from);
         })?;
-        Ok(obj_json)
+        obj_json
     }
 
     pub fn lookup_cb(
@@ -169,76 +444,25 @@ impl<T: IO> Connection<T> {
         mut on_object: impl FnMut(ObjectResult),
         mut on_signature: impl FnMut(SignatureResult),
     ) -> Result<(), UbusError> {
-        let mut buffer = [0u8; 1024];
-        let header = self.header_by_obj_cmd(0, UbusCmdType::LOOKUP);
-        let mut request = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
-        if obj_path.len() != 0 {
-            request.put(UbusMsgAttr::ObjPath(obj_path)).unwrap();
-        }
-        self.send(request)?;
-
-        loop {
-            let message = self.next_message()?;
-            if message.header.sequence != header.sequence {
-                continue;
-            }
-
-            let attrs = BlobIter::<UbusMsgAttr>::new(message.blob.data);
-
-            if message.header.cmd_type == UbusCmdType::STATUS {
-                for attr in attrs {
-                    if let UbusMsgAttr::Status(0) = attr {
-                        return Ok(());
-                    } else if let UbusMsgAttr::Status(status) = attr {
-                        return Err(UbusError::Status(status));
-                    }
-                }
-                return Err(UbusError::InvalidData("Invalid status message"));
-            }
-
-            if message.header.cmd_type != UbusCmdType::DATA {
-                continue;
+        let limits = self.limits;
+        self.lookup_replies(obj_path, |reply| {
+            let object = ObjectResult {
+                path: reply.path,
+                id: reply.id,
+                ty: reply.ty,
+            };
+            on_object(object);
+
+            let signature = Payload::from(reply.signature).table_with(&limits)?;
+            for (name, signature) in signature {
+                on_signature(SignatureResult {
+                    object,
+                    name,
+                    args: signature_policy(name, &signature, OrderedMap::new())?,
+                })
             }
-
-            let mut obj_path: Option<&str> = None;
-            let mut obj_id: Option<u32> = None;
-            let mut obj_type: Option<u32> = None;
-            for attr in attrs {
-                match attr {
-                    UbusMsgAttr::ObjPath(path) => obj_path = Some(path),
-                    UbusMsgAttr::ObjId(id) => obj_id = Some(id),
-                    UbusMsgAttr::ObjType(ty) => obj_type = Some(ty),
-                    UbusMsgAttr::Signature(nested) => {
-                        let object = ObjectResult {
-                            path: obj_path.unwrap(),
-                            id: obj_id.unwrap(),
-                            ty: obj_type.unwrap(),
-                        };
-                        on_object(object);
-
-                        for signature in nested {
-                            if let BlobMsgPayload::Table(table) = signature.1 {
-                                on_signature(SignatureResult {
-                                    object,
-                                    name: signature.0,
-                                    args: table
-                                        .iter()
-                                        .map(|(k, v)| {
-                                            if let BlobMsgPayload::Int32(typeid) = *v {
-                                                (*k, BlobMsgType::from(typeid as u32))
-                                            } else {
-                                                panic!()
-                                            }
-                                        })
-                                        .collect(),
-                                })
-                            }
-                        }
-                    }
-                    _ => continue,
-                }
-            }
-        }
+            Ok(())
+        })
     }
 
     pub fn lookup_id(&mut self, obj_path: &str) -> Result<u32, UbusError> {
@@ -252,68 +476,82 @@ impl<T: IO> Connection<T> {
         obj_path: &str,
         mut on_object: impl FnMut(UbusObject),
     ) -> Result<(), UbusError> {
-        let mut buffer = [0u8; 1024];
-        let header = self.header_by_obj_cmd(0, UbusCmdType::LOOKUP);
-        let mut request = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
-        if obj_path.len() != 0 {
-            request.put(UbusMsgAttr::ObjPath(obj_path)).unwrap();
-        }
-        self.send(request)?;
-
-        loop {
-            let message = self.next_message()?;
-            if message.header.sequence != header.sequence {
-                continue;
+        let limits = self.limits;
+        self.lookup_replies(obj_path, |reply| {
+            let mut obj = UbusObject {
+                path: reply.path,
+                id: reply.id,
+                ty: reply.ty,
+                ..UbusObject::default()
+            };
+            let signature = Payload::from(reply.signature).table_with(&limits)?;
+            for (name, policy) in signature {
+                let signature = Method {
+                    name,
+                    policy: signature_policy(name, &policy, OrderedMap::new())?,
+                };
+                obj.methods.insert(name, signature);
             }
+            on_object(obj);
+            Ok(())
+        })
+    }
 
-            let attrs = BlobIter::<UbusMsgAttr>::new(message.blob.data);
-
-            if message.header.cmd_type == UbusCmdType::STATUS {
-                for attr in attrs {
-                    if let UbusMsgAttr::Status(0) = attr {
-                        return Ok(());
-                    } else if let UbusMsgAttr::Status(status) = attr {
-                        return Err(UbusError::Status(status));
-                    }
+    /// Like [`Self::lookup`], decoding every reply with the allocations of `context`
+    ///
+    /// The object is only lent to `on_object` as its maps go back to `context` for the
+    /// next one, [`UbusObject::to_owned_value`] keeps a copy.
+    pub fn lookup_in(
+        &mut self,
+        context: &mut DecodeContext,
+        obj_path: &str,
+        mut on_object: impl FnMut(&UbusObject),
+    ) -> Result<(), UbusError> {
+        let limits = self.limits;
+        self.lookup_replies(obj_path, |reply| {
+            let mut obj = UbusObject {
+                path: reply.path,
+                id: reply.id,
+                ty: reply.ty,
+                methods: context.methods(),
+            };
+            let signature = context.decode(reply.signature.into(), BlobMsgType::TABLE, &limits)?;
+            if let BlobMsgPayload::Table(table) = &signature {
+                for (name, policy) in table.iter() {
+                    let method = Method {
+                        name,
+                        policy: signature_policy(name, policy, context.policy())?,
+                    };
+                    obj.methods.insert(name, method);
                 }
-                return Err(UbusError::InvalidData("Invalid status message"));
             }
+            context.recycle(signature);
+            on_object(&obj);
+            context.recycle_object(obj);
+            Ok(())
+        })
+    }
 
-            if message.header.cmd_type != UbusCmdType::DATA {
+    /// Send a lookup of `obj_path` and pass every reply to `on_reply` until the status
+    fn lookup_replies(
+        &mut self,
+        obj_path: &str,
+        mut on_reply: impl FnMut(LookupReplyMsg) -> Result<(), UbusError>,
+    ) -> Result<(), UbusError> {
+        let header = self.send_lookup(obj_path)?;
+        loop {
+            let message = self.next_message()?;
+            if message.header.sequence != header.sequence {
                 continue;
             }
-            let mut obj = UbusObject::default();
-            for attr in attrs {
-                match attr {
-                    UbusMsgAttr::ObjPath(path) => obj.path = path,
-                    UbusMsgAttr::ObjId(id) => obj.id = id,
-                    UbusMsgAttr::ObjType(ty) => obj.ty = ty,
-                    UbusMsgAttr::Signature(nested) => {
-                        for item in nested {
-                            let signature = Method {
-                                name: item.0,
-                                policy: if let BlobMsgPayload::Table(table) = item.1 {
-                                    table
-                                        .iter()
-                                        .map(|(k, v)| {
-                                            if let BlobMsgPayload::Int32(typeid) = *v {
-                                                (*k, BlobMsgType::from(typeid as u32))
-                                            } else {
-                                                panic!()
-                                            }
-                                        })
-                                        .collect()
-                                } else {
-                                    panic!()
-                                },
-                            };
-                            obj.methods.insert(item.0, signature);
-                        }
-                    }
-                    _ => continue,
+            match UbusCmd::try_from(message)? {
+                UbusCmd::Status(status) => return status.result(),
+                UbusCmd::LookupReply(reply) => on_reply(reply)?,
+                UbusCmd::Data(_) | UbusCmd::ObjectAdded(_) => {
+                    return Err(UbusError::InvalidData("Invalid lookup reply"));
                 }
+                _ => continue,
             }
-            on_object(obj);
         }
     }
 
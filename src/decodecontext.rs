@@ -0,0 +1,109 @@
+use crate::blob::Budget;
+use crate::{BlobMsg, BlobMsgPayload, BlobMsgType, DecodeLimits, Method, Payload, UbusError};
+use crate::{OrderedMap, UbusObject};
+use core::mem::{ManuallyDrop, align_of, size_of};
+use std::vec::Vec;
+
+/// Allocations kept from one decoded message for the next
+///
+/// Decoding allocates a vector for every table and array and a lookup also one for the
+/// methods of every object and the policy of every method. Trees and objects handed back
+/// with [`Self::recycle`] and [`Self::recycle_object`] return those allocations here, so
+/// decoding message after message of a similar shape, e.g. with
+/// [`crate::Connection::lookup_in`], stops allocating once the context is warm.
+#[derive(Debug, Default)]
+pub struct DecodeContext {
+    lists: Vec<Vec<BlobMsg<'static>>>,
+    tables: Vec<Vec<(&'static str, BlobMsgPayload<'static>)>>,
+    policies: Vec<Vec<(&'static str, BlobMsgType)>>,
+    methods: Vec<Vec<(&'static str, Method<'static>)>>,
+}
+
+impl DecodeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like [`Payload::decode_with`], with tables and arrays decoded into vectors from
+    /// this context
+    pub fn decode<'a>(
+        &mut self,
+        payload: Payload<'a>,
+        ty: BlobMsgType,
+        limits: &DecodeLimits,
+    ) -> Result<BlobMsgPayload<'a>, UbusError> {
+        payload.decode_nested(ty, &mut Budget::pooled(limits, self))
+    }
+
+    /// Keep the vectors of a tree which is no longer needed
+    pub fn recycle(&mut self, payload: BlobMsgPayload<'_>) {
+        match payload {
+            BlobMsgPayload::Array(mut list) => {
+                for item in list.drain(..) {
+                    self.recycle(item.data);
+                }
+                // SAFETY: `BlobMsg<'_>` and `BlobMsg<'static>` differ only in lifetime
+                self.lists.push(unsafe { erase(list) });
+            }
+            BlobMsgPayload::Table(table) => {
+                let mut table = table.into_vec();
+                for (_, data) in table.drain(..) {
+                    self.recycle(data);
+                }
+                // SAFETY: the entry types differ only in lifetime
+                self.tables.push(unsafe { erase(table) });
+            }
+            _ => {}
+        }
+    }
+
+    /// Keep the maps of an object which is no longer needed
+    pub fn recycle_object(&mut self, object: UbusObject<'_>) {
+        let mut methods = object.methods.into_vec();
+        for (_, method) in methods.drain(..) {
+            // SAFETY: the entry types differ only in lifetime
+            self.policies
+                .push(unsafe { erase(method.policy.into_vec()) });
+        }
+        // SAFETY: the entry types differ only in lifetime
+        self.methods.push(unsafe { erase(methods) });
+    }
+
+    /// Number of vectors and maps ready to be reused
+    pub fn pooled(&self) -> usize {
+        self.lists.len() + self.tables.len() + self.policies.len() + self.methods.len()
+    }
+
+    pub(crate) fn list<'a>(&mut self) -> Vec<BlobMsg<'a>> {
+        self.lists.pop().unwrap_or_default()
+    }
+
+    pub(crate) fn table<'a>(&mut self) -> Vec<(&'a str, BlobMsgPayload<'a>)> {
+        self.tables.pop().unwrap_or_default()
+    }
+
+    pub(crate) fn policy<'a>(&mut self) -> OrderedMap<&'a str, BlobMsgType> {
+        OrderedMap::from_vec(self.policies.pop().unwrap_or_default())
+    }
+
+    pub(crate) fn methods<'a>(&mut self) -> OrderedMap<&'a str, Method<'a>> {
+        OrderedMap::from_vec(self.methods.pop().unwrap_or_default())
+    }
+}
+
+/// Empty a vector and keep its allocation for elements with the lifetimes erased
+///
+/// Pooled vectors are `'static` in their element type and shrink back to any shorter
+/// lifetime by covariance when taken from the pool.
+///
+/// # Safety
+///
+/// `T` and `S` must be the same type up to lifetimes.
+unsafe fn erase<T, S>(mut list: Vec<T>) -> Vec<S> {
+    debug_assert!(size_of::<T>() == size_of::<S>() && align_of::<T>() == align_of::<S>());
+    list.clear();
+    let mut list = ManuallyDrop::new(list);
+    // SAFETY: the allocation came from a `Vec` of a type with the same layout and holds no
+    // elements, so none of them outlive the lifetimes they were borrowed for.
+    unsafe { Vec::from_raw_parts(list.as_mut_ptr().cast(), 0, list.capacity()) }
+}
@@ -0,0 +1,404 @@
+use crate::{Blob, BlobIter, BlobMsg, BlobMsgPayload, BlobMsgTable, BlobMsgValue, DecodeLimits};
+use crate::{BlobMsgType, UbusError};
+use core::fmt;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/// Streams blobmsg data into a [`fmt::Write`] as the script printed by `jshn -r`
+///
+/// Evaluating the output in a shell which sourced `/usr/share/libubox/jshn.sh` loads the
+/// data like `json_load` would. Array items are named by their index, unknown types and
+/// non-finite doubles are added as `null`. Strings which are not valid UTF-8 are quoted as
+/// `$'...'` with every invalid byte as a `\xXX` escape, so the shell gets the raw bytes.
+pub struct JshnWriter<W> {
+    out: W,
+}
+
+impl<W: fmt::Write> JshnWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Write attributes without header (e.g. the `DATA` of a reply), decoding one attribute
+    /// at a time
+    pub fn write_table(&mut self, iter: BlobIter<Blob>) -> Result<(), UbusError> {
+        self.out.write_str("json_init;\n")?;
+        for blob in iter {
+            let msg: BlobMsg = blob?.try_into()?;
+            self.write_element(msg.name, &msg.data)?;
+        }
+        Ok(())
+    }
+
+    /// Write the entries of a table
+    pub fn write_object(&mut self, table: &BlobMsgTable) -> fmt::Result {
+        self.out.write_str("json_init;\n")?;
+        for (name, value) in table.iter() {
+            self.write_element(name, value)?;
+        }
+        Ok(())
+    }
+
+    fn write_element(&mut self, name: &str, payload: &BlobMsgPayload) -> fmt::Result {
+        let ty = match payload {
+            BlobMsgPayload::Array(_) => "array",
+            BlobMsgPayload::Table(_) => "object",
+            BlobMsgPayload::String(_) | BlobMsgPayload::Bytes(_) => "string",
+            BlobMsgPayload::Int64(_)
+            | BlobMsgPayload::Int32(_)
+            | BlobMsgPayload::Int16(_)
+            | BlobMsgPayload::Int8(_) => "int",
+            BlobMsgPayload::Bool(_) => "boolean",
+            BlobMsgPayload::Double(num) if num.is_finite() => "double",
+            BlobMsgPayload::Double(_) | BlobMsgPayload::Unknown(_, _) => "null",
+        };
+        write!(self.out, "json_add_{} ", ty)?;
+        self.write_quoted(name)?;
+        match payload {
+            BlobMsgPayload::Array(list) => {
+                self.out.write_str(";\n")?;
+                for (i, item) in list.iter().enumerate() {
+                    self.write_element(&i.to_string(), &item.data)?;
+                }
+                self.out.write_str("json_close_array;\n")
+            }
+            BlobMsgPayload::Table(table) => {
+                self.out.write_str(";\n")?;
+                for (name, value) in table.iter() {
+                    self.write_element(name, value)?;
+                }
+                self.out.write_str("json_close_object;\n")
+            }
+            BlobMsgPayload::String(s) => {
+                self.out.write_char(' ')?;
+                self.write_quoted(s)?;
+                self.out.write_str(";\n")
+            }
+            BlobMsgPayload::Bytes(bytes) => {
+                self.out.write_char(' ')?;
+                self.write_bytes(bytes)?;
+                self.out.write_str(";\n")
+            }
+            BlobMsgPayload::Int64(num) => writeln!(self.out, " {};", num),
+            BlobMsgPayload::Int32(num) => writeln!(self.out, " {};", num),
+            BlobMsgPayload::Int16(num) => writeln!(self.out, " {};", num),
+            BlobMsgPayload::Int8(num) => writeln!(self.out, " {};", num),
+            BlobMsgPayload::Bool(b) => writeln!(self.out, " {};", *b as u8),
+            BlobMsgPayload::Double(num) if num.is_finite() => writeln!(self.out, " {:.6};", num),
+            BlobMsgPayload::Double(_) | BlobMsgPayload::Unknown(_, _) => self.out.write_str(";\n"),
+        }
+    }
+
+    /// Single quote `s` for the shell, a quote inside becomes `'\''`
+    fn write_quoted(&mut self, s: &str) -> fmt::Result {
+        self.out.write_char('\'')?;
+        for (i, part) in s.split('\'').enumerate() {
+            if i > 0 {
+                self.out.write_str("'\\''")?;
+            }
+            self.out.write_str(part)?;
+        }
+        self.out.write_char('\'')
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        self.out.write_str("$'")?;
+        for chunk in bytes.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                if matches!(c, '\\' | '\'') {
+                    self.out.write_char('\\')?;
+                }
+                self.out.write_char(c)?;
+            }
+            for byte in chunk.invalid() {
+                write!(self.out, "\\x{:02x}", byte)?;
+            }
+        }
+        self.out.write_char('\'')
+    }
+}
+
+/// Parse a `jshn -r` style script back into a table
+///
+/// Understands `json_init`, the `json_add_*` commands and `json_close_object` /
+/// `json_close_array`, with arguments quoted as a POSIX shell would, including `$'...'`.
+/// Strings which are not valid UTF-8 become `Bytes`. Integers become
+/// `INT32` when they fit and `INT64` otherwise, `json_add_null` becomes `UNSPEC`.
+pub fn parse_jshn(script: &str) -> Result<BlobMsgValue, UbusError> {
+    let mut parser = Jshn {
+        open: Vec::new(),
+        current: Container::default(),
+    };
+    let mut words = Words {
+        rest: script,
+        line: 1,
+    };
+    loop {
+        let command = words.command().map_err(|problem| UbusError::Jshn {
+            line: words.line,
+            problem,
+        })?;
+        let Some((line, command)) = command else {
+            break;
+        };
+        parser
+            .command(&command)
+            .map_err(|problem| UbusError::Jshn { line, problem })?;
+    }
+    if !parser.open.is_empty() {
+        return Err(UbusError::Jshn {
+            line: words.line,
+            problem: "unterminated object or array",
+        });
+    }
+    Ok(BlobMsgValue::Table(parser.current.items))
+}
+
+#[derive(Default)]
+struct Container {
+    name: String,
+    array: bool,
+    items: Vec<(String, BlobMsgValue)>,
+}
+
+impl Container {
+    fn into_value(self) -> BlobMsgValue {
+        if self.array {
+            BlobMsgValue::Array(self.items.into_iter().map(|(_, v)| v).collect())
+        } else {
+            BlobMsgValue::Table(self.items)
+        }
+    }
+}
+
+struct Jshn {
+    open: Vec<Container>,
+    current: Container,
+}
+
+impl Jshn {
+    fn command(&mut self, words: &[Word]) -> Result<(), &'static str> {
+        let (command, args) = words.split_first().ok_or("empty command")?;
+        let (name, value) = match args {
+            [] => ("", None),
+            [name] => (text(name)?, None),
+            [name, value] => (text(name)?, Some(value.as_slice())),
+            _ => return Err("too many arguments"),
+        };
+        let value = match (text(command)?, value) {
+            ("json_init", None) if name.is_empty() => {
+                self.open.clear();
+                self.current = Container::default();
+                return Ok(());
+            }
+            ("json_add_object", None) => return self.open(name, false),
+            ("json_add_array", None) => return self.open(name, true),
+            ("json_close_object", None) if name.is_empty() => return self.close(false),
+            ("json_close_array", None) if name.is_empty() => return self.close(true),
+            ("json_add_null", None) => {
+                BlobMsgValue::Unknown(BlobMsgType::UNSPEC.value(), Vec::new())
+            }
+            ("json_add_string", Some(s)) => match core::str::from_utf8(s) {
+                Ok(s) => BlobMsgValue::String(s.to_string()),
+                Err(_) => BlobMsgValue::Bytes(s.to_vec()),
+            },
+            ("json_add_int", Some(num)) => {
+                let num: i64 = text(num)?.parse().map_err(|_| "invalid integer")?;
+                match i32::try_from(num) {
+                    Ok(num) => BlobMsgValue::Int32(num),
+                    Err(_) => BlobMsgValue::Int64(num),
+                }
+            }
+            ("json_add_boolean", Some(b)) => {
+                let b: i64 = text(b)?.parse().map_err(|_| "invalid boolean")?;
+                BlobMsgValue::Bool(b != 0)
+            }
+            ("json_add_double", Some(num)) => {
+                BlobMsgValue::Double(text(num)?.parse().map_err(|_| "invalid double")?)
+            }
+            (
+                "json_init" | "json_add_object" | "json_add_array" | "json_close_object"
+                | "json_close_array" | "json_add_null" | "json_add_string" | "json_add_int"
+                | "json_add_boolean" | "json_add_double",
+                _,
+            ) => return Err("wrong number of arguments"),
+            _ => return Err("unknown command"),
+        };
+        self.current.items.push((name.to_string(), value));
+        Ok(())
+    }
+
+    fn open(&mut self, name: &str, array: bool) -> Result<(), &'static str> {
+        if self.open.len() >= DecodeLimits::DEFAULT.max_depth {
+            return Err("nested too deep");
+        }
+        let parent = core::mem::replace(
+            &mut self.current,
+            Container {
+                name: name.to_string(),
+                array,
+                items: Vec::new(),
+            },
+        );
+        self.open.push(parent);
+        Ok(())
+    }
+
+    fn close(&mut self, array: bool) -> Result<(), &'static str> {
+        if self.open.is_empty() {
+            return Err("nothing to close");
+        }
+        if self.current.array != array {
+            return Err("closes the wrong kind of container");
+        }
+        let parent = self.open.pop().ok_or("nothing to close")?;
+        let mut done = core::mem::replace(&mut self.current, parent);
+        let name = core::mem::take(&mut done.name);
+        self.current.items.push((name, done.into_value()));
+        Ok(())
+    }
+}
+
+/// A shell word, which holds raw bytes after `$'\xXX'` escapes
+type Word = Vec<u8>;
+
+fn text(word: &[u8]) -> Result<&str, &'static str> {
+    core::str::from_utf8(word).map_err(|_| "invalid UTF-8")
+}
+
+/// Append a character to a word
+fn push(word: &mut Vec<u8>, c: char) {
+    word.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+/// Splits a script into commands of shell words
+struct Words<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl Words<'_> {
+    /// The next command and the line it starts on
+    fn command(&mut self) -> Result<Option<(usize, Vec<Word>)>, &'static str> {
+        let mut words = Vec::new();
+        let mut start = self.line;
+        loop {
+            let mut chars = self.rest.chars();
+            let Some(c) = chars.next() else {
+                return Ok((!words.is_empty()).then_some((start, words)));
+            };
+            match c {
+                ';' | '\n' => {
+                    self.rest = chars.as_str();
+                    self.line += (c == '\n') as usize;
+                    if !words.is_empty() {
+                        return Ok(Some((start, words)));
+                    }
+                }
+                ' ' | '\t' | '\r' => self.rest = chars.as_str(),
+                '#' if words.is_empty() => {
+                    let end = self.rest.find('\n').unwrap_or(self.rest.len());
+                    self.rest = &self.rest[end..];
+                }
+                _ => {
+                    if words.is_empty() {
+                        start = self.line;
+                    }
+                    words.push(self.word()?);
+                }
+            }
+        }
+    }
+
+    /// One word, made of bare, single, dollar single and double quoted parts
+    fn word(&mut self) -> Result<Word, &'static str> {
+        let mut word = Vec::new();
+        let mut chars = self.rest.chars();
+        loop {
+            let rest = chars.as_str();
+            match chars.next() {
+                None | Some(' ' | '\t' | '\r' | '\n' | ';') => {
+                    self.rest = rest;
+                    return Ok(word);
+                }
+                Some('\'') => {
+                    let quoted = chars.as_str();
+                    let end = quoted.find('\'').ok_or("unterminated quote")?;
+                    word.extend_from_slice(&quoted.as_bytes()[..end]);
+                    self.line += quoted[..end].matches('\n').count();
+                    chars = quoted[end + 1..].chars();
+                }
+                Some('"') => loop {
+                    match chars.next().ok_or("unterminated quote")? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or("unterminated quote")? {
+                            c @ ('"' | '\\' | '$' | '`') => push(&mut word, c),
+                            '\n' => self.line += 1,
+                            c => {
+                                word.push(b'\\');
+                                push(&mut word, c);
+                            }
+                        },
+                        '$' | '`' => return Err("shell expansion is not supported"),
+                        c => {
+                            self.line += (c == '\n') as usize;
+                            push(&mut word, c);
+                        }
+                    }
+                },
+                Some('\\') => match chars.next().ok_or("trailing backslash")? {
+                    '\n' => self.line += 1,
+                    c => push(&mut word, c),
+                },
+                Some('$') if rest[1..].starts_with('\'') => {
+                    chars.next();
+                    loop {
+                        match chars.next().ok_or("unterminated quote")? {
+                            '\'' => break,
+                            '\\' => match chars.next().ok_or("unterminated quote")? {
+                                'x' => {
+                                    let hex = chars.as_str().get(..2).ok_or("invalid escape")?;
+                                    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                                        return Err("invalid escape");
+                                    }
+                                    word.push(
+                                        u8::from_str_radix(hex, 16)
+                                            .map_err(|_| "invalid escape")?,
+                                    );
+                                    chars = chars.as_str()[2..].chars();
+                                }
+                                'n' => word.push(b'\n'),
+                                't' => word.push(b'\t'),
+                                'r' => word.push(b'\r'),
+                                c @ ('\\' | '\'' | '"') => push(&mut word, c),
+                                _ => return Err("unsupported escape"),
+                            },
+                            c => {
+                                self.line += (c == '\n') as usize;
+                                push(&mut word, c);
+                            }
+                        }
+                    }
+                }
+                Some('$' | '`' | '|' | '&' | '<' | '>' | '(' | ')') => {
+                    return Err("shell syntax is not supported");
+                }
+                Some(c) => push(&mut word, c),
+            }
+        }
+    }
+}
+
+impl BlobMsgTable<'_> {
+    /// Render this table as a `jshn -r` style script
+    pub fn to_jshn(&self) -> String {
+        let mut jshn = JshnWriter::new(String::new());
+        // Writing to a String never fails
+        let _ = jshn.write_object(self);
+        jshn.into_inner()
+    }
+}
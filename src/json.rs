@@ -0,0 +1,107 @@
+use crate::{Blob, BlobIter, BlobMsg, BlobMsgPayload, BlobMsgTable, BlobMsgType, UbusError};
+use core::convert::TryFrom;
+use serde_json::{Map, Number, Value};
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/// Conversions between blobmsg and JSON following libubox `blobmsg_json`:
+///
+/// * booleans are type 7 (`BOOL`), `null` is `UNSPEC`
+/// * integers become `INT32` when they fit, `INT64` otherwise
+/// * fractional numbers become `DOUBLE`
+/// * JSON can not represent unknown types, they become `null`
+/// * objects keep the wire order of their keys, a key given more than once keeps its
+///   first position and its last value, like [`BlobMsgTable::get`]
+/// * JSON strings are UTF-8, so bytes of a string which are not valid UTF-8 are replaced
+///   by U+FFFD like [`String::from_utf8_lossy`]; [`crate::JsonWriter`] keeps them
+impl From<&BlobMsgPayload<'_>> for Value {
+    fn from(payload: &BlobMsgPayload<'_>) -> Self {
+        match payload {
+            BlobMsgPayload::Array(list) => Value::Array(list.iter().map(Value::from).collect()),
+            BlobMsgPayload::Table(table) => Value::Object(
+                table
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), Value::from(v)))
+                    .collect(),
+            ),
+            BlobMsgPayload::String(s) => Value::String(s.to_string()),
+            BlobMsgPayload::Bytes(bytes) => {
+                Value::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+            BlobMsgPayload::Int64(num) => Value::from(*num),
+            BlobMsgPayload::Int32(num) => Value::from(*num),
+            BlobMsgPayload::Int16(num) => Value::from(*num),
+            BlobMsgPayload::Int8(num) => Value::from(*num),
+            BlobMsgPayload::Bool(b) => Value::Bool(*b),
+            BlobMsgPayload::Double(num) => {
+                Number::from_f64(*num).map_or(Value::Null, Value::Number)
+            }
+            BlobMsgPayload::Unknown(_, _) => Value::Null,
+        }
+    }
+}
+
+impl From<BlobMsgPayload<'_>> for Value {
+    fn from(payload: BlobMsgPayload<'_>) -> Self {
+        Value::from(&payload)
+    }
+}
+
+impl From<&BlobMsg<'_>> for Value {
+    fn from(msg: &BlobMsg<'_>) -> Self {
+        Value::from(&msg.data)
+    }
+}
+
+/// Collect the attributes of a blobmsg table without header (e.g. the `DATA` of a reply)
+/// into a JSON object
+impl<'a> TryFrom<BlobIter<'a, Blob<'a>>> for Value {
+    type Error = UbusError;
+    fn try_from(iter: BlobIter<'a, Blob<'a>>) -> Result<Self, Self::Error> {
+        let mut object = Map::new();
+        for blob in iter {
+            let msg: BlobMsg = blob?.try_into()?;
+            object.insert(msg.name.to_string(), Value::from(&msg.data));
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for BlobMsgPayload<'a> {
+    type Error = UbusError;
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Null => BlobMsgPayload::Unknown(BlobMsgType::UNSPEC.value(), &[]),
+            Value::Bool(b) => BlobMsgPayload::Bool(*b),
+            Value::Number(num) => {
+                if let Some(num) = num.as_i64() {
+                    match i32::try_from(num) {
+                        Ok(num) => BlobMsgPayload::Int32(num),
+                        Err(_) => BlobMsgPayload::Int64(num),
+                    }
+                } else if num.is_u64() {
+                    return Err(UbusError::InvalidData("JSON integer does not fit INT64"));
+                } else {
+                    BlobMsgPayload::Double(num.as_f64().unwrap_or_default())
+                }
+            }
+            Value::String(s) => BlobMsgPayload::String(s),
+            Value::Array(list) => BlobMsgPayload::Array(
+                list.iter()
+                    .map(|item| {
+                        Ok(BlobMsg {
+                            name: "",
+                            data: BlobMsgPayload::try_from(item)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, UbusError>>()?,
+            ),
+            Value::Object(object) => BlobMsgPayload::Table(
+                object
+                    .iter()
+                    .map(|(k, v)| Ok((k.as_str(), BlobMsgPayload::try_from(v)?)))
+                    .collect::<Result<BlobMsgTable, UbusError>>()?,
+            ),
+        })
+    }
+}
@@ -57,74 +57,24 @@ macro_rules! values {
     };
 }
 
-macro_rules! invalid_data_panic {
-    ($($arg:tt)*) => (if cfg!(debug_assertions) { panic!($($arg)*); })
-}
-
 macro_rules! valid_data {
     (($left:expr) >= ($right:expr), $msg:literal) => {{
         if !(($left) >= ($right)) {
-            invalid_data_panic!("Invalid Data: {} ({:?} < {:?})", $msg, $left, $right);
-            return Err(Error::InvalidData($msg));
+            return Err(UbusError::InvalidData($msg));
         }
     }};
     (($left:expr) == ($right:expr), $msg:literal) => {{
         if !(($left) == ($right)) {
-            invalid_data_panic!("Invalid Data: {} ({:?} != {:?})", $msg, $left, $right);
-            return Err(Error::InvalidData($msg));
+            return Err(UbusError::InvalidData($msg));
         }
     }};
     ($thing:expr, $msg:literal) => {{
         if !($thing) {
-            invalid_data_panic!("Invalid Data: {}", $msg);
             return Err(UbusError::InvalidData($msg));
         }
     }};
 }
 
-#[derive(Debug)]
-pub enum NoIO {}
-impl core::fmt::Display for NoIO {
-    fn fmt(&self, _f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        unreachable!()
-    }
-}
-
-#[derive(Debug)]
-pub enum Error<T = NoIO> {
-    IO(T),
-    InvalidData(&'static str),
-    Status(i32),
-}
-
-impl<T: core::fmt::Display> core::fmt::Display for Error<T> {
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        use Error::*;
-        match self {
-            IO(e) => write!(f, "IO Error: {}", e),
-            InvalidData(e) => write!(f, "Invalid Data: {}", e),
-            Status(e) => write!(f, "Ubus Status: {}", e),
-        }
-    }
-}
-
-impl<T: IOError> From<Error<NoIO>> for Error<T> {
-    fn from(e: Error<NoIO>) -> Self {
-        use Error::*;
-        match e {
-            IO(_) => unreachable!(),
-            InvalidData(v) => InvalidData(v),
-            Status(v) => Status(v),
-        }
-    }
-}
-
-impl<T> From<core::convert::Infallible> for Error<T> {
-    fn from(_: core::convert::Infallible) -> Self {
-        unreachable!()
-    }
-}
-
 pub trait IOError {}
 
 pub trait IO {
@@ -134,16 +84,39 @@ pub trait IO {
 }
 
 mod blob;
+mod blobattr;
 mod blobmsg;
+mod blobmsgbinary;
+mod blobmsgdiff;
+mod blobmsgjson;
+mod blobmsgpath;
+mod blobmsgpolicy;
+mod blobmsgview;
+mod blobmsgvisit;
 mod connection;
+mod decodecontext;
+mod jshn;
+mod json;
+mod ubuscmd;
 mod ubuserror;
 mod ubusmsg;
 mod ubusobj;
 mod usock;
 
 pub use blob::*;
+pub use blobattr::*;
 pub use blobmsg::*;
+pub use blobmsgbinary::*;
+pub use blobmsgdiff::*;
+pub use blobmsgjson::*;
+pub use blobmsgpath::*;
+pub use blobmsgpolicy::*;
+pub use blobmsgview::*;
+pub use blobmsgvisit::*;
 pub use connection::*;
+pub use decodecontext::*;
+pub use jshn::*;
+pub use ubuscmd::*;
 pub use ubuserror::*;
 pub use ubusmsg::*;
 pub use ubusobj::*;
@@ -0,0 +1,233 @@
+use crate::{
+    Blob, BlobAttrId, BlobIter, BlobSchema, UbusCmdType, UbusError, UbusMsg, UbusMsgAttr,
+    UbusMsgBuilder, UbusMsgHeader, UbusStatus, blob_schema,
+};
+use core::convert::TryFrom;
+
+blob_schema! {
+    /// Result of a request, the last message sent for it
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct StatusMsg {
+        BlobAttrId::STATUS => pub code: UbusStatus,
+        BlobAttrId::OBJID => pub obj: Option<u32>,
+    }
+}
+
+blob_schema! {
+    /// One reply to an invoke, blobmsg attributes in `data`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct DataMsg<'a> {
+        BlobAttrId::OBJID => pub obj: Option<u32>,
+        BlobAttrId::DATA => pub data: &'a [u8],
+    }
+}
+
+blob_schema! {
+    /// Look up objects, all of them without `path`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct LookupMsg<'a> {
+        BlobAttrId::OBJPATH => pub path: Option<&'a str>,
+    }
+}
+
+blob_schema! {
+    /// One object found by a lookup, its methods as a blobmsg table in `signature`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct LookupReplyMsg<'a> {
+        BlobAttrId::OBJPATH => pub path: &'a str,
+        BlobAttrId::OBJID => pub id: u32,
+        BlobAttrId::OBJTYPE => pub ty: u32,
+        BlobAttrId::SIGNATURE => pub signature: &'a [u8],
+    }
+}
+
+blob_schema! {
+    /// Call `method` of `obj` with blobmsg arguments in `data`, `user` and `group` are
+    /// filled in by ubusd for ACL checks
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct InvokeMsg<'a> {
+        BlobAttrId::OBJID => pub obj: u32,
+        BlobAttrId::METHOD => pub method: &'a str,
+        BlobAttrId::DATA => pub data: Option<&'a [u8]>,
+        BlobAttrId::USER => pub user: Option<&'a str>,
+        BlobAttrId::GROUP => pub group: Option<&'a str>,
+    }
+}
+
+blob_schema! {
+    /// Register an object, either with a new type from `signature` or an existing `ty`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct AddObjectMsg<'a> {
+        BlobAttrId::OBJPATH => pub path: Option<&'a str>,
+        BlobAttrId::OBJTYPE => pub ty: Option<u32>,
+        BlobAttrId::SIGNATURE => pub signature: Option<&'a [u8]>,
+    }
+}
+
+blob_schema! {
+    /// Id and type ubusd gave an added object
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct ObjectAddedMsg {
+        BlobAttrId::OBJID => pub id: u32,
+        BlobAttrId::OBJTYPE => pub ty: Option<u32>,
+    }
+}
+
+blob_schema! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct RemoveObjectMsg {
+        BlobAttrId::OBJID => pub obj: u32,
+    }
+}
+
+blob_schema! {
+    /// Subscribe the object `obj` to notifications of `target`, or unsubscribe it
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SubscribeMsg {
+        BlobAttrId::OBJID => pub obj: u32,
+        BlobAttrId::TARGET => pub target: u32,
+    }
+}
+
+blob_schema! {
+    /// Notification sent by `obj` to its subscribers
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct NotifyMsg<'a> {
+        BlobAttrId::OBJID => pub obj: u32,
+        BlobAttrId::METHOD => pub method: &'a str,
+        BlobAttrId::DATA => pub data: Option<&'a [u8]>,
+        BlobAttrId::NO_REPLY => pub no_reply: Option<bool>,
+    }
+}
+
+impl StatusMsg {
+    /// `Ok` for [`UbusStatus::OK`], the status as error otherwise
+    pub fn result(&self) -> Result<(), UbusError> {
+        match self.code {
+            UbusStatus::OK => Ok(()),
+            code => Err(UbusError::Status(code)),
+        }
+    }
+}
+
+/// A ubus message decoded according to its command
+///
+/// Parsing checks the attributes ubusd requires for each command are present and have the
+/// right type, other attributes are ignored. Building is the reverse, see
+/// [`Self::to_message`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UbusCmd<'a> {
+    Hello,
+    Status(StatusMsg),
+    Data(DataMsg<'a>),
+    /// Sent as a `DATA` message
+    LookupReply(LookupReplyMsg<'a>),
+    /// Sent as a `DATA` message
+    ObjectAdded(ObjectAddedMsg),
+    Ping,
+    Lookup(LookupMsg<'a>),
+    Invoke(InvokeMsg<'a>),
+    AddObject(AddObjectMsg<'a>),
+    RemoveObject(RemoveObjectMsg),
+    Subscribe(SubscribeMsg),
+    Unsubscribe(SubscribeMsg),
+    Notify(NotifyMsg<'a>),
+    /// A command without typed form, e.g. `MONITOR`, and its raw attributes
+    Other(UbusCmdType, &'a [u8]),
+}
+
+impl<'a> UbusCmd<'a> {
+    /// Decode the attributes of a message sent as `cmd`
+    pub fn parse(cmd: UbusCmdType, data: &'a [u8]) -> Result<Self, UbusError> {
+        Ok(match cmd {
+            UbusCmdType::HELLO => UbusCmd::Hello,
+            UbusCmdType::STATUS => UbusCmd::Status(BlobSchema::decode(data)?),
+            UbusCmdType::DATA => {
+                // Replies to a lookup or an added object are told apart by their attributes
+                let has = |id: BlobAttrId| {
+                    BlobIter::<Blob>::new(data)
+                        .flatten()
+                        .any(|blob| blob.tag.id() == id.value())
+                };
+                if has(BlobAttrId::DATA) {
+                    UbusCmd::Data(BlobSchema::decode(data)?)
+                } else if has(BlobAttrId::OBJPATH) {
+                    UbusCmd::LookupReply(BlobSchema::decode(data)?)
+                } else if has(BlobAttrId::OBJID) {
+                    UbusCmd::ObjectAdded(BlobSchema::decode(data)?)
+                } else {
+                    UbusCmd::Data(BlobSchema::decode(data)?)
+                }
+            }
+            UbusCmdType::PING => UbusCmd::Ping,
+            UbusCmdType::LOOKUP => UbusCmd::Lookup(BlobSchema::decode(data)?),
+            UbusCmdType::INVOKE => UbusCmd::Invoke(BlobSchema::decode(data)?),
+            UbusCmdType::ADD_OBJECT => UbusCmd::AddObject(BlobSchema::decode(data)?),
+            UbusCmdType::REMOVE_OBJECT => UbusCmd::RemoveObject(BlobSchema::decode(data)?),
+            UbusCmdType::SUBSCRIBE => UbusCmd::Subscribe(BlobSchema::decode(data)?),
+            UbusCmdType::UNSUBSCRIBE => UbusCmd::Unsubscribe(BlobSchema::decode(data)?),
+            UbusCmdType::NOTIFY => UbusCmd::Notify(BlobSchema::decode(data)?),
+            cmd => UbusCmd::Other(cmd, data),
+        })
+    }
+
+    pub fn cmd_type(&self) -> UbusCmdType {
+        match self {
+            UbusCmd::Hello => UbusCmdType::HELLO,
+            UbusCmd::Status(_) => UbusCmdType::STATUS,
+            UbusCmd::Data(_) | UbusCmd::LookupReply(_) | UbusCmd::ObjectAdded(_) => {
+                UbusCmdType::DATA
+            }
+            UbusCmd::Ping => UbusCmdType::PING,
+            UbusCmd::Lookup(_) => UbusCmdType::LOOKUP,
+            UbusCmd::Invoke(_) => UbusCmdType::INVOKE,
+            UbusCmd::AddObject(_) => UbusCmdType::ADD_OBJECT,
+            UbusCmd::RemoveObject(_) => UbusCmdType::REMOVE_OBJECT,
+            UbusCmd::Subscribe(_) => UbusCmdType::SUBSCRIBE,
+            UbusCmd::Unsubscribe(_) => UbusCmdType::UNSUBSCRIBE,
+            UbusCmd::Notify(_) => UbusCmdType::NOTIFY,
+            UbusCmd::Other(cmd, _) => *cmd,
+        }
+    }
+
+    /// Build this command into `buffer`, using `header` with its command type replaced by
+    /// [`Self::cmd_type`]
+    pub fn to_message<'b>(
+        &self,
+        buffer: &'b mut [u8],
+        header: &UbusMsgHeader,
+    ) -> Result<UbusMsgBuilder<'b>, UbusError> {
+        let header = UbusMsgHeader {
+            cmd_type: self.cmd_type(),
+            ..*header
+        };
+        let mut message = UbusMsgBuilder::new(buffer, &header)?;
+        match self {
+            UbusCmd::Hello | UbusCmd::Ping => {}
+            UbusCmd::Status(msg) => message.put_schema(msg)?,
+            UbusCmd::Data(msg) => message.put_schema(msg)?,
+            UbusCmd::LookupReply(msg) => message.put_schema(msg)?,
+            UbusCmd::ObjectAdded(msg) => message.put_schema(msg)?,
+            UbusCmd::Lookup(msg) => message.put_schema(msg)?,
+            UbusCmd::Invoke(msg) => message.put_schema(msg)?,
+            UbusCmd::AddObject(msg) => message.put_schema(msg)?,
+            UbusCmd::RemoveObject(msg) => message.put_schema(msg)?,
+            UbusCmd::Subscribe(msg) | UbusCmd::Unsubscribe(msg) => message.put_schema(msg)?,
+            UbusCmd::Notify(msg) => message.put_schema(msg)?,
+            UbusCmd::Other(_, data) => {
+                for blob in BlobIter::<Blob>::new(data) {
+                    let blob = blob?;
+                    message.put(UbusMsgAttr::Unknown(blob.tag.id().into(), blob.data))?;
+                }
+            }
+        }
+        Ok(message)
+    }
+}
+
+impl<'a> TryFrom<UbusMsg<'a>> for UbusCmd<'a> {
+    type Error = UbusError;
+    fn try_from(message: UbusMsg<'a>) -> Result<Self, Self::Error> {
+        Self::parse(message.header.cmd_type, message.blob.data)
+    }
+}
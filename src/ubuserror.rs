@@ -1,10 +1,63 @@
 extern crate alloc;
+use core::convert::Infallible;
 use core::str::Utf8Error;
 use std::io;
 
+use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
+use alloc::vec::Vec;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
 
+use crate::BlobMsgType;
+
+values!(pub UbusStatus(i32) {
+    OK                = 0,
+    INVALID_COMMAND   = 1,
+    INVALID_ARGUMENT  = 2,
+    METHOD_NOT_FOUND  = 3,
+    NOT_FOUND         = 4,
+    NO_DATA           = 5,
+    PERMISSION_DENIED = 6,
+    TIMEOUT           = 7,
+    NOT_SUPPORTED     = 8,
+    UNKNOWN_ERROR     = 9,
+    CONNECTION_FAILED = 10,
+    NO_MEMORY         = 11,
+    PARSE_ERROR       = 12,
+    SYSTEM_ERROR      = 13,
+});
+
+impl UbusStatus {
+    /// The message `ubus_strerror()` gives for this status
+    pub fn message(self) -> &'static str {
+        match self {
+            UbusStatus::OK => "Success",
+            UbusStatus::INVALID_COMMAND => "Invalid command",
+            UbusStatus::INVALID_ARGUMENT => "Invalid argument",
+            UbusStatus::METHOD_NOT_FOUND => "Method not found",
+            UbusStatus::NOT_FOUND => "Not found",
+            UbusStatus::NO_DATA => "No response",
+            UbusStatus::PERMISSION_DENIED => "Permission denied",
+            UbusStatus::TIMEOUT => "Request timed out",
+            UbusStatus::NOT_SUPPORTED => "Operation not supported",
+            UbusStatus::CONNECTION_FAILED => "Connection failed",
+            UbusStatus::NO_MEMORY => "Out of memory",
+            UbusStatus::PARSE_ERROR => "Parsing message data failed",
+            UbusStatus::SYSTEM_ERROR => "System error",
+            _ => "Unknown error",
+        }
+    }
+}
+
+impl core::fmt::Display for UbusStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} ({})", self.message(), self.value())
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum UbusError {
     #[error("io error")]
@@ -13,10 +66,141 @@ pub enum UbusError {
     Utf8(#[from] Utf8Error),
     #[error("Invalid Data")]
     InvalidData(&'static str),
-    #[error("Ubus return ErrorCode({0})")]
-    Status(i32),
+    #[error("Ubus status: {0}")]
+    Status(UbusStatus),
     #[error("Error parse arguments string:{0}")]
     ParseArguments(#[from] serde_json::Error),
+    #[error("Invalid reply: {0}")]
+    ParseReply(serde_json::Error),
     #[error("Invalid method:{0}")]
     InvalidMethod(String),
+    #[error("Missing required attribute:{0}")]
+    MissingAttribute(String),
+    #[error("Attribute {name} has type {found:?}, expected {expected:?}")]
+    AttributeType {
+        name: String,
+        expected: BlobMsgType,
+        found: BlobMsgType,
+    },
+    #[error("Payload too short: {needed} bytes needed, {found} found")]
+    PayloadTooShort { needed: usize, found: usize },
+    #[error("Nesting deeper than {0} levels")]
+    NestingTooDeep(usize),
+    #[error("More than {0} attributes")]
+    TooManyAttributes(usize),
+    #[error("Message of {size} bytes exceeds the limit of {max}")]
+    MessageTooLarge { size: usize, max: usize },
+    #[error("Invalid argument {name}: {problem}")]
+    InvalidArgument {
+        name: String,
+        problem: ArgumentProblem,
+    },
+    #[error("{0}")]
+    InvalidArguments(ArgumentIssues),
+    #[error("Invalid ubus message attribute {id}: {source}")]
+    InvalidAttribute { id: u32, source: Box<UbusError> },
+    #[error("jshn line {line}: {problem}")]
+    Jshn { line: usize, problem: &'static str },
+    #[error("Path position {position}: {problem}")]
+    Path {
+        position: usize,
+        problem: &'static str,
+    },
+    #[error("Formatter error")]
+    Format(#[from] core::fmt::Error),
+    /// An error of an invoke or call, with what was being called
+    #[error("Calling {method} on {}: {source}", object_name(.path, .obj))]
+    Call {
+        path: Option<String>,
+        /// Id of the object, the peer of the request, if it got that far
+        obj: Option<u32>,
+        method: String,
+        source: Box<UbusError>,
+    },
+}
+
+impl UbusError {
+    /// The status the peer returned, also from within a [`UbusError::Call`]
+    pub fn status(&self) -> Option<UbusStatus> {
+        match self {
+            UbusError::Status(status) => Some(*status),
+            UbusError::Call { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+}
+
+fn object_name(path: &Option<String>, obj: &Option<u32>) -> String {
+    match (path, obj) {
+        (Some(path), _) => path.clone(),
+        (None, Some(obj)) => format!("0x{:08x}", obj),
+        (None, None) => String::from("?"),
+    }
+}
+
+/// Why a JSON value could not be converted to the type of a method argument
+#[derive(Debug, Clone, PartialEq, Error, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArgumentProblem {
+    #[error("{value} does not fit {expected:?}")]
+    Overflow {
+        #[serde(serialize_with = "type_name")]
+        expected: BlobMsgType,
+        value: String,
+    },
+    #[error("expected {expected:?}, found {found}")]
+    WrongType {
+        #[serde(serialize_with = "type_name")]
+        expected: BlobMsgType,
+        found: &'static str,
+    },
+    #[error("{0} is not an integer")]
+    #[serde(serialize_with = "not_integer")]
+    NotInteger(f64),
+    #[error("not in the method signature")]
+    Unknown,
+    #[error("required but missing")]
+    Missing,
+    #[error("{reason}")]
+    Unencodable { reason: String },
+}
+
+fn type_name<S: Serializer>(ty: &BlobMsgType, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{:?}", ty))
+}
+
+/// A tagged variant needs named fields, the number goes into `value`
+fn not_integer<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut problem = serializer.serialize_struct("NotInteger", 1)?;
+    problem.serialize_field("value", value)?;
+    problem.end()
+}
+
+/// A problem with one argument found while validating a call
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArgumentIssue {
+    pub name: String,
+    pub problem: ArgumentProblem,
+}
+
+/// Every problem found while validating the arguments of a call
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ArgumentIssues(pub Vec<ArgumentIssue>);
+
+impl core::fmt::Display for ArgumentIssues {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Invalid arguments")?;
+        for (i, issue) in self.0.iter().enumerate() {
+            let sep = if i == 0 { ":" } else { "," };
+            write!(f, "{} {}: {}", sep, issue.name, issue.problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Infallible> for UbusError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
 }
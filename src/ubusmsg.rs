@@ -1,8 +1,11 @@
-use crate::{Blob, BlobBuilder, BlobIter, BlobMsgPayload, BlobTag, IO, Payload, UbusError};
-use core::convert::TryInto;
+use crate::{
+    Blob, BlobAttrData, BlobBuilder, BlobFieldId, BlobIter, BlobMsg, BlobMsgTable, BlobSchema,
+    BlobTag, DecodeLimits, IO, Payload, UbusError, UbusStatus,
+};
+use core::convert::TryFrom;
 use core::mem::{size_of, transmute};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::boxed::Box;
 use storage_endian::{BEu16, BEu32};
 
 values!(pub UbusMsgVersion(u8) {
@@ -41,6 +44,12 @@ values!(pub BlobAttrId(u32) {
     GROUP       = 0x0d,
 });
 
+impl BlobFieldId for BlobAttrId {
+    fn id(self) -> u32 {
+        self.value()
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub struct UbusMsgHeader {
@@ -71,27 +80,54 @@ pub struct UbusMsg<'a> {
 
 impl<'a> UbusMsg<'a> {
     pub fn from_io<T: IO>(io: &mut T, buffer: &'a mut [u8]) -> Result<Self, UbusError> {
+        Self::from_io_with(io, buffer, &DecodeLimits::default())
+    }
+
+    /// Receive a message, rejecting it before reading the body if it exceeds `limits`
+    pub fn from_io_with<T: IO>(
+        io: &mut T,
+        buffer: &'a mut [u8],
+        limits: &DecodeLimits,
+    ) -> Result<Self, UbusError> {
+        valid_data!(
+            buffer.len() >= (UbusMsgHeader::SIZE + BlobTag::SIZE),
+            "Receive buffer is too small"
+        );
         let (pre_buffer, buffer) = buffer.split_at_mut(UbusMsgHeader::SIZE + BlobTag::SIZE);
 
         // Read in the message header and the following blob tag
         io.get(pre_buffer)?;
 
-        let (header, tag) = pre_buffer.split_at(UbusMsgHeader::SIZE);
+        let Some((header, tag)) = pre_buffer.split_first_chunk::<{ UbusMsgHeader::SIZE }>() else {
+            return Err(UbusError::InvalidData("Message header too short"));
+        };
+        let Some(tag) = tag.first_chunk::<{ BlobTag::SIZE }>() else {
+            return Err(UbusError::InvalidData("Message blob tag too short"));
+        };
 
-        let header = UbusMsgHeader::from_bytes(header.try_into().unwrap());
+        let header = UbusMsgHeader::from_bytes(*header);
         valid_data!(header.version == UbusMsgVersion::CURRENT, "Wrong version");
 
-        let tag = BlobTag::from_bytes(tag.try_into().unwrap());
+        let tag = BlobTag::from_bytes(*tag);
         tag.is_valid()?;
+        let size = UbusMsgHeader::SIZE + tag.size();
+        if size > limits.max_message_len {
+            return Err(UbusError::MessageTooLarge {
+                size,
+                max: limits.max_message_len,
+            });
+        }
 
         // Get a slice the size of the blob's data bytes (do we need to worry about padding here?)
-        let data = &mut buffer[..tag.inner_len()];
+        let Some(data) = buffer.get_mut(..tag.inner_len()) else {
+            return Err(UbusError::InvalidData("Message larger than receive buffer"));
+        };
 
         // Receive data into slice
         io.get(data)?;
 
         // Create the blob from our parts
-        let blob = Blob::from_tag_and_data(tag, data).unwrap();
+        let blob = Blob::from_tag_and_data(tag, data)?;
 
         Ok(UbusMsg { header, blob })
     }
@@ -111,74 +147,116 @@ impl core::fmt::Debug for UbusMsg<'_> {
 }
 
 pub struct UbusMsgBuilder<'a> {
-    buffer: &'a mut [u8],
-    offset: usize,
+    blob: BlobBuilder<'a>,
 }
 
 impl<'a> UbusMsgBuilder<'a> {
     pub fn new(buffer: &'a mut [u8], header: &UbusMsgHeader) -> Result<Self, UbusError> {
-        valid_data!(
-            buffer.len() >= (UbusMsgHeader::SIZE + BlobTag::SIZE),
-            "Builder buffer is too small"
-        );
-
-        let header_buf = &mut buffer[..UbusMsgHeader::SIZE];
-        let header_buf: &mut [u8; UbusMsgHeader::SIZE] = header_buf.try_into().unwrap();
+        let Some(header_buf) = buffer.first_chunk_mut() else {
+            return Err(UbusError::InvalidData("Builder buffer is too small"));
+        };
         *header_buf = header.to_bytes();
 
         let offset = UbusMsgHeader::SIZE + BlobTag::SIZE;
+        valid_data!(buffer.len() >= offset, "Builder buffer is too small");
+        // More would not fit into the size of the message blob
+        let end = buffer.len().min(UbusMsgHeader::SIZE + BlobTag::MAX_SIZE);
+        let buffer = &mut buffer[..end];
 
-        Ok(Self { buffer, offset })
+        Ok(Self {
+            blob: BlobBuilder::from_bytes_at(buffer, offset),
+        })
     }
 
     pub fn put(&mut self, attr: UbusMsgAttr) -> Result<(), UbusError> {
-        let mut blob = BlobBuilder::from_bytes(&mut self.buffer[self.offset..]);
+        let blob = &mut self.blob;
 
         match attr {
-            UbusMsgAttr::Status(val) => blob.push_u32(BlobAttrId::STATUS.value(), val as u32)?,
-            UbusMsgAttr::ObjPath(val) => blob.push_str(BlobAttrId::OBJPATH.value(), val)?,
-            UbusMsgAttr::ObjId(val) => blob.push_u32(BlobAttrId::OBJID.value(), val)?,
-            UbusMsgAttr::Method(val) => blob.push_str(BlobAttrId::METHOD.value(), val)?,
-            //UbusMsgAttr::ObjType(val) => blob.push_u32(BlobAttrId::STATUS.value(), val)?,
-            UbusMsgAttr::ObjType(val) => blob.push_u32(BlobAttrId::OBJTYPE.value(), val)?,
-            UbusMsgAttr::Signature(_) => unimplemented!(),
+            UbusMsgAttr::Status(val) => val.encode(blob, BlobAttrId::STATUS.value())?,
+            UbusMsgAttr::ObjPath(val) => val.encode(blob, BlobAttrId::OBJPATH.value())?,
+            UbusMsgAttr::ObjId(val) => val.encode(blob, BlobAttrId::OBJID.value())?,
+            UbusMsgAttr::Method(val) => val.encode(blob, BlobAttrId::METHOD.value())?,
+            UbusMsgAttr::ObjType(val) => val.encode(blob, BlobAttrId::OBJTYPE.value())?,
+            UbusMsgAttr::Signature(table) => {
+                blob.open(BlobAttrId::SIGNATURE.value())?;
+                for (name, payload) in table.iter() {
+                    blob.push_payload(name, payload)?;
+                }
+                blob.close()?
+            }
             UbusMsgAttr::Data(val) => blob.push_bytes(BlobAttrId::DATA.value(), val)?,
-            UbusMsgAttr::Target(val) => blob.push_u32(BlobAttrId::TARGET.value(), val)?,
-            UbusMsgAttr::Active(val) => blob.push_bool(BlobAttrId::ACTIVE.value(), val)?,
-            UbusMsgAttr::NoReply(val) => blob.push_bool(BlobAttrId::NO_REPLY.value(), val)?,
-            UbusMsgAttr::Subscribers(_) => unimplemented!(),
-            UbusMsgAttr::User(val) => blob.push_str(BlobAttrId::USER.value(), val)?,
-            UbusMsgAttr::Group(val) => blob.push_str(BlobAttrId::GROUP.value(), val)?,
+            UbusMsgAttr::Target(val) => val.encode(blob, BlobAttrId::TARGET.value())?,
+            UbusMsgAttr::Active(val) => val.encode(blob, BlobAttrId::ACTIVE.value())?,
+            UbusMsgAttr::NoReply(val) => val.encode(blob, BlobAttrId::NO_REPLY.value())?,
+            UbusMsgAttr::Subscribers(iter) => iter.encode(blob, BlobAttrId::SUBSCRIBERS.value())?,
+            UbusMsgAttr::User(val) => val.encode(blob, BlobAttrId::USER.value())?,
+            UbusMsgAttr::Group(val) => val.encode(blob, BlobAttrId::GROUP.value())?,
             UbusMsgAttr::Unknown(id, val) => blob.push_bytes(id.value(), val)?,
         };
 
-        self.offset += blob.len();
         Ok(())
     }
 
-    pub fn finish(self) -> &'a [u8] {
+    /// Write the fields of `schema` as attributes of this message
+    pub fn put_schema<'b>(&mut self, schema: &impl BlobSchema<'b>) -> Result<(), UbusError> {
+        schema.encode(&mut self.blob)
+    }
+
+    /// Open a nested attribute such as `DATA`, following attributes go inside it
+    /// until [`Self::close`]
+    pub fn open(&mut self, id: BlobAttrId) -> Result<(), UbusError> {
+        self.blob.open(id.value())
+    }
+
+    /// Open a blobmsg table inside the current nested attribute
+    pub fn open_table(&mut self, name: &str) -> Result<(), UbusError> {
+        self.blob.open_table(name)
+    }
+
+    /// Open a blobmsg array inside the current nested attribute
+    pub fn open_array(&mut self, name: &str) -> Result<(), UbusError> {
+        self.blob.open_array(name)
+    }
+
+    /// Close the innermost open attribute, table or array
+    pub fn close(&mut self) -> Result<(), UbusError> {
+        self.blob.close()
+    }
+
+    /// Write a blobmsg inside the current nested attribute
+    pub fn put_blobmsg(&mut self, msg: &BlobMsg) -> Result<(), UbusError> {
+        self.blob.push_blobmsg(msg)
+    }
+
+    /// Attributes left open are closed
+    pub fn finish(mut self) -> Result<&'a [u8], UbusError> {
+        while self.blob.depth() > 0 {
+            self.blob.close()?;
+        }
+        let (buffer, offset) = self.blob.into_parts();
         // Update tag with correct size
-        let tag = BlobTag::new(0, self.offset - UbusMsgHeader::SIZE, false).unwrap();
-        let tag_buf = &mut self.buffer[UbusMsgHeader::SIZE..UbusMsgHeader::SIZE + BlobTag::SIZE];
-        let tag_buf: &mut [u8; BlobTag::SIZE] = tag_buf.try_into().unwrap();
-        *tag_buf = tag.to_bytes();
-        &self.buffer[..self.offset]
+        let mut tag = BlobTag::from_bytes([0; BlobTag::SIZE]);
+        tag.set_size(offset - UbusMsgHeader::SIZE)?;
+        buffer[UbusMsgHeader::SIZE..UbusMsgHeader::SIZE + BlobTag::SIZE]
+            .copy_from_slice(&tag.to_bytes());
+        Ok(&buffer[..offset])
     }
 }
-impl<'a> Into<&'a [u8]> for UbusMsgBuilder<'a> {
-    fn into(self) -> &'a [u8] {
-        self.finish()
+impl<'a> TryFrom<UbusMsgBuilder<'a>> for &'a [u8] {
+    type Error = UbusError;
+    fn try_from(message: UbusMsgBuilder<'a>) -> Result<Self, Self::Error> {
+        message.finish()
     }
 }
 
 #[derive(Debug)]
 pub enum UbusMsgAttr<'a> {
-    Status(i32),
+    Status(UbusStatus),
     ObjPath(&'a str),
     ObjId(u32),
     Method(&'a str),
     ObjType(u32),
-    Signature(HashMap<&'a str, BlobMsgPayload<'a>>),
+    Signature(BlobMsgTable<'a>),
     Data(&'a [u8]),
     Target(u32),
     Active(bool),
@@ -189,24 +267,41 @@ pub enum UbusMsgAttr<'a> {
     Unknown(BlobAttrId, &'a [u8]),
 }
 
-impl<'a> From<Blob<'a>> for UbusMsgAttr<'a> {
-    fn from(blob: Blob<'a>) -> Self {
-        let payload = Payload::from(blob.data);
-        match blob.tag.id().into() {
-            BlobAttrId::STATUS => UbusMsgAttr::Status(payload.try_into().unwrap()),
-            BlobAttrId::OBJPATH => UbusMsgAttr::ObjPath(payload.try_into().unwrap()),
-            BlobAttrId::OBJID => UbusMsgAttr::ObjId(payload.try_into().unwrap()),
-            BlobAttrId::METHOD => UbusMsgAttr::Method(payload.try_into().unwrap()),
-            BlobAttrId::OBJTYPE => UbusMsgAttr::ObjType(payload.try_into().unwrap()),
-            BlobAttrId::SIGNATURE => UbusMsgAttr::Signature(payload.try_into().unwrap()),
-            BlobAttrId::DATA => UbusMsgAttr::Data(payload.try_into().unwrap()),
-            BlobAttrId::TARGET => UbusMsgAttr::Target(payload.try_into().unwrap()),
-            BlobAttrId::ACTIVE => UbusMsgAttr::Active(payload.try_into().unwrap()),
-            BlobAttrId::NO_REPLY => UbusMsgAttr::NoReply(payload.try_into().unwrap()),
-            BlobAttrId::SUBSCRIBERS => UbusMsgAttr::Subscribers(payload.try_into().unwrap()),
-            BlobAttrId::USER => UbusMsgAttr::User(payload.try_into().unwrap()),
-            BlobAttrId::GROUP => UbusMsgAttr::Group(payload.try_into().unwrap()),
-            id => UbusMsgAttr::Unknown(id, blob.data.into()),
-        }
+impl<'a> TryFrom<Blob<'a>> for UbusMsgAttr<'a> {
+    type Error = UbusError;
+    fn try_from(blob: Blob<'a>) -> Result<Self, Self::Error> {
+        Self::decode_with(blob, &DecodeLimits::default())
+    }
+}
+
+impl<'a> UbusMsgAttr<'a> {
+    /// Decode an attribute, enforcing `limits` on the blobmsg tree of a signature
+    pub fn decode_with(blob: Blob<'a>, limits: &DecodeLimits) -> Result<Self, UbusError> {
+        let data = blob.data;
+        let id = blob.tag.id();
+        let attr = || -> Result<Self, UbusError> {
+            Ok(match id.into() {
+                BlobAttrId::STATUS => UbusMsgAttr::Status(BlobAttrData::decode(data)?),
+                BlobAttrId::OBJPATH => UbusMsgAttr::ObjPath(BlobAttrData::decode(data)?),
+                BlobAttrId::OBJID => UbusMsgAttr::ObjId(BlobAttrData::decode(data)?),
+                BlobAttrId::METHOD => UbusMsgAttr::Method(BlobAttrData::decode(data)?),
+                BlobAttrId::OBJTYPE => UbusMsgAttr::ObjType(BlobAttrData::decode(data)?),
+                BlobAttrId::SIGNATURE => {
+                    UbusMsgAttr::Signature(Payload::from(data).table_with(limits)?)
+                }
+                BlobAttrId::DATA => UbusMsgAttr::Data(data),
+                BlobAttrId::TARGET => UbusMsgAttr::Target(BlobAttrData::decode(data)?),
+                BlobAttrId::ACTIVE => UbusMsgAttr::Active(BlobAttrData::decode(data)?),
+                BlobAttrId::NO_REPLY => UbusMsgAttr::NoReply(BlobAttrData::decode(data)?),
+                BlobAttrId::SUBSCRIBERS => UbusMsgAttr::Subscribers(BlobAttrData::decode(data)?),
+                BlobAttrId::USER => UbusMsgAttr::User(BlobAttrData::decode(data)?),
+                BlobAttrId::GROUP => UbusMsgAttr::Group(BlobAttrData::decode(data)?),
+                id => UbusMsgAttr::Unknown(id, data),
+            })
+        };
+        attr().map_err(|source| UbusError::InvalidAttribute {
+            id,
+            source: Box::new(source),
+        })
     }
 }
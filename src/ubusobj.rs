@@ -1,87 +1,665 @@
 extern crate alloc;
 use crate::*;
-use alloc::{string::ToString, vec::Vec};
-use serde::{Deserialize, Serialize};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Index;
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
-use std::collections::HashMap;
+
+/// Methods of an object or arguments of a method, kept in signature order
+///
+/// Unlike [`BlobMsgTable`] keys are unique, inserting an existing key replaces its value
+/// in place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderedMap<K, V>(Vec<(K, V)>);
+
+impl<K: AsRef<str>, V> OrderedMap<K, V> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Add `value` under `key`, returning the value it replaces
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.get_mut(key.as_ref()) {
+            Some(old) => Some(core::mem::replace(old, value)),
+            None => {
+                self.0.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.as_ref() == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.0
+            .iter_mut()
+            .find(|(k, _)| k.as_ref() == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.0.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.0.iter().map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.0.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn from_vec(entries: Vec<(K, V)>) -> Self {
+        Self(entries)
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<(K, V)> {
+        self.0
+    }
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<K: AsRef<str>, V> Index<&str> for OrderedMap<K, V> {
+    type Output = V;
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).expect("key not found in OrderedMap")
+    }
+}
+
+impl<K: AsRef<str>, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K, V> IntoIterator for OrderedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = alloc::vec::IntoIter<Self::Item>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<K: Serialize, V: Serialize> Serialize for OrderedMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (k, v) in &self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for OrderedMap<K, V>
+where
+    K: AsRef<str> + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor<K, V>(PhantomData<OrderedMap<K, V>>);
+        impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+        where
+            K: AsRef<str> + Deserialize<'de>,
+            V: Deserialize<'de>,
+        {
+            type Value = OrderedMap<K, V>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map")
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut map = OrderedMap::new();
+                while let Some((k, v)) = access.next_entry()? {
+                    map.insert(k, v);
+                }
+                Ok(map)
+            }
+        }
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Method<'a> {
     pub name: &'a str,
-    pub policy: HashMap<&'a str, BlobMsgType>,
+    pub policy: OrderedMap<&'a str, BlobMsgType>,
 }
+impl<'a> Method<'a> {
+    /// Decode arguments received for this method
+    ///
+    /// `BOOL` and `INT8` share type 7, so both decode as [`BlobMsgPayload::Bool`] with
+    /// the semantics of `blobmsg_get_bool()`.
+    pub fn parse_args(&self, data: &'a [u8]) -> Result<Vec<BlobMsg<'a>>, UbusError> {
+        let mut args = Vec::new();
+        for blob in BlobIter::<Blob>::new(data) {
+            args.push(blob?.try_into()?);
+        }
+        Ok(args)
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct UbusObject<'a> {
     pub path: &'a str,
     pub id: u32,
     pub ty: u32,
-    pub methods: HashMap<&'a str, Method<'a>>,
+    pub methods: OrderedMap<&'a str, Method<'a>>,
+}
+
+/// Opt-in lenient conversions for [`UbusObject::args_from_json_with`]
+///
+/// Without any of them a JSON value has to match the policy type exactly, integers have
+/// to fit and every key has to be in the policy, otherwise building the arguments fails.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Coercion {
+    /// Accept strings holding a number, e.g. `"1500"`, for numeric arguments
+    pub numeric_strings: bool,
+    /// Accept floats without fractional part, e.g. `1500.0`, for integer arguments
+    pub integral_floats: bool,
+    /// Accept `"true"`, `"false"`, `"1"` and `"0"` for boolean arguments
+    pub bool_strings: bool,
+    /// Leave out keys which are not in the policy instead of failing
+    pub skip_unknown: bool,
+}
+
+impl Coercion {
+    pub const STRICT: Self = Self {
+        numeric_strings: false,
+        integral_floats: false,
+        bool_strings: false,
+        skip_unknown: false,
+    };
+
+    pub const LENIENT: Self = Self {
+        numeric_strings: true,
+        integral_floats: true,
+        bool_strings: true,
+        skip_unknown: true,
+    };
+}
+
+/// Name of the JSON type of `value` for error messages
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(num) if num.is_f64() => "float",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Name of the blobmsg type of `value` for error messages
+fn payload_type(value: &BlobMsgPayload) -> &'static str {
+    match value {
+        BlobMsgPayload::Array(_) => "array",
+        BlobMsgPayload::Table(_) => "table",
+        BlobMsgPayload::String(_) | BlobMsgPayload::Bytes(_) => "string",
+        BlobMsgPayload::Int64(_) => "int64",
+        BlobMsgPayload::Int32(_) => "int32",
+        BlobMsgPayload::Int16(_) => "int16",
+        BlobMsgPayload::Int8(_) => "int8",
+        BlobMsgPayload::Bool(_) => "bool",
+        BlobMsgPayload::Double(_) => "double",
+        BlobMsgPayload::Unknown(_, _) => "unknown",
+    }
+}
+
+/// `value` as an argument of type `ty`, integers take the width of `ty` if they fit or
+/// become a double, `UNSPEC` takes any value
+fn policy_payload<'a>(
+    name: &str,
+    ty: BlobMsgType,
+    value: &BlobMsgPayload<'a>,
+) -> Result<BlobMsgPayload<'a>, UbusError> {
+    let error = |problem| UbusError::InvalidArgument {
+        name: name.to_string(),
+        problem,
+    };
+    let wrong_type = || {
+        error(ArgumentProblem::WrongType {
+            expected: ty,
+            found: payload_type(value),
+        })
+    };
+    if value.ty() == ty || ty == BlobMsgType::UNSPEC {
+        return Ok(value.clone());
+    }
+    let num = match *value {
+        BlobMsgPayload::Int64(num) => num,
+        BlobMsgPayload::Int32(num) => num.into(),
+        BlobMsgPayload::Int16(num) => num.into(),
+        BlobMsgPayload::Int8(num) => num.into(),
+        _ => return Err(wrong_type()),
+    };
+    let overflow = || {
+        error(ArgumentProblem::Overflow {
+            expected: ty,
+            value: num.to_string(),
+        })
+    };
+    Ok(match ty {
+        BlobMsgType::INT64 => BlobMsgPayload::Int64(num),
+        BlobMsgType::INT32 => BlobMsgPayload::Int32(num.try_into().map_err(|_| overflow())?),
+        BlobMsgType::INT16 => BlobMsgPayload::Int16(num.try_into().map_err(|_| overflow())?),
+        // BOOL shares its type with INT8
+        BlobMsgType::BOOL => BlobMsgPayload::Int8(num.try_into().map_err(|_| overflow())?),
+        BlobMsgType::DOUBLE => BlobMsgPayload::Double(num as f64),
+        _ => return Err(wrong_type()),
+    })
+}
+
+/// Checked conversion of one JSON argument to the type its policy asks for
+struct Arg<'v> {
+    name: &'v str,
+    ty: BlobMsgType,
+    value: &'v Value,
+    coercion: Coercion,
+}
+
+impl Arg<'_> {
+    fn error(&self, problem: ArgumentProblem) -> UbusError {
+        UbusError::InvalidArgument {
+            name: self.name.to_string(),
+            problem,
+        }
+    }
+
+    fn wrong_type(&self) -> UbusError {
+        self.error(ArgumentProblem::WrongType {
+            expected: self.ty,
+            found: json_type(self.value),
+        })
+    }
+
+    fn int<T: TryFrom<i64>>(&self) -> Result<T, UbusError> {
+        let num = match self.value {
+            Value::Number(num) => {
+                if let Some(num) = num.as_i64() {
+                    num
+                } else if num.is_u64() {
+                    return Err(self.overflow(num));
+                } else {
+                    let float = num.as_f64().unwrap_or(f64::NAN);
+                    self.integral(float)?
+                }
+            }
+            Value::String(s) if self.coercion.numeric_strings => match s.trim().parse::<i64>() {
+                Ok(num) => num,
+                Err(_) => match s.trim().parse::<f64>() {
+                    Ok(float) if float.is_finite() => self.integral(float)?,
+                    _ => return Err(self.wrong_type()),
+                },
+            },
+            _ => return Err(self.wrong_type()),
+        };
+        T::try_from(num).map_err(|_| self.overflow(num))
+    }
+
+    fn integral(&self, float: f64) -> Result<i64, UbusError> {
+        if float.fract() != 0.0 || !self.coercion.integral_floats {
+            return Err(self.error(ArgumentProblem::NotInteger(float)));
+        }
+        // i64::MAX as f64 rounds up to 2^63, which is already out of range
+        if float < i64::MIN as f64 || float >= i64::MAX as f64 {
+            return Err(self.overflow(float));
+        }
+        Ok(float as i64)
+    }
+
+    fn overflow(&self, value: impl ToString) -> UbusError {
+        self.error(ArgumentProblem::Overflow {
+            expected: self.ty,
+            value: value.to_string(),
+        })
+    }
+
+    fn double(&self) -> Result<f64, UbusError> {
+        match self.value {
+            Value::Number(num) => num.as_f64().ok_or_else(|| self.wrong_type()),
+            Value::String(s) if self.coercion.numeric_strings => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|num| num.is_finite())
+                .ok_or_else(|| self.wrong_type()),
+            _ => Err(self.wrong_type()),
+        }
+    }
+
+    fn add_to(&self, builder: &mut BlobMsgBuilder) -> Result<(), UbusError> {
+        let name = self.name;
+        match (self.ty, self.value) {
+            (BlobMsgType::STRING, Value::String(s)) => builder.add_string(name, s),
+            (BlobMsgType::INT64, _) => builder.add_int64(name, self.int()?),
+            (BlobMsgType::INT32, _) => builder.add_int32(name, self.int()?),
+            (BlobMsgType::INT16, _) => builder.add_int16(name, self.int()?),
+            (BlobMsgType::BOOL, Value::Bool(b)) => builder.add_bool(name, *b),
+            (BlobMsgType::BOOL, Value::String(s)) if self.coercion.bool_strings => match s.trim() {
+                "true" | "1" => builder.add_bool(name, true),
+                "false" | "0" => builder.add_bool(name, false),
+                _ => Err(self.wrong_type()),
+            },
+            // BOOL shares its type with INT8
+            (BlobMsgType::BOOL, _) => builder.add_int8(name, self.int()?),
+            (BlobMsgType::DOUBLE, _) => builder.add_double(name, self.double()?),
+            (BlobMsgType::ARRAY, Value::Array(_))
+            | (BlobMsgType::TABLE, Value::Object(_))
+            | (BlobMsgType::UNSPEC, _) => {
+                let data = BlobMsgPayload::try_from(self.value)?;
+                builder.add(&BlobMsg { name, data })
+            }
+            _ => Err(self.wrong_type()),
+        }
+    }
+}
+
+impl Method<'_> {
+    /// Check JSON arguments against this method's policy without building them
+    ///
+    /// Reports arguments missing from the policy, values that can not be converted to
+    /// their policy type and the names in `required` that are absent, since ubus
+    /// signatures do not say which arguments are optional.
+    pub fn validate_json(
+        &self,
+        args: &Value,
+        required: &[&str],
+        coercion: Coercion,
+    ) -> ArgumentIssues {
+        let mut issues = Vec::new();
+        let Some(object) = args.as_object() else {
+            issues.push(ArgumentIssue {
+                name: String::new(),
+                problem: ArgumentProblem::WrongType {
+                    expected: BlobMsgType::TABLE,
+                    found: json_type(args),
+                },
+            });
+            return ArgumentIssues(issues);
+        };
+        for (name, value) in object {
+            let Some(ty) = self.policy.get(name.as_str()) else {
+                issues.push(ArgumentIssue {
+                    name: name.clone(),
+                    problem: ArgumentProblem::Unknown,
+                });
+                continue;
+            };
+            let arg = Arg {
+                name,
+                ty: *ty,
+                value,
+                coercion,
+            };
+            let problem = match arg.add_to(&mut BlobMsgBuilder::new()) {
+                Ok(()) => continue,
+                Err(UbusError::InvalidArgument { problem, .. }) => problem,
+                Err(e) => ArgumentProblem::Unencodable {
+                    reason: e.to_string(),
+                },
+            };
+            issues.push(ArgumentIssue {
+                name: name.clone(),
+                problem,
+            });
+        }
+        for name in required {
+            if !object.contains_key(*name) {
+                issues.push(ArgumentIssue {
+                    name: name.to_string(),
+                    problem: ArgumentProblem::Missing,
+                });
+            }
+        }
+        ArgumentIssues(issues)
+    }
 }
 
 impl<'a> UbusObject<'a> {
-    pub fn args_from_json(&self, method: &'a str, json: &'a str) -> Result<Vec<u8>, UbusError> {
-        let mut args = Vec::new();
-        if json.len() == 0 {
-            return Ok(args);
-        }
-        match serde_json::from_str::<Value>(json) {
-            Ok(value) => {
-                if let Some(object) = value.as_object() {
-                    let method = self
-                        .methods
-                        .get(method)
-                        .ok_or(UbusError::InvalidMethod(method.to_string()))?;
-                    for (k, v) in object.into_iter() {
-                        if let Some(arg_typ) = method.policy.get(k.as_str()) {
-                            let mut builder =
-                                BlobMsgBuilder::new_extended(arg_typ.value(), k.as_str());
-                            match *arg_typ {
-                                BlobMsgType::STRING => {
-                                    if let Some(s) = v.as_str() {
-                                        builder.push_str(s)?;
-                                    }
-                                }
-                                BlobMsgType::INT64 => {
-                                    if let Some(num) = v.as_i64() {
-                                        builder.push_int64(num)?;
-                                    }
-                                }
-                                BlobMsgType::INT32 => {
-                                    if let Some(num) = v.as_i64() {
-                                        builder.push_int32(num as i32)?;
-                                    }
-                                }
-                                BlobMsgType::INT16 => {
-                                    if let Some(num) = v.as_i64() {
-                                        builder.push_int16(num as i16)?;
-                                    }
-                                }
-                                BlobMsgType::INT8 => {
-                                    if let Some(num) = v.as_i64() {
-                                        builder.push_int8(num as i8)?;
-                                    }
-                                }
-                                // BlobMsgType::BOOL => {
-                                //     if let Some(b) = v.as_bool() {
-                                //         builder.push_bool(b)?;
-                                //     }
-                                // }
-                                BlobMsgType::DOUBLE => {
-                                    if let Some(b) = v.as_f64() {
-                                        builder.push_double(b)?;
-                                    }
-                                }
-                                _ => continue,
-                            }
-                            args.extend_from_slice(builder.data())
-                        } else {
-                            continue;
-                        }
-                    }
+    /// Validate JSON arguments with [`Method::validate_json`] and build them only if
+    /// there is no problem at all
+    pub fn args_from_json_strict(
+        &self,
+        method: &'a str,
+        json: &'a str,
+        required: &[&str],
+        coercion: Coercion,
+    ) -> Result<Vec<u8>, UbusError> {
+        let signature = self
+            .methods
+            .get(method)
+            .ok_or(UbusError::InvalidMethod(method.to_string()))?;
+        let value = match json.trim() {
+            "" => Value::Object(Default::default()),
+            json => serde_json::from_str::<Value>(json)?,
+        };
+        let issues = signature.validate_json(&value, required, coercion);
+        if !issues.0.is_empty() {
+            return Err(UbusError::InvalidArguments(issues));
+        }
+        let mut args = BlobMsgBuilder::new();
+        if let Value::Object(object) = &value {
+            for (name, value) in object {
+                if let Some(ty) = signature.policy.get(name.as_str()) {
+                    let arg = Arg {
+                        name,
+                        ty: *ty,
+                        value,
+                        coercion,
+                    };
+                    arg.add_to(&mut args)?;
                 }
-                Ok(args)
             }
-            Err(e) => Err(UbusError::ParseArguments(e)),
+        }
+        Ok(args.data().to_vec())
+    }
+
+    /// Build the arguments of `method` from a JSON object, checking every value against
+    /// the method's policy
+    pub fn args_from_json(&self, method: &'a str, json: &'a str) -> Result<Vec<u8>, UbusError> {
+        self.args_from_json_with(method, json, Coercion::STRICT)
+    }
+
+    /// Like [`Self::args_from_json`] with some lenient conversions enabled
+    pub fn args_from_json_with(
+        &self,
+        method: &'a str,
+        json: &'a str,
+        coercion: Coercion,
+    ) -> Result<Vec<u8>, UbusError> {
+        let value = match json.len() {
+            0 => Value::Null,
+            _ => serde_json::from_str::<Value>(json).map_err(UbusError::ParseArguments)?,
+        };
+        self.args_from_value(method, &value, coercion)
+    }
+
+    /// Like [`Self::args_from_json_with`] for an already parsed value, `null` means no
+    /// arguments and any other value besides an object is an error
+    pub fn args_from_value(
+        &self,
+        method: &str,
+        value: &Value,
+        coercion: Coercion,
+    ) -> Result<Vec<u8>, UbusError> {
+        let method = self
+            .methods
+            .get(method)
+            .ok_or(UbusError::InvalidMethod(method.to_string()))?;
+        let mut args = BlobMsgBuilder::new();
+        let object = match value {
+            Value::Object(object) => object,
+            Value::Null => return Ok(Vec::new()),
+            value => {
+                return Err(UbusError::InvalidArgument {
+                    name: String::new(),
+                    problem: ArgumentProblem::WrongType {
+                        expected: BlobMsgType::TABLE,
+                        found: json_type(value),
+                    },
+                });
+            }
+        };
+        for (k, v) in object.into_iter() {
+            let Some(arg_typ) = method.policy.get(k.as_str()) else {
+                if coercion.skip_unknown {
+                    continue;
+                }
+                return Err(UbusError::InvalidArgument {
+                    name: k.clone(),
+                    problem: ArgumentProblem::Unknown,
+                });
+            };
+            let arg = Arg {
+                name: k,
+                ty: *arg_typ,
+                value: v,
+                coercion,
+            };
+            arg.add_to(&mut args)?;
+        }
+        Ok(args.data().to_vec())
+    }
+
+    /// Build the arguments of `method` from a blobmsg table, keeping the order and any
+    /// repeated names of its entries
+    ///
+    /// Values have to have their policy type already, integers are written with the
+    /// width of theirs if they fit. Like [`Self::args_from_value`], entries missing from
+    /// the policy and anything but a table are errors.
+    pub fn args_from_payload(
+        &self,
+        method: &str,
+        args: &BlobMsgPayload,
+    ) -> Result<Vec<u8>, UbusError> {
+        let method = self
+            .methods
+            .get(method)
+            .ok_or(UbusError::InvalidMethod(method.to_string()))?;
+        let BlobMsgPayload::Table(table) = args else {
+            return Err(UbusError::InvalidArgument {
+                name: String::new(),
+                problem: ArgumentProblem::WrongType {
+                    expected: BlobMsgType::TABLE,
+                    found: payload_type(args),
+                },
+            });
+        };
+        let mut builder = BlobMsgBuilder::new();
+        for (name, value) in table.iter() {
+            let Some(ty) = method.policy.get(name) else {
+                return Err(UbusError::InvalidArgument {
+                    name: name.to_string(),
+                    problem: ArgumentProblem::Unknown,
+                });
+            };
+            let data = policy_payload(name, *ty, value)?;
+            builder.add(&BlobMsg { name, data })?;
+        }
+        Ok(builder.data().to_vec())
+    }
+}
+
+/// Owned counterpart of [`Method`]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedMethod {
+    pub name: String,
+    pub policy: OrderedMap<String, BlobMsgType>,
+}
+
+impl OwnedMethod {
+    pub fn as_method(&self) -> Method<'_> {
+        Method {
+            name: &self.name,
+            policy: self.policy.iter().map(|(k, v)| (k.as_str(), *v)).collect(),
+        }
+    }
+}
+
+impl Method<'_> {
+    /// Copy this method out of the receive buffer
+    pub fn to_owned_value(&self) -> OwnedMethod {
+        OwnedMethod {
+            name: self.name.to_string(),
+            policy: self
+                .policy
+                .iter()
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect(),
+        }
+    }
+}
+
+/// Owned counterpart of [`UbusObject`], e.g. for caching lookup results
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedUbusObject {
+    pub path: String,
+    pub id: u32,
+    pub ty: u32,
+    pub methods: OrderedMap<String, OwnedMethod>,
+}
+
+impl OwnedUbusObject {
+    pub fn as_object(&self) -> UbusObject<'_> {
+        UbusObject {
+            path: &self.path,
+            id: self.id,
+            ty: self.ty,
+            methods: self
+                .methods
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_method()))
+                .collect(),
+        }
+    }
+}
+
+impl UbusObject<'_> {
+    /// Copy this object out of the receive buffer
+    pub fn to_owned_value(&self) -> OwnedUbusObject {
+        OwnedUbusObject {
+            path: self.path.to_string(),
+            id: self.id,
+            ty: self.ty,
+            methods: self
+                .methods
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_owned_value()))
+                .collect(),
         }
     }
 }
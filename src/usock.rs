@@ -20,4 +20,3 @@ impl Connection<UnixStream> {
 }
 
 impl IOError for std::io::Error {}
-impl std::error::Error for Error {}
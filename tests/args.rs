@@ -0,0 +1,280 @@
+use serde_json::{Value, json};
+use ubus::*;
+
+fn object() -> UbusObject<'static> {
+    let mut policy = OrderedMap::new();
+    policy.insert("name", BlobMsgType::STRING);
+    policy.insert("mtu", BlobMsgType::INT32);
+    policy.insert("metric", BlobMsgType::INT16);
+    policy.insert("rx_bytes", BlobMsgType::INT64);
+    policy.insert("weight", BlobMsgType::DOUBLE);
+    policy.insert("enabled", BlobMsgType::BOOL);
+    policy.insert("vlans", BlobMsgType::ARRAY);
+    policy.insert("data", BlobMsgType::UNSPEC);
+    let mut obj = UbusObject::default();
+    obj.methods.insert(
+        "set",
+        Method {
+            name: "set",
+            policy,
+        },
+    );
+    obj
+}
+
+fn decode(args: &[u8]) -> Value {
+    Value::try_from(BlobIter::<Blob>::new(args)).unwrap()
+}
+
+fn problem(result: Result<std::vec::Vec<u8>, UbusError>) -> (String, ArgumentProblem) {
+    match result {
+        Err(UbusError::InvalidArgument { name, problem }) => (name, problem),
+        other => panic!("expected an argument error, got {:?}", other),
+    }
+}
+
+#[test]
+fn strict() {
+    let obj = object();
+    let args = obj
+        .args_from_json(
+            "set",
+            r#"{"name": "eth0", "mtu": 1500, "metric": -1, "rx_bytes": 5000000000,
+                "weight": 2, "enabled": false, "vlans": [1, 2], "data": {"x": null}}"#,
+        )
+        .unwrap();
+    assert_eq!(
+        decode(&args),
+        json!({"name": "eth0", "mtu": 1500, "metric": -1, "rx_bytes": 5000000000i64,
+               "weight": 2.0, "enabled": false, "vlans": [1, 2], "data": {"x": null}})
+    );
+
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"mtu": 4294967296}"#)),
+        (
+            "mtu".into(),
+            ArgumentProblem::Overflow {
+                expected: BlobMsgType::INT32,
+                value: "4294967296".into()
+            }
+        )
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"metric": 40000}"#)),
+        (
+            "metric".into(),
+            ArgumentProblem::Overflow {
+                expected: BlobMsgType::INT16,
+                value: "40000".into()
+            }
+        )
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"enabled": 300}"#)).1,
+        ArgumentProblem::Overflow {
+            expected: BlobMsgType::BOOL,
+            value: "300".into()
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"rx_bytes": 18446744073709551615}"#)).1,
+        ArgumentProblem::Overflow {
+            expected: BlobMsgType::INT64,
+            value: "18446744073709551615".into()
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"mtu": 1500.5}"#)).1,
+        ArgumentProblem::NotInteger(1500.5)
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"mtu": 1500.0}"#)).1,
+        ArgumentProblem::NotInteger(1500.0)
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"mtu": "1500"}"#)).1,
+        ArgumentProblem::WrongType {
+            expected: BlobMsgType::INT32,
+            found: "string"
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"name": 1}"#)).1,
+        ArgumentProblem::WrongType {
+            expected: BlobMsgType::STRING,
+            found: "integer"
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"vlans": {"a": 1}}"#)).1,
+        ArgumentProblem::WrongType {
+            expected: BlobMsgType::ARRAY,
+            found: "object"
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"enabled": "yes"}"#)).1,
+        ArgumentProblem::WrongType {
+            expected: BlobMsgType::BOOL,
+            found: "string"
+        }
+    );
+
+    let err = obj
+        .args_from_json("set", r#"{"mtu": 4294967296}"#)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid argument mtu: 4294967296 does not fit INT32"
+    );
+}
+
+#[test]
+fn lenient() {
+    let obj = object();
+    let args = obj
+        .args_from_json_with(
+            "set",
+            r#"{"mtu": "1500", "metric": 10.0, "weight": " 0.5 ", "enabled": "1",
+                "rx_bytes": "1e3"}"#,
+            Coercion::LENIENT,
+        )
+        .unwrap();
+    assert_eq!(
+        decode(&args),
+        json!({"mtu": 1500, "metric": 10, "weight": 0.5, "enabled": true, "rx_bytes": 1000})
+    );
+
+    // Still checked
+    let coercion = Coercion {
+        numeric_strings: true,
+        ..Coercion::STRICT
+    };
+    assert_eq!(
+        problem(obj.args_from_json_with("set", r#"{"mtu": "99999999999"}"#, coercion)).1,
+        ArgumentProblem::Overflow {
+            expected: BlobMsgType::INT32,
+            value: "99999999999".into()
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json_with("set", r#"{"mtu": "15OO"}"#, coercion)).1,
+        ArgumentProblem::WrongType {
+            expected: BlobMsgType::INT32,
+            found: "string"
+        }
+    );
+    assert_eq!(
+        problem(obj.args_from_json_with("set", r#"{"mtu": "1500.0"}"#, coercion)).1,
+        ArgumentProblem::NotInteger(1500.0)
+    );
+    assert_eq!(
+        problem(obj.args_from_json_with("set", r#"{"rx_bytes": 1e30}"#, Coercion::LENIENT)).1,
+        ArgumentProblem::Overflow {
+            expected: BlobMsgType::INT64,
+            value: "1000000000000000000000000000000".into()
+        }
+    );
+}
+
+#[test]
+fn validate() {
+    let obj = object();
+    let method = &obj.methods["set"];
+    let args = json!({"name": "eth0", "mtu": 1e10, "speed": 1000, "enabled": "maybe"});
+    let issues = method.validate_json(&args, &["name", "ifname"], Coercion::STRICT);
+    assert_eq!(
+        issues.0,
+        [
+            ArgumentIssue {
+                name: "mtu".into(),
+                problem: ArgumentProblem::NotInteger(1e10)
+            },
+            ArgumentIssue {
+                name: "speed".into(),
+                problem: ArgumentProblem::Unknown
+            },
+            ArgumentIssue {
+                name: "enabled".into(),
+                problem: ArgumentProblem::WrongType {
+                    expected: BlobMsgType::BOOL,
+                    found: "string"
+                }
+            },
+            ArgumentIssue {
+                name: "ifname".into(),
+                problem: ArgumentProblem::Missing
+            },
+        ]
+    );
+    assert_eq!(
+        serde_json::to_value(&issues).unwrap(),
+        json!([
+            {"name": "mtu", "problem": {"kind": "not_integer", "value": 1e10}},
+            {"name": "speed", "problem": {"kind": "unknown"}},
+            {
+                "name": "enabled",
+                "problem": {"kind": "wrong_type", "expected": "BOOL", "found": "string"}
+            },
+            {"name": "ifname", "problem": {"kind": "missing"}},
+        ])
+    );
+
+    let err = obj
+        .args_from_json_strict("set", &args.to_string(), &["name"], Coercion::LENIENT)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid arguments: mtu: 10000000000 does not fit INT32, \
+         speed: not in the method signature, enabled: expected BOOL, found string"
+    );
+
+    // Unknown keys fail unless they are to be skipped
+    assert_eq!(
+        problem(obj.args_from_json("set", r#"{"name": "eth0", "speed": 1000}"#)),
+        ("speed".into(), ArgumentProblem::Unknown)
+    );
+    let coercion = Coercion {
+        skip_unknown: true,
+        ..Coercion::STRICT
+    };
+    let args = obj
+        .args_from_json_with("set", r#"{"name": "eth0", "speed": 1000}"#, coercion)
+        .unwrap();
+    assert_eq!(decode(&args), json!({"name": "eth0"}));
+    let args = obj
+        .args_from_json_strict("set", r#"{"name": "eth0"}"#, &["name"], Coercion::STRICT)
+        .unwrap();
+    assert_eq!(decode(&args), json!({"name": "eth0"}));
+    assert!(matches!(
+        obj.args_from_json_strict("set", "", &["name"], Coercion::STRICT),
+        Err(UbusError::InvalidArguments(_))
+    ));
+    assert!(matches!(
+        obj.args_from_json_strict("get", "{}", &[], Coercion::STRICT),
+        Err(UbusError::InvalidMethod(_))
+    ));
+    // Anything but an object, an empty string or null is an error
+    for json in ["[1]", r#""x""#, "42"] {
+        assert!(matches!(
+            problem(obj.args_from_json("set", json)),
+            (
+                name,
+                ArgumentProblem::WrongType {
+                    expected: BlobMsgType::TABLE,
+                    ..
+                }
+            ) if name.is_empty()
+        ));
+    }
+    assert!(obj.args_from_json("set", "").unwrap().is_empty());
+    assert!(obj.args_from_json("set", "null").unwrap().is_empty());
+
+    // The method is checked whatever the arguments
+    for json in ["", "null", "[1]"] {
+        assert!(matches!(
+            obj.args_from_json("get", json),
+            Err(UbusError::InvalidMethod(_))
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+fn sample() -> Vec<u8> {
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_bool("up", true).unwrap();
+    builder.add_int16("vlan", -2).unwrap();
+    builder.add_int64("tx", 5_000_000_000).unwrap();
+    builder.open_array("load").unwrap();
+    builder.add_double("", 0.5).unwrap();
+    builder.add_string_bytes("", b"\xe9").unwrap();
+    builder.close().unwrap();
+    builder.data().to_vec()
+}
+
+fn encode(data: &[u8], format: BinaryFormat) -> Vec<u8> {
+    let mut writer = BinaryWriter::new(Vec::new(), format);
+    writer.write_table(BlobIter::new(data)).unwrap();
+    writer.into_inner()
+}
+
+fn decode(data: &[u8], format: BinaryFormat) -> Vec<u8> {
+    let mut builder = BlobMsgBuilder::new();
+    assert_eq!(builder.add_binary_map(data, format).unwrap(), data.len());
+    builder.data().to_vec()
+}
+
+#[test]
+fn msgpack() {
+    let data = sample();
+    let packed = encode(&data, BinaryFormat::MsgPack);
+    assert_eq!(
+        packed,
+        [
+            0x84, // map of 4
+            0xa2, b'u', b'p', 0xc3, // true
+            0xa4, b'v', b'l', b'a', b'n', 0xd1, 0xff, 0xfe, // int 16
+            0xa2, b't', b'x', 0xd3, 0, 0, 0, 0x01, 0x2a, 0x05, 0xf2, 0x00, // int 64
+            0xa4, b'l', b'o', b'a', b'd', 0x92, // array of 2
+            0xcb, 0x3f, 0xe0, 0, 0, 0, 0, 0, 0, // float 64
+            0xc4, 0x01, 0xe9, // bin 8
+        ]
+    );
+    assert_eq!(decode(&packed, BinaryFormat::MsgPack), data);
+
+    // The decoded tree encodes the same
+    let table: BlobMsgTable = BlobIter::<Blob>::new(&data)
+        .map(|blob| {
+            let msg: BlobMsg = blob.unwrap().try_into().unwrap();
+            (msg.name, msg.data)
+        })
+        .collect();
+    assert_eq!(
+        BlobMsgPayload::Table(table)
+            .to_binary(BinaryFormat::MsgPack)
+            .unwrap(),
+        packed
+    );
+}
+
+#[test]
+fn cbor() {
+    let data = sample();
+    let encoded = encode(&data, BinaryFormat::Cbor);
+    assert_eq!(
+        encoded,
+        [
+            0xa4, // map of 4
+            0x62, b'u', b'p', 0xf5, // true
+            0x64, b'v', b'l', b'a', b'n', 0x39, 0x00, 0x01, // -2 with a two byte argument
+            0x62, b't', b'x', 0x1b, 0, 0, 0, 0x01, 0x2a, 0x05, 0xf2, 0x00, // eight bytes
+            0x64, b'l', b'o', b'a', b'd', 0x82, // array of 2
+            0xfb, 0x3f, 0xe0, 0, 0, 0, 0, 0, 0, // double
+            0x41, 0xe9, // byte string
+        ]
+    );
+    assert_eq!(decode(&encoded, BinaryFormat::Cbor), data);
+}
+
+#[test]
+fn status_reply() {
+    let raw = include_bytes!("corpus/status_data.bin");
+    let mut buffer = [0u8; 4096];
+    let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+    let data = BlobIter::<UbusMsgAttr>::new(message.blob.data)
+        .find_map(|attr| match attr.unwrap() {
+            UbusMsgAttr::Data(data) => Some(data),
+            _ => None,
+        })
+        .unwrap();
+    for format in [BinaryFormat::MsgPack, BinaryFormat::Cbor] {
+        assert_eq!(decode(&encode(data, format), format), data);
+    }
+}
+
+#[test]
+fn foreign() {
+    // What other encoders write: the shortest forms, unsigned and 32 bit floats
+    let packed = [
+        0x85, // map of 5
+        0xa1, b'a', 0x05, // positive fixint
+        0xa1, b'b', 0xcc, 0xc8, // uint 8, 200 needs an INT16
+        0xa1, b'c', 0xd0, 0x01, // int 8 needs an INT16, INT8 would read back as a boolean
+        0xa1, b'd', 0xca, 0x3f, 0xc0, 0x00, 0x00, // float 32
+        0xa1, b'e', 0x91, 0xc0, // [nil]
+    ];
+    let mut builder = BlobMsgBuilder::new();
+    builder
+        .add_binary_map(&packed, BinaryFormat::MsgPack)
+        .unwrap();
+    let value = BlobMsgValue::try_from(BlobIter::<Blob>::new(builder.data())).unwrap();
+    assert_eq!(
+        value,
+        BlobMsgValue::Table(Vec::from([
+            ("a".into(), BlobMsgValue::Int32(5)),
+            ("b".into(), BlobMsgValue::Int16(200)),
+            ("c".into(), BlobMsgValue::Int16(1)),
+            ("d".into(), BlobMsgValue::Double(1.5)),
+            (
+                "e".into(),
+                BlobMsgValue::Array(Vec::from([BlobMsgValue::null()]))
+            ),
+        ]))
+    );
+
+    // Indefinite lengths, tags and half floats in CBOR
+    let encoded = [
+        0xbf, // indefinite map
+        0x61, b'a', 0x9f, 0x01, 0x38, 0x63, 0xff, // [1, -100]
+        0x61, b'b', 0xc1, 0x1a, 0x65, 0x00, 0x00, 0x00, // epoch time tag
+        0x61, b'c', 0xf9, 0xc2, 0x00, // -3.0
+        0x61, b'd', 0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // u64::MAX
+        0xff,
+    ];
+    let mut builder = BlobMsgBuilder::new();
+    assert!(matches!(
+        builder.add_binary_map(&encoded, BinaryFormat::Cbor),
+        Err(UbusError::InvalidData("Integer out of range"))
+    ));
+
+    // A single value out of a longer stream
+    let mut builder = BlobMsgBuilder::new();
+    let used = builder
+        .add_binary("c", &encoded[18..], BinaryFormat::Cbor)
+        .unwrap();
+    assert_eq!(used, 3);
+    assert_eq!(builder.data(), blobmsg!({ "c": -3.0 }).to_bytes().unwrap());
+
+    let mut builder = BlobMsgBuilder::new();
+    let mut end = encoded[..21].to_vec();
+    end.push(0xff);
+    builder.add_binary_map(&end, BinaryFormat::Cbor).unwrap();
+    let value = BlobMsgValue::try_from(BlobIter::<Blob>::new(builder.data())).unwrap();
+    assert_eq!(
+        value,
+        BlobMsgValue::Table(Vec::from([
+            (
+                "a".into(),
+                BlobMsgValue::Array(Vec::from([
+                    BlobMsgValue::Int32(1),
+                    BlobMsgValue::Int16(-100)
+                ]))
+            ),
+            ("b".into(), BlobMsgValue::Int32(0x6500_0000)),
+            ("c".into(), BlobMsgValue::Double(-3.0)),
+        ]))
+    );
+}
+
+#[test]
+fn errors() {
+    let cases: [(&[u8], BinaryFormat, &str); 6] = [
+        (
+            &[0x81, 0xa1],
+            BinaryFormat::MsgPack,
+            "Truncated binary data",
+        ),
+        (
+            &[0x81, 0x01, 0xc0],
+            BinaryFormat::MsgPack,
+            "Map keys must be strings",
+        ),
+        (
+            &[0x81, 0xa1, b'a', 0xd4, 0x01, 0x00],
+            BinaryFormat::MsgPack,
+            "Unsupported MessagePack type",
+        ),
+        (&[0x90], BinaryFormat::MsgPack, "Expected a map"),
+        (
+            &[0xa1, 0x61, b'a', 0xff],
+            BinaryFormat::Cbor,
+            "Unexpected CBOR break",
+        ),
+        (
+            &[0xa1, 0x61, b'a', 0x5f, 0xff],
+            BinaryFormat::Cbor,
+            "Unsupported CBOR item",
+        ),
+    ];
+    for (data, format, problem) in cases {
+        let mut builder = BlobMsgBuilder::new();
+        match builder.add_binary_map(data, format) {
+            Err(UbusError::InvalidData(found)) => assert_eq!(found, problem, "{:02x?}", data),
+            other => panic!("{:02x?}: {:?}", data, other),
+        }
+    }
+
+    let mut deep = [0x91u8; 64].to_vec();
+    deep.push(0xc0);
+    let mut builder = BlobMsgBuilder::new();
+    assert!(matches!(
+        builder.add_binary("", &deep, BinaryFormat::MsgPack),
+        Err(UbusError::NestingTooDeep(_))
+    ));
+    // A long run of CBOR tags in front of one value does not exhaust the stack
+    let mut tagged = std::vec![0xa1, 0x61, b'a'];
+    tagged.resize(tagged.len() + 1_000_000, 0xc0);
+    tagged.push(0x05);
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_binary_map(&tagged, BinaryFormat::Cbor).unwrap();
+    assert_eq!(builder.data(), blobmsg!({ "a": 5 }).to_bytes().unwrap());
+    tagged.pop();
+    let mut builder = BlobMsgBuilder::new();
+    assert!(matches!(
+        builder.add_binary_map(&tagged, BinaryFormat::Cbor),
+        Err(UbusError::InvalidData("Truncated binary data"))
+    ));
+}
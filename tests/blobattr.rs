@@ -0,0 +1,164 @@
+use std::vec::Vec;
+use ubus::*;
+
+blob_schema! {
+    #[derive(Debug, PartialEq)]
+    pub struct Limits {
+        1 => pub nofile: u32,
+        2 => pub core: Option<i64>,
+    }
+}
+
+blob_schema! {
+    #[derive(Debug)]
+    pub struct Instance<'a> {
+        1 => pub name: &'a str,
+        2 => pub pid: Option<u32>,
+        3 => pub running: bool,
+        4 => pub command: Option<BlobIter<'a, Blob<'a>>>,
+        5 => pub limits: Option<Nested<Limits>>,
+        6 => pub cookie: Option<&'a [u8]>,
+        7 => pub weight: Option<f64>,
+    }
+}
+
+const INSTANCE: &[u8] = &[
+    0x01, 0x00, 0x00, 0x09, b'e', b't', b'h', b'0', 0x00, 0x00, 0x00, 0x00, // name
+    0x02, 0x00, 0x00, 0x08, 0x00, 0x00, 0x04, 0xd2, // pid
+    0x03, 0x00, 0x00, 0x05, 0x01, 0x00, 0x00, 0x00, // running
+    0x05, 0x00, 0x00, 0x0c, // limits
+    0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x04, 0x00, // nofile
+];
+
+fn encode<'a>(schema: &impl BlobSchema<'a>, buffer: &mut [u8]) -> Vec<u8> {
+    let mut builder = BlobBuilder::from_bytes(buffer);
+    schema.encode(&mut builder).unwrap();
+    let len = builder.len();
+    buffer[..len].to_vec()
+}
+
+#[test]
+fn schema() {
+    let instance = Instance::decode(INSTANCE).unwrap();
+    assert_eq!(instance.name, "eth0");
+    assert_eq!(instance.pid, Some(1234));
+    assert!(instance.running);
+    assert!(instance.command.is_none());
+    assert_eq!(
+        instance.limits,
+        Some(Nested(Limits {
+            nofile: 1024,
+            core: None
+        }))
+    );
+    assert_eq!((instance.cookie, instance.weight), (None, None));
+
+    let mut buffer = [0u8; 256];
+    assert_eq!(encode(&instance, &mut buffer), INSTANCE);
+
+    // Every type round trips
+    let mut buffer = [0u8; 64];
+    let mut command = BlobBuilder::from_bytes(&mut buffer);
+    command.push_str(0, "/sbin/netifd").unwrap();
+    command.push_str(0, "-v").unwrap();
+    let len = command.len();
+    let instance = Instance {
+        name: "it's",
+        pid: None,
+        running: false,
+        command: Some(BlobIter::new(&buffer[..len])),
+        limits: Some(Nested(Limits {
+            nofile: 4096,
+            core: Some(-1),
+        })),
+        cookie: Some(&[0xde, 0xad]),
+        weight: Some(0.5),
+    };
+    let mut out = [0u8; 256];
+    let data = encode(&instance, &mut out);
+    let decoded = Instance::decode(&data).unwrap();
+    assert_eq!(decoded.name, "it's");
+    assert_eq!(decoded.pid, None);
+    assert!(!decoded.running);
+    let args: Vec<_> = decoded
+        .command
+        .unwrap()
+        .map(|arg| <&str as BlobAttrData>::decode(arg.unwrap().data).unwrap())
+        .collect();
+    assert_eq!(args, ["/sbin/netifd", "-v"]);
+    assert_eq!(decoded.limits.unwrap().0.core, Some(-1));
+    assert_eq!(decoded.cookie, Some(&[0xde, 0xad][..]));
+    assert_eq!(decoded.weight, Some(0.5));
+}
+
+#[test]
+fn parse() {
+    // Unknown ids are ignored, the last duplicate wins
+    let mut data = INSTANCE.to_vec();
+    data.extend_from_slice(&[0x02, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01]);
+    data.extend_from_slice(&[0x7f, 0x00, 0x00, 0x04]);
+    assert_eq!(Instance::decode(&data).unwrap().pid, Some(1));
+
+    let policy = [
+        BlobAttrPolicy::required(2, BlobAttrType::INT32),
+        BlobAttrPolicy::new(5, BlobAttrType::NESTED),
+        BlobAttrPolicy::new(6, BlobAttrType::BINARY),
+    ];
+    let [pid, limits, cookie] = BlobAttrPolicy::parse(&policy, &data).unwrap();
+    assert!(cookie.is_none());
+    assert_eq!(
+        BlobAttrValue::decode(policy[0].ty, pid.unwrap().data).unwrap(),
+        BlobAttrValue::Int32(1)
+    );
+    let limits = BlobAttrValue::decode(policy[1].ty, limits.unwrap().data).unwrap();
+    assert_eq!(limits.ty(), BlobAttrType::NESTED);
+
+    let mut buffer = [0u8; 16];
+    let mut builder = BlobBuilder::from_bytes(&mut buffer);
+    BlobAttrValue::Int16(0x1234)
+        .encode(&mut builder, 9)
+        .unwrap();
+    assert_eq!(builder.len(), 8);
+    assert_eq!(
+        buffer[..8],
+        [0x09, 0x00, 0x00, 0x06, 0x12, 0x34, 0x00, 0x00]
+    );
+}
+
+#[test]
+fn errors() {
+    // Missing required field
+    assert!(matches!(
+        Instance::decode(&INSTANCE[12..]),
+        Err(UbusError::MissingAttribute(name)) if name == "name"
+    ));
+    assert!(matches!(
+        BlobAttrPolicy::parse(&[BlobAttrPolicy::required(9, BlobAttrType::INT8)], INSTANCE),
+        Err(UbusError::MissingAttribute(id)) if id == "9"
+    ));
+
+    // A 16 bit pid
+    let mut data = INSTANCE.to_vec();
+    data.extend_from_slice(&[0x02, 0x00, 0x00, 0x06, 0x04, 0xd2, 0x00, 0x00]);
+    assert!(matches!(
+        Instance::decode(&data),
+        Err(UbusError::InvalidAttribute { id: 2, .. })
+    ));
+
+    // A name without terminator
+    let mut data = INSTANCE.to_vec();
+    data[8] = b'!';
+    assert!(matches!(
+        Instance::decode(&data),
+        Err(UbusError::InvalidAttribute { id: 1, .. })
+    ));
+
+    // Ubus message attributes are checked the same way
+    let blob = Blob::from_bytes(&data).unwrap();
+    let attr = BlobIter::<UbusMsgAttr>::new(&data).next().unwrap();
+    assert_eq!(blob.tag.id(), BlobAttrId::STATUS.value());
+    assert!(matches!(
+        attr,
+        Err(UbusError::InvalidAttribute { id: 1, .. })
+    ));
+}
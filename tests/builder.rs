@@ -0,0 +1,241 @@
+use serde_json::{Value, json};
+use std::string::String;
+use std::vec::Vec;
+use ubus::*;
+
+fn decode(data: &[u8]) -> Value {
+    Value::try_from(BlobIter::<Blob>::new(data)).unwrap()
+}
+
+#[test]
+fn nested() {
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_bool("up", true).unwrap();
+    builder.add_int32("uptime", 86123).unwrap();
+    builder.open_array("ipv4-address").unwrap();
+    builder.open_table("").unwrap();
+    builder.add_string("address", "192.168.1.1").unwrap();
+    builder.add_int32("mask", 24).unwrap();
+    builder.close().unwrap();
+    builder.close().unwrap();
+    builder.open_table("data").unwrap();
+    builder.close().unwrap();
+    builder.add_int64("rx_bytes", 5_000_000_000).unwrap();
+    assert_eq!(builder.depth(), 0);
+    assert!(builder.close().is_err());
+
+    let expected = json!({
+        "up": true,
+        "uptime": 86123,
+        "ipv4-address": [{"address": "192.168.1.1", "mask": 24}],
+        "data": {},
+        "rx_bytes": 5_000_000_000i64,
+    });
+    assert_eq!(decode(builder.data()), expected);
+
+    // Same bytes as converting a decoded tree
+    let mut table = BlobMsgBuilder::new();
+    for name in ["up", "uptime", "ipv4-address", "data", "rx_bytes"] {
+        let data = BlobMsgPayload::try_from(&expected[name]).unwrap();
+        table.add(&BlobMsg { name, data }).unwrap();
+    }
+    assert_eq!(table.data(), builder.data());
+}
+
+#[test]
+fn extended() {
+    // Children pushed into a single named table grow its size
+    let mut builder = BlobMsgBuilder::new_extended(BlobMsgType::TABLE.value(), "opts").unwrap();
+    builder.add_string("name", "eth0").unwrap();
+    builder.open_array("vlans").unwrap();
+    builder.add_int16("", 1).unwrap();
+    builder.add_int16("", 2).unwrap();
+    builder.close().unwrap();
+    let msg: BlobMsg = builder.build().unwrap().try_into().unwrap();
+    assert_eq!(msg.name, "opts");
+    assert_eq!(Value::from(&msg), json!({"name": "eth0", "vlans": [1, 2]}));
+}
+
+#[test]
+fn message() {
+    let header = UbusMsgHeader {
+        version: UbusMsgVersion::CURRENT,
+        cmd_type: UbusCmdType::INVOKE,
+        sequence: 1.into(),
+        peer: 0x5c3a17e2.into(),
+    };
+    let mut args = BlobMsgBuilder::new();
+    args.add_string("name", "eth0").unwrap();
+    args.open_table("opts").unwrap();
+    args.add_bool("force", false).unwrap();
+    args.close().unwrap();
+
+    let mut buffer = [0u8; 256];
+    let mut message = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
+    message.put(UbusMsgAttr::ObjId(0x5c3a17e2)).unwrap();
+    message.put(UbusMsgAttr::Method("status")).unwrap();
+    message.put(UbusMsgAttr::Data(args.data())).unwrap();
+    let copied = message.finish().unwrap().to_vec();
+
+    let mut buffer = [0u8; 256];
+    let mut message = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
+    message.put(UbusMsgAttr::ObjId(0x5c3a17e2)).unwrap();
+    message.put(UbusMsgAttr::Method("status")).unwrap();
+    message.open(BlobAttrId::DATA).unwrap();
+    message
+        .put_blobmsg(&BlobMsg {
+            name: "name",
+            data: BlobMsgPayload::String("eth0"),
+        })
+        .unwrap();
+    message.open_table("opts").unwrap();
+    message
+        .put_blobmsg(&BlobMsg {
+            name: "force",
+            data: BlobMsgPayload::Bool(false),
+        })
+        .unwrap();
+    // DATA and "opts" are closed by finish
+    assert_eq!(message.finish().unwrap(), copied);
+}
+
+#[test]
+fn overflow() {
+    let header = UbusMsgHeader {
+        version: UbusMsgVersion::CURRENT,
+        cmd_type: UbusCmdType::INVOKE,
+        sequence: 1.into(),
+        peer: 0.into(),
+    };
+    let mut buffer = [0u8; 32];
+    let mut message = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
+    message.open(BlobAttrId::DATA).unwrap();
+    assert!(message.open_table("a long table name").is_err());
+
+    let mut raw = [0u8; 16];
+    let mut blob = BlobBuilder::from_bytes(&mut raw);
+    blob.push_u32(BlobAttrId::OBJID.value(), 1).unwrap();
+    blob.push_u32(BlobAttrId::OBJID.value(), 2).unwrap();
+    assert!(blob.push_u32(BlobAttrId::OBJID.value(), 3).is_err());
+    assert!(blob.close().is_err());
+    let ids: Vec<_> = BlobIter::<UbusMsgAttr>::new(&raw)
+        .map(|attr| match attr.unwrap() {
+            UbusMsgAttr::ObjId(id) => id,
+            attr => panic!("unexpected {:?}", attr),
+        })
+        .collect();
+    assert_eq!(ids, [1, 2]);
+}
+
+#[test]
+fn literal() {
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_bool("up", true).unwrap();
+    builder.add_int32("uptime", 86123).unwrap();
+    builder.open_array("ipv4-address").unwrap();
+    builder.open_table("").unwrap();
+    builder.add_string("address", "192.168.1.1").unwrap();
+    builder.add_int32("mask", 24).unwrap();
+    builder.close().unwrap();
+    builder.close().unwrap();
+    builder.open_table("data").unwrap();
+    builder.close().unwrap();
+    builder.add_int64("rx_bytes", 5_000_000_000).unwrap();
+
+    let address = String::from("192.168.1.1");
+    let value = blobmsg!({
+        "up": true,
+        "uptime": 86123,
+        "ipv4-address": [{ "address": address, "mask": 24 }],
+        "data": {},
+        "rx_bytes": 5_000_000_000i64,
+    });
+    assert_eq!(value.to_bytes().unwrap(), builder.data());
+
+    // Literal types pick the blobmsg type, expressions go through From
+    let mtu: Option<i16> = None;
+    let value = blobmsg!({
+        "name": "eth0",
+        "vlans": [1, 2i8, -3i16, null],
+        "opts": { "mtu": mtu, "weight": 0.5, "ports": vec!["lan1", "lan2"] },
+    });
+    assert_eq!(
+        value,
+        BlobMsgValue::Table(Vec::from([
+            ("name".into(), BlobMsgValue::String("eth0".into())),
+            (
+                "vlans".into(),
+                BlobMsgValue::Array(Vec::from([
+                    BlobMsgValue::Int32(1),
+                    BlobMsgValue::Int16(2),
+                    BlobMsgValue::Int16(-3),
+                    BlobMsgValue::null(),
+                ]))
+            ),
+            (
+                "opts".into(),
+                BlobMsgValue::Table(Vec::from([
+                    ("mtu".into(), BlobMsgValue::null()),
+                    ("weight".into(), BlobMsgValue::Double(0.5)),
+                    (
+                        "ports".into(),
+                        BlobMsgValue::Array(Vec::from([
+                            BlobMsgValue::String("lan1".into()),
+                            BlobMsgValue::String("lan2".into()),
+                        ]))
+                    ),
+                ]))
+            ),
+        ]))
+    );
+    // An i8 is written as INT16, so it reads back as the same integer
+    assert_eq!(
+        decode(&value.to_bytes().unwrap()),
+        json!({"name": "eth0", "vlans": [1, 2, -3, null],
+               "opts": {"mtu": null, "weight": 0.5, "ports": ["lan1", "lan2"]}})
+    );
+    assert_eq!(blobmsg!([]), BlobMsgValue::Array(Vec::new()));
+    assert!(blobmsg!([1]).to_bytes().is_err());
+
+    // Integers without suffix grow to INT64, a suffix is kept
+    let value = blobmsg!([
+        5000000000,
+        -5000000000,
+        2147483647,
+        5i64,
+        -9223372036854775808,
+        0x7fff_ffff_ffff_ffff
+    ]);
+    assert_eq!(
+        value,
+        BlobMsgValue::Array(Vec::from([
+            BlobMsgValue::Int64(5_000_000_000),
+            BlobMsgValue::Int64(-5_000_000_000),
+            BlobMsgValue::Int32(i32::MAX),
+            BlobMsgValue::Int64(5),
+            BlobMsgValue::Int64(i64::MIN),
+            BlobMsgValue::Int64(i64::MAX),
+        ]))
+    );
+
+    // Keys can be expressions
+    let name = String::from("wan");
+    let prefix = "tx";
+    let value = blobmsg!({
+        name.as_str(): { format!("{}_bytes", prefix): 5000000000 },
+        &name[..1]: null,
+    });
+    assert_eq!(
+        value,
+        BlobMsgValue::Table(Vec::from([
+            (
+                "wan".into(),
+                BlobMsgValue::Table(Vec::from([(
+                    "tx_bytes".into(),
+                    BlobMsgValue::Int64(5_000_000_000)
+                )]))
+            ),
+            ("w".into(), BlobMsgValue::null()),
+        ]))
+    );
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::string::String;
+use std::vec::Vec;
+use ubus::*;
+
+const HELLO: &[u8] = include_bytes!("corpus/hello.bin");
+
+/// A peer with one object `test` whose method `echo` replies with its arguments, followed
+/// by a second reply `{"extra": true}`
+fn connect() -> Connection<UnixStream> {
+    let (client, mut server) = UnixStream::pair().unwrap();
+
+    std::thread::spawn(move || {
+        server.write_all(HELLO).unwrap();
+        let mut signature = BlobMsgBuilder::new();
+        signature.open_table("echo").unwrap();
+        signature
+            .add_int32("name", BlobMsgType::STRING.value() as i32)
+            .unwrap();
+        signature
+            .add_int32("count", BlobMsgType::INT32.value() as i32)
+            .unwrap();
+        signature
+            .add_int32("weight", BlobMsgType::DOUBLE.value() as i32)
+            .unwrap();
+        signature
+            .add_int32("data", BlobMsgType::UNSPEC.value() as i32)
+            .unwrap();
+        signature.close().unwrap();
+        let mut extra = BlobMsgBuilder::new();
+        extra.add_bool("extra", true).unwrap();
+
+        loop {
+            let mut head = [0u8; UbusMsgHeader::SIZE + BlobTag::SIZE];
+            if server.read_exact(&mut head).is_err() {
+                return;
+            }
+            let (header, tag) = head.split_first_chunk().unwrap();
+            let header = UbusMsgHeader::from_bytes(*header);
+            let tag = BlobTag::from_bytes(*tag.first_chunk().unwrap());
+            let mut attrs = std::vec![0u8; tag.inner_len()];
+            server.read_exact(&mut attrs).unwrap();
+
+            let mut replies = std::vec::Vec::new();
+            match UbusCmd::parse(header.cmd_type, &attrs).unwrap() {
+                UbusCmd::Lookup(_) => replies.push(UbusCmd::LookupReply(LookupReplyMsg {
+                    path: "test",
+                    id: 0x13333337,
+                    ty: 0x42,
+                    signature: signature.data(),
+                })),
+                UbusCmd::Invoke(invoke) => {
+                    for data in [invoke.data.unwrap_or_default(), extra.data()] {
+                        replies.push(UbusCmd::Data(DataMsg {
+                            obj: Some(invoke.obj),
+                            data,
+                        }));
+                    }
+                }
+                cmd => panic!("{:?}", cmd),
+            }
+            replies.push(UbusCmd::Status(StatusMsg {
+                code: UbusStatus::OK,
+                obj: None,
+            }));
+            for reply in replies {
+                let mut buffer = [0u8; 256];
+                let reply = reply
+                    .to_message(&mut buffer, &header)
+                    .unwrap()
+                    .finish()
+                    .unwrap();
+                server.write_all(reply).unwrap();
+            }
+        }
+    });
+
+    Connection::new(client).unwrap()
+}
+
+#[test]
+fn value() {
+    let mut connection = connect();
+    let args = blobmsg!({"name": "x", "count": 3});
+    let replies = connection
+        .call_value("test", "echo", &args.as_payload())
+        .unwrap();
+    assert_eq!(
+        replies,
+        [
+            blobmsg!({"name": "x", "count": 3}),
+            blobmsg!({"extra": true})
+        ]
+    );
+
+    // No arguments
+    let replies = connection
+        .call_value("test", "echo", &blobmsg!({}).as_payload())
+        .unwrap();
+    assert_eq!(replies, [blobmsg!({}), blobmsg!({"extra": true})]);
+
+    // Order, repeated names and bytes are kept, integers take the width of the policy
+    let args = BlobMsgValue::Table(Vec::from([
+        ("count".into(), BlobMsgValue::Int64(4)),
+        ("name".into(), BlobMsgValue::Bytes(b"Caf\xe9".to_vec())),
+        ("count".into(), BlobMsgValue::Int16(5)),
+    ]));
+    let replies = connection
+        .call_value("test", "echo", &args.as_payload())
+        .unwrap();
+    assert_eq!(
+        replies[0],
+        BlobMsgValue::Table(Vec::from([
+            ("count".into(), BlobMsgValue::Int32(4)),
+            ("name".into(), BlobMsgValue::Bytes(b"Caf\xe9".to_vec())),
+            ("count".into(), BlobMsgValue::Int32(5)),
+        ]))
+    );
+
+    // Integers widen to a double, anything goes for an unspecified type
+    let args = blobmsg!({"weight": 2, "data": ["a", {"b": 1}]});
+    let replies = connection
+        .call_value("test", "echo", &args.as_payload())
+        .unwrap();
+    assert_eq!(
+        replies[0],
+        blobmsg!({"weight": 2.0, "data": ["a", {"b": 1}]})
+    );
+
+    // Values are checked against the policy
+    let err = connection
+        .call_value("test", "echo", &blobmsg!({"count": "3"}).as_payload())
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(
+        *source,
+        UbusError::InvalidArgument {
+            problem: ArgumentProblem::WrongType {
+                expected: BlobMsgType::INT32,
+                found: "string"
+            },
+            ..
+        }
+    ));
+    let err = connection
+        .call_value(
+            "test",
+            "echo",
+            &blobmsg!({"count": 5000000000}).as_payload(),
+        )
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(
+        *source,
+        UbusError::InvalidArgument {
+            problem: ArgumentProblem::Overflow { .. },
+            ..
+        }
+    ));
+
+    // Arguments missing from the policy are errors, as is anything but a table
+    let args = blobmsg!({"name": "x", "ignored": false});
+    let err = connection
+        .call_value("test", "echo", &args.as_payload())
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(
+        *source,
+        UbusError::InvalidArgument { ref name, problem: ArgumentProblem::Unknown } if name == "ignored"
+    ));
+    let err = connection
+        .call_value("test", "echo", &BlobMsgPayload::Int32(1))
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(
+        *source,
+        UbusError::InvalidArgument {
+            problem: ArgumentProblem::WrongType {
+                expected: BlobMsgType::TABLE,
+                found: "int32"
+            },
+            ..
+        }
+    ));
+}
+
+#[test]
+fn json() {
+    let mut connection = connect();
+    let reply: Value = connection
+        .call_as("test", "echo", &json!({"name": "x", "count": 3}))
+        .unwrap();
+    assert_eq!(reply, json!({"name": "x", "count": 3, "extra": true}));
+
+    // Arguments are checked against the policy
+    let err = connection
+        .call_as::<Value>("test", "echo", &json!({"count": "3"}))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        UbusError::Call {
+            obj: Some(0x13333337),
+            ..
+        }
+    ));
+    let err = connection
+        .call_as::<Value>("test", "nope", &json!({}))
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Calling nope on test"));
+}
+
+#[test]
+fn typed() {
+    #[derive(Serialize)]
+    struct Args<'a> {
+        name: &'a str,
+        count: i32,
+    }
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Reply {
+        name: String,
+        count: i32,
+        extra: bool,
+    }
+
+    let mut connection = connect();
+    let args = Args {
+        name: "x",
+        count: 3,
+    };
+    let reply: Reply = connection.call_as("test", "echo", &args).unwrap();
+    assert_eq!(
+        reply,
+        Reply {
+            name: "x".into(),
+            count: 3,
+            extra: true,
+        }
+    );
+
+    // A reply not matching the type
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Wrong {
+        name: i32,
+    }
+    let err = connection
+        .call_as::<Wrong>("test", "echo", &args)
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(*source, UbusError::ParseReply(_)));
+}
@@ -0,0 +1,198 @@
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+/// Parse `raw` as a typed command, check building it gives the same bytes again and
+/// pass it on
+fn roundtrip(raw: &[u8], check: impl FnOnce(UbusCmd)) {
+    let mut buffer = [0u8; 4096];
+    let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+    let cmd = UbusCmd::try_from(message).unwrap();
+    assert_eq!(cmd.cmd_type(), message.header.cmd_type);
+    let mut out = [0u8; 4096];
+    let built: &[u8] = cmd
+        .to_message(&mut out, &message.header)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(built, raw);
+    check(cmd);
+}
+
+#[test]
+fn corpus() {
+    roundtrip(include_bytes!("corpus/hello.bin"), |cmd| {
+        assert_eq!(cmd, UbusCmd::Hello);
+    });
+    roundtrip(include_bytes!("corpus/list_request.bin"), |cmd| {
+        assert_eq!(cmd, UbusCmd::Lookup(LookupMsg { path: None }));
+    });
+    roundtrip(include_bytes!("corpus/list_data_hostapd.bin"), |cmd| {
+        let UbusCmd::LookupReply(reply) = cmd else {
+            panic!("{:?}", cmd);
+        };
+        assert_eq!(reply.path, "hostapd.wlan1");
+        assert_eq!(reply.id, 0x3ad0156e);
+        let signature: BlobMsgTable = Payload::from(reply.signature).try_into().unwrap();
+        assert!(signature.contains_key("get_clients"));
+    });
+    roundtrip(include_bytes!("corpus/status_request.bin"), |cmd| {
+        let UbusCmd::Invoke(invoke) = cmd else {
+            panic!("{:?}", cmd);
+        };
+        assert_eq!(invoke.obj, 0x5c3a17e2);
+        assert_eq!(invoke.method, "status");
+        assert_eq!(invoke.data, Some(&[][..]));
+        assert_eq!(invoke.user, None);
+    });
+    roundtrip(include_bytes!("corpus/invoke_data.bin"), |cmd| {
+        let UbusCmd::Data(data) = cmd else {
+            panic!("{:?}", cmd);
+        };
+        assert_eq!(data.obj, Some(0x13333377));
+        assert_eq!(BlobIter::<Blob>::new(data.data).count(), 5);
+    });
+    roundtrip(include_bytes!("corpus/status_status.bin"), |cmd| {
+        let status = StatusMsg {
+            code: UbusStatus::OK,
+            obj: Some(0x13333377),
+        };
+        assert_eq!(cmd, UbusCmd::Status(status));
+        assert!(status.result().is_ok());
+    });
+}
+
+#[test]
+fn build() {
+    let header = UbusMsgHeader {
+        version: UbusMsgVersion::CURRENT,
+        cmd_type: UbusCmdType::HELLO,
+        sequence: 7.into(),
+        peer: 0x1234.into(),
+    };
+    let mut args = BlobMsgBuilder::new();
+    args.add_string("message", "hi").unwrap();
+    let cmds = [
+        UbusCmd::Ping,
+        UbusCmd::Invoke(InvokeMsg {
+            obj: 0x1234,
+            method: "say",
+            data: Some(args.data()),
+            user: Some("root"),
+            group: Some("root"),
+        }),
+        UbusCmd::Notify(NotifyMsg {
+            obj: 0x1234,
+            method: "said",
+            data: None,
+            no_reply: Some(true),
+        }),
+        UbusCmd::AddObject(AddObjectMsg {
+            path: Some("test"),
+            ty: None,
+            signature: Some(&[]),
+        }),
+        UbusCmd::ObjectAdded(ObjectAddedMsg {
+            id: 0x1234,
+            ty: Some(0x5678),
+        }),
+        UbusCmd::RemoveObject(RemoveObjectMsg { obj: 0x1234 }),
+        UbusCmd::Subscribe(SubscribeMsg {
+            obj: 0x1234,
+            target: 0x5678,
+        }),
+        UbusCmd::Unsubscribe(SubscribeMsg {
+            obj: 0x1234,
+            target: 0x5678,
+        }),
+        UbusCmd::Status(StatusMsg {
+            code: UbusStatus::NOT_FOUND,
+            obj: None,
+        }),
+        UbusCmd::Other(UbusCmdType::MONITOR, &[0x01, 0x00, 0x00, 0x08, 0, 0, 0, 1]),
+    ];
+    for cmd in cmds {
+        let mut buffer = [0u8; 256];
+        let raw = cmd
+            .to_message(&mut buffer, &header)
+            .unwrap()
+            .finish()
+            .unwrap()
+            .to_vec();
+        let mut buffer = [0u8; 256];
+        let message = UbusMsg::from_io(&mut Replay(&raw), &mut buffer).unwrap();
+        assert_eq!(message.header.cmd_type, cmd.cmd_type());
+        assert_eq!(message.header.sequence, header.sequence);
+        assert_eq!(UbusCmd::try_from(message).unwrap(), cmd);
+    }
+    assert!(matches!(
+        StatusMsg {
+            code: UbusStatus::NOT_FOUND,
+            obj: None
+        }
+        .result(),
+        Err(UbusError::Status(UbusStatus::NOT_FOUND))
+    ));
+}
+
+#[test]
+fn required() {
+    let parse = |cmd: UbusCmdType, attrs: &[UbusMsgAttr]| {
+        let mut raw = Vec::new();
+        for attr in attrs {
+            let mut buffer = [0u8; 64];
+            let mut builder = BlobBuilder::from_bytes(&mut buffer);
+            match attr {
+                UbusMsgAttr::ObjId(id) => builder.push_u32(BlobAttrId::OBJID.value(), *id),
+                UbusMsgAttr::Method(m) => builder.push_str(BlobAttrId::METHOD.value(), m),
+                UbusMsgAttr::Unknown(id, data) => builder.push_bytes(id.value(), *data),
+                _ => unreachable!(),
+            }
+            .unwrap();
+            let len = builder.len();
+            raw.extend_from_slice(&buffer[..len]);
+        }
+        UbusCmd::parse(cmd, &raw).map(|_| ())
+    };
+    let missing = |result: Result<(), UbusError>, field: &str| {
+        assert!(
+            matches!(&result, Err(UbusError::MissingAttribute(name)) if name == field),
+            "{:?}",
+            result
+        );
+    };
+
+    // ubusd rejects invokes without object or method
+    missing(
+        parse(UbusCmdType::INVOKE, &[UbusMsgAttr::ObjId(1)]),
+        "method",
+    );
+    missing(
+        parse(UbusCmdType::INVOKE, &[UbusMsgAttr::Method("x")]),
+        "obj",
+    );
+    assert!(
+        parse(
+            UbusCmdType::INVOKE,
+            &[UbusMsgAttr::ObjId(1), UbusMsgAttr::Method("x")]
+        )
+        .is_ok()
+    );
+    missing(parse(UbusCmdType::STATUS, &[]), "code");
+    missing(parse(UbusCmdType::DATA, &[]), "data");
+    missing(
+        parse(UbusCmdType::SUBSCRIBE, &[UbusMsgAttr::ObjId(1)]),
+        "target",
+    );
+
+    // Wrong payload size for the type
+    let status = UbusMsgAttr::Unknown(BlobAttrId::STATUS, &[0, 0]);
+    assert!(matches!(
+        parse(UbusCmdType::STATUS, &[status]),
+        Err(UbusError::InvalidAttribute { id: 1, .. })
+    ));
+    // Commands without attributes ignore whatever they carry
+    assert!(parse(UbusCmdType::PING, &[UbusMsgAttr::ObjId(1)]).is_ok());
+}
@@ -0,0 +1,42 @@
+#![allow(dead_code)]
+
+use std::vec::Vec;
+use ubus::*;
+
+/// Replays a fixed byte stream
+pub struct Replay<'a>(pub &'a [u8]);
+impl IO for Replay<'_> {
+    type Error = std::io::Error;
+    fn put(&mut self, _data: &[u8]) -> Result<(), UbusError> {
+        Ok(())
+    }
+    fn get(&mut self, data: &mut [u8]) -> Result<(), UbusError> {
+        if data.len() > self.0.len() {
+            return Err(UbusError::InvalidData("Replay exhausted"));
+        }
+        let (head, tail) = self.0.split_at(data.len());
+        data.copy_from_slice(head);
+        self.0 = tail;
+        Ok(())
+    }
+}
+
+/// A hello followed by `replies` to the first request of a connection
+pub fn replies(replies: &[UbusCmd]) -> Vec<u8> {
+    let mut header = UbusMsgHeader {
+        version: UbusMsgVersion::CURRENT,
+        cmd_type: UbusCmdType::HELLO,
+        sequence: 0.into(),
+        peer: 0x1234.into(),
+    };
+    let mut stream = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let hello = UbusCmd::Hello.to_message(&mut buffer, &header).unwrap();
+    stream.extend_from_slice(hello.finish().unwrap());
+    header.sequence = 1.into();
+    for reply in replies {
+        let message = reply.to_message(&mut buffer, &header).unwrap();
+        stream.extend_from_slice(message.finish().unwrap());
+    }
+    stream
+}
@@ -0,0 +1,141 @@
+use std::string::ToString;
+use std::vec::Vec;
+use ubus::*;
+
+fn table(data: &[u8]) -> BlobMsgPayload<'_> {
+    BlobMsgPayload::Table(
+        BlobIter::<Blob>::new(data)
+            .map(|blob| {
+                let msg: BlobMsg = blob.unwrap().try_into().unwrap();
+                (msg.name, msg.data)
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn diff() {
+    let old = blobmsg!({
+        "up": true,
+        "uptime": 86123,
+        "ipv4-address": [{ "address": "192.168.1.1", "mask": 24 }],
+        "dns-server": ["1.1.1.1", "8.8.8.8"],
+        "data": { "zone": "lan" },
+        "rx_bytes": 4_000_000_000i64,
+        "metric": 0,
+    })
+    .to_bytes()
+    .unwrap();
+    let new = blobmsg!({
+        "up": true,
+        "uptime": 86124,
+        "ipv4-address": [{ "address": "192.168.1.1", "mask": 16 }],
+        "dns-server": ["1.1.1.1"],
+        "data": {},
+        "rx_bytes": 4_000_000_001i64,
+        "metric": 0i64,
+        "l3.device": "br-lan",
+    })
+    .to_bytes()
+    .unwrap();
+    let (old, new) = (table(&old), table(&new));
+
+    assert!(old.diff(&old).is_empty());
+    let changes: Vec<_> = old.diff(&new).iter().map(ToString::to_string).collect();
+    assert_eq!(
+        changes,
+        [
+            "~ @.uptime: 86123 -> 86124",
+            "~ @.ipv4-address[0].mask: 24 -> 16",
+            "- @.dns-server[1]: \"8.8.8.8\"",
+            "- @.data.zone: \"lan\"",
+            "~ @.rx_bytes: 4000000000 -> 4000000001",
+            "~ @.metric: 0 -> 0",
+            "+ @['l3.device']: \"br-lan\"",
+        ]
+    );
+
+    let changes = old.diff(&new);
+    assert!(matches!(
+        changes[1],
+        BlobMsgChange::Changed {
+            old: BlobMsgPayload::Int32(24),
+            new: BlobMsgPayload::Int32(16),
+            ..
+        }
+    ));
+    // Paths select what changed
+    let path = changes[6].path();
+    assert_eq!(path.to_string().parse::<BlobMsgPath>().unwrap(), *path);
+    assert!(matches!(
+        path.select(&new)[..],
+        [BlobMsgPayload::String("br-lan")]
+    ));
+    // A NaN is unchanged against itself, repeated keys compare their last value once
+    let old = BlobMsgValue::Table(Vec::from([
+        ("x".into(), BlobMsgValue::Double(f64::NAN)),
+        ("a".into(), BlobMsgValue::Int32(1)),
+        ("a".into(), BlobMsgValue::Int32(2)),
+    ]));
+    let new = BlobMsgValue::Table(Vec::from([
+        ("a".into(), BlobMsgValue::Int32(3)),
+        ("x".into(), BlobMsgValue::Double(f64::NAN)),
+        ("a".into(), BlobMsgValue::Int32(2)),
+        ("b".into(), BlobMsgValue::Int32(4)),
+        ("b".into(), BlobMsgValue::Int32(5)),
+    ]));
+    assert!(old.as_payload().diff(&old.as_payload()).is_empty());
+    let changes: Vec<_> = old
+        .as_payload()
+        .diff(&new.as_payload())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["+ @.b: 5"]);
+}
+
+#[test]
+fn merge_patch() {
+    let target = blobmsg!({
+        "a": "b",
+        "c": { "d": "e", "f": "g" },
+        "list": [1, 2],
+    })
+    .to_bytes()
+    .unwrap();
+    let patch = blobmsg!({
+        "a": "z",
+        "c": { "f": null, "h": { "i": null, "j": 1 } },
+        "list": [3],
+        "k": null,
+    })
+    .to_bytes()
+    .unwrap();
+
+    let patched = table(&target).patched(&table(&patch));
+    assert_eq!(
+        patched,
+        blobmsg!({
+            "a": "z",
+            "c": { "d": "e", "h": { "j": 1 } },
+            "list": [3],
+        })
+    );
+
+    // Applying the same patch again changes nothing
+    let again = patched.to_bytes().unwrap();
+    assert!(table(&again).diff(&table(&again)).is_empty());
+    assert_eq!(table(&again).patched(&table(&patch)), patched);
+
+    // Anything but a table replaces the target, duplicates keep the last one
+    let mut value = blobmsg!({ "dup": 1, "dup": 2 });
+    value.merge_patch(&BlobMsgPayload::Int8(1));
+    assert_eq!(value, BlobMsgValue::Int8(1));
+    let mut value = blobmsg!({ "dup": { "x": 1 }, "dup": { "y": 2 } });
+    let patch = blobmsg!({ "dup": { "z": 3 } });
+    value.merge_patch(&patch.as_payload());
+    assert_eq!(
+        value,
+        blobmsg!({ "dup": { "x": 1 }, "dup": { "y": 2, "z": 3 } })
+    );
+}
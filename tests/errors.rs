@@ -0,0 +1,197 @@
+use std::format;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use ubus::*;
+
+mod common;
+use common::{Replay, replies};
+
+const HELLO: &[u8] = include_bytes!("corpus/hello.bin");
+
+/// A peer answering every request with `status`
+fn connect(status: UbusStatus) -> Connection<UnixStream> {
+    let (client, mut server) = UnixStream::pair().unwrap();
+
+    std::thread::spawn(move || {
+        server.write_all(HELLO).unwrap();
+        loop {
+            let mut head = [0u8; UbusMsgHeader::SIZE + BlobTag::SIZE];
+            if server.read_exact(&mut head).is_err() {
+                return;
+            }
+            let (header, tag) = head.split_first_chunk().unwrap();
+            let header = UbusMsgHeader::from_bytes(*header);
+            let tag = BlobTag::from_bytes(*tag.first_chunk().unwrap());
+            let mut attrs = std::vec![0u8; tag.inner_len()];
+            server.read_exact(&mut attrs).unwrap();
+
+            let reply = UbusCmd::Status(StatusMsg {
+                code: status,
+                obj: None,
+            });
+            let mut buffer = [0u8; 64];
+            let reply = reply
+                .to_message(&mut buffer, &header)
+                .unwrap()
+                .finish()
+                .unwrap();
+            server.write_all(reply).unwrap();
+        }
+    });
+
+    Connection::new(client).unwrap()
+}
+
+#[test]
+fn status() {
+    assert_eq!(UbusStatus::from(3), UbusStatus::METHOD_NOT_FOUND);
+    assert_eq!(UbusStatus::PERMISSION_DENIED.value(), 6);
+    assert_eq!(UbusStatus::NO_DATA.message(), "No response");
+    assert_eq!(format!("{:?}", UbusStatus::TIMEOUT), "TIMEOUT");
+    assert_eq!(format!("{}", UbusStatus::TIMEOUT), "Request timed out (7)");
+    assert_eq!(format!("{:?}", UbusStatus::from(99)), "UNKNOWN(99)");
+    assert_eq!(format!("{}", UbusStatus::from(99)), "Unknown error (99)");
+    assert_eq!(serde_json::to_string(&UbusStatus::NOT_FOUND).unwrap(), "4");
+}
+
+#[test]
+fn invoke() {
+    let mut connection = connect(UbusStatus::METHOD_NOT_FOUND);
+    let err = connection
+        .invoke(0x13333337, "info", &[], |_| unreachable!())
+        .unwrap_err();
+    assert_eq!(err.status(), Some(UbusStatus::METHOD_NOT_FOUND));
+    assert_eq!(
+        err.to_string(),
+        "Calling info on 0x13333337: Ubus status: Method not found (3)"
+    );
+    let UbusError::Call {
+        path,
+        obj,
+        method,
+        source,
+    } = err
+    else {
+        panic!("{:?}", err);
+    };
+    assert_eq!(path, None);
+    assert_eq!(obj, Some(0x13333337));
+    assert_eq!(method, "info");
+    assert!(matches!(
+        *source,
+        UbusError::Status(UbusStatus::METHOD_NOT_FOUND)
+    ));
+
+    // The same for replies decoded as they arrive
+    /// Ignores everything
+    struct Nothing;
+    impl BlobMsgVisitor for Nothing {
+        fn enter_table(&mut self, _name: &str) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn enter_array(&mut self, _name: &str) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn leave(&mut self) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn scalar(&mut self, _name: &str, _value: BlobMsgPayload) -> Result<(), UbusError> {
+            Ok(())
+        }
+    }
+    let err = connection
+        .invoke_visit(0x13333337, "info", &[], &mut Nothing)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        UbusError::Call {
+            obj: Some(0x13333337),
+            ..
+        }
+    ));
+    assert_eq!(err.status(), Some(UbusStatus::METHOD_NOT_FOUND));
+}
+
+#[test]
+fn call() {
+    // ubusd answers a lookup of an unknown path with NOT_FOUND
+    let mut connection = connect(UbusStatus::NOT_FOUND);
+    let err = connection.call("network.nope", "status", "").unwrap_err();
+    assert_eq!(err.status(), Some(UbusStatus::NOT_FOUND));
+    assert_eq!(
+        err.to_string(),
+        "Calling status on network.nope: Ubus status: Not found (4)"
+    );
+    assert!(matches!(
+        err,
+        UbusError::Call { path: Some(path), obj: None, .. } if path == "network.nope"
+    ));
+
+    // No object found at all
+    let mut connection = connect(UbusStatus::OK);
+    let err = connection.call("network.nope", "status", "").unwrap_err();
+    assert_eq!(err.status(), Some(UbusStatus::NOT_FOUND));
+
+    // Errors without a status have none
+    assert_eq!(UbusError::InvalidData("x").status(), None);
+}
+
+#[test]
+fn signature() {
+    // A method whose policy is not a table
+    let mut signature = BlobMsgBuilder::new();
+    signature.add_int32("status", 0).unwrap();
+    let stream = replies(&[
+        UbusCmd::LookupReply(LookupReplyMsg {
+            path: "test",
+            id: 0x13333337,
+            ty: 0x42,
+            signature: signature.data(),
+        }),
+        UbusCmd::Status(StatusMsg {
+            code: UbusStatus::OK,
+            obj: None,
+        }),
+    ]);
+
+    let mut connection = Connection::new(Replay(&stream)).unwrap();
+    let err = connection.lookup("test", |_| unreachable!()).unwrap_err();
+    assert!(matches!(err, UbusError::AttributeType { ref name, .. } if name == "status"));
+
+    // The same with callbacks per object and method
+    let mut connection = Connection::new(Replay(&stream)).unwrap();
+    let mut objects = 0;
+    let err = connection
+        .lookup_cb("test", |_| objects += 1, |_| unreachable!())
+        .unwrap_err();
+    assert_eq!(objects, 1);
+    assert!(matches!(err, UbusError::AttributeType { ref name, .. } if name == "status"));
+}
+
+#[test]
+fn unexpected_reply() {
+    // A DATA reply without data is no result of an invoke
+    let stream = replies(&[
+        UbusCmd::ObjectAdded(ObjectAddedMsg {
+            id: 0x13333337,
+            ty: None,
+        }),
+        UbusCmd::Status(StatusMsg {
+            code: UbusStatus::OK,
+            obj: None,
+        }),
+    ]);
+    let mut connection = Connection::new(Replay(&stream)).unwrap();
+    let err = connection
+        .invoke(0x13333337, "info", &[], |_| unreachable!())
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(*source, UbusError::InvalidData(_)));
+
+    // Nor of a lookup
+    let mut connection = Connection::new(Replay(&stream)).unwrap();
+    let err = connection.lookup("test", |_| unreachable!()).unwrap_err();
+    assert!(matches!(err, UbusError::InvalidData(_)));
+}
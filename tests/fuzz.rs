@@ -0,0 +1,212 @@
+use serde_json::Value;
+use std::format;
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+/// A sample of the messages in `corpus/`, captured from the `list`, `invoke` and `status` fixtures
+const CORPUS: &[(&str, &[u8])] = &[
+    ("hello", include_bytes!("corpus/hello.bin")),
+    ("invoke_data", include_bytes!("corpus/invoke_data.bin")),
+    (
+        "invoke_request",
+        include_bytes!("corpus/invoke_request.bin"),
+    ),
+    ("invoke_status", include_bytes!("corpus/invoke_status.bin")),
+    ("list_data_0", include_bytes!("corpus/list_data_0.bin")),
+    ("list_data_1", include_bytes!("corpus/list_data_1.bin")),
+    ("list_data_2", include_bytes!("corpus/list_data_2.bin")),
+    ("list_data_25", include_bytes!("corpus/list_data_25.bin")),
+    ("list_request", include_bytes!("corpus/list_request.bin")),
+    ("list_status", include_bytes!("corpus/list_status.bin")),
+    ("status_data", include_bytes!("corpus/status_data.bin")),
+    (
+        "status_request",
+        include_bytes!("corpus/status_request.bin"),
+    ),
+    ("status_status", include_bytes!("corpus/status_status.bin")),
+];
+
+const ROUNDS: usize = 2000;
+
+/// xorshift64, deterministic so failures can be reproduced
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
+
+fn mutate(rng: &mut Rng, seed: &[u8]) -> Vec<u8> {
+    let mut data = seed.to_vec();
+    for _ in 0..=rng.below(4) {
+        let at = rng.below(data.len());
+        match rng.below(6) {
+            0 if !data.is_empty() => data[at] ^= 1 << rng.below(8),
+            1 if !data.is_empty() => data[at] = rng.next() as u8,
+            2 => data.truncate(at),
+            // Tamper with an aligned word, most likely a blob tag or length
+            3 if data.len() >= 4 => {
+                let at = at & !3;
+                let end = (at + 4).min(data.len());
+                let word = rng.next().to_be_bytes();
+                data[at..end].copy_from_slice(&word[..end - at]);
+            }
+            4 => data.insert(at, rng.next() as u8),
+            _ if !data.is_empty() => {
+                let len = rng.below(data.len() - at);
+                data.extend_from_within(at..at + len);
+            }
+            _ => {}
+        }
+    }
+    data
+}
+
+fn walk(view: BlobMsgView, depth: usize) {
+    let _ = (view.name(), view.as_str(), view.as_bool(), view.as_int());
+    let _ = (view.as_f64(), view.len(), view.nth(1), view.get("up"));
+    let _ = view.decode().map(|payload| format!("{}", payload));
+    for child in view.iter().take(64) {
+        match child {
+            Ok(child) if depth < 16 => walk(child, depth + 1),
+            _ => {}
+        }
+    }
+}
+
+fn decode_blobmsg(data: &[u8]) {
+    for blob in BlobIter::<Blob>::new(data) {
+        let Ok(blob) = blob else { break };
+        if let Ok(msg) = TryInto::<BlobMsg>::try_into(blob) {
+            let _ = format!("{}", msg);
+            let _ = Value::from(&msg);
+            let _ = format!("{}", msg.to_owned_value());
+        }
+    }
+    let _ = Value::try_from(BlobIter::<Blob>::new(data));
+    let _ = BlobMsgValue::try_from(BlobIter::<Blob>::new(data));
+    walk(BlobMsgView::table(data), 0);
+    let policy = [
+        BlobMsgPolicy::new("up", BlobMsgType::BOOL),
+        BlobMsgPolicy::new("uptime", BlobMsgType::INT32),
+        BlobMsgPolicy::new("device", BlobMsgType::STRING),
+    ];
+    let _ = BlobMsgPolicy::parse(&policy, data);
+}
+
+fn decode_message(raw: &[u8]) {
+    let mut buffer = [0u8; 4096];
+    let Ok(message) = UbusMsg::from_io(&mut Replay(raw), &mut buffer) else {
+        return;
+    };
+    let _ = format!("{:?}", message);
+    for attr in BlobIter::<UbusMsgAttr>::new(message.blob.data) {
+        match attr {
+            Ok(UbusMsgAttr::Data(data)) => decode_blobmsg(data),
+            Ok(UbusMsgAttr::Signature(table)) => {
+                let _ = format!("{:?}", table);
+            }
+            Ok(UbusMsgAttr::Subscribers(iter)) => iter.take(64).for_each(drop),
+            Ok(_) => {}
+            Err(e) => {
+                let _ = format!("{}", e);
+            }
+        }
+    }
+}
+
+fn drive_connection(raw: &[u8]) {
+    let mut stream = CORPUS[0].1.to_vec();
+    stream.extend_from_slice(raw);
+    if let Ok(mut connection) = Connection::new(Replay(&stream)) {
+        let _ = connection.lookup("", |obj| {
+            let _ = obj.to_owned_value();
+        });
+    }
+    if let Ok(mut connection) = Connection::new(Replay(&stream)) {
+        let _ = connection.invoke(0, "status", &[], |iter| {
+            decode_blobmsg(BlobMsgView::from(iter).data())
+        });
+    }
+}
+
+#[test]
+fn corpus_decodes() {
+    for (name, raw) in CORPUS {
+        let mut buffer = [0u8; 4096];
+        let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+        for attr in BlobIter::<UbusMsgAttr>::new(message.blob.data) {
+            assert!(attr.is_ok(), "{}: {:?}", name, attr);
+        }
+    }
+}
+
+#[test]
+fn mutated_corpus_never_panics() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for (_, seed) in CORPUS {
+        for round in 0..ROUNDS {
+            let raw = mutate(&mut rng, seed);
+            decode_message(&raw);
+            if round % 8 == 0 {
+                drive_connection(&raw);
+            }
+        }
+    }
+}
+
+#[test]
+fn truncated_attribute_is_reported() {
+    let (_, raw) = CORPUS[10];
+    let mut buffer = [0u8; 4096];
+    let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+    let Some(Ok(UbusMsgAttr::Data(data))) = BlobIter::<UbusMsgAttr>::new(message.blob.data)
+        .find(|attr| matches!(attr, Ok(UbusMsgAttr::Data(_))))
+    else {
+        panic!("no data attribute");
+    };
+
+    // Cut the reply in the middle of an attribute
+    let data = &data[..data.len() - 10];
+    let items: Vec<_> = BlobIter::<Blob>::new(data).collect();
+    assert!(matches!(items.last(), Some(Err(UbusError::InvalidData(_)))));
+    assert_eq!(items.iter().filter(|item| item.is_err()).count(), 1);
+    assert!(Value::try_from(BlobIter::<Blob>::new(data)).is_err());
+
+    // A too short integer is an error, not a panic
+    let mut builder = BlobMsgBuilder::new_extended(BlobMsgType::INT64.value(), "uptime").unwrap();
+    builder.push_int32(1).unwrap();
+    let result: Result<BlobMsg, _> = builder.build().unwrap().try_into();
+    assert!(matches!(
+        result,
+        Err(UbusError::PayloadTooShort {
+            needed: 8,
+            found: 4
+        })
+    ));
+}
+
+#[test]
+fn invalid_attribute_names_its_id() {
+    // STATUS attribute with a 2 byte payload
+    let raw = [
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x01, 0x00, 0x00,
+        0x06, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let mut buffer = [0u8; 64];
+    let message = UbusMsg::from_io(&mut Replay(&raw), &mut buffer).unwrap();
+    let attr = BlobIter::<UbusMsgAttr>::new(message.blob.data).next();
+    assert!(matches!(
+        attr,
+        Some(Err(UbusError::InvalidAttribute { id: 1, .. }))
+    ));
+}
@@ -2,8 +2,7 @@ use std::io::{Read, Write};
 use std::os::unix::net::UnixStream;
 use ubus::*;
 
-#[test]
-fn test() {
+fn connect() -> Connection<UnixStream> {
     let (client, mut server) = UnixStream::pair().unwrap();
 
     std::thread::spawn(move || {
@@ -16,17 +15,128 @@ fn test() {
         }
     });
 
-    let mut connection = Connection::new(client).unwrap();
+    Connection::new(client).unwrap()
+}
 
+#[test]
+fn test() {
+    let mut connection = connect();
     connection
         .invoke(0x13333337, "info", &[], |x| {
             for i in x {
-                std::dbg!(i);
+                std::dbg!(i.unwrap());
             }
         })
         .unwrap();
 }
 
+/// Counts values and keeps the integers of the `memory` table
+#[derive(Default)]
+struct Memory {
+    depth: usize,
+    values: usize,
+    memory: Vec<(String, i64)>,
+    inside: bool,
+}
+
+impl BlobMsgVisitor for Memory {
+    fn enter_table(&mut self, name: &str) -> Result<(), UbusError> {
+        self.depth += 1;
+        self.inside = name == "memory";
+        Ok(())
+    }
+    fn enter_array(&mut self, _name: &str) -> Result<(), UbusError> {
+        self.depth += 1;
+        Ok(())
+    }
+    fn leave(&mut self) -> Result<(), UbusError> {
+        self.depth -= 1;
+        self.inside = false;
+        Ok(())
+    }
+    fn scalar(&mut self, name: &str, value: BlobMsgPayload) -> Result<(), UbusError> {
+        self.values += 1;
+        if let (true, BlobMsgPayload::Int64(value)) = (self.inside, value) {
+            self.memory.push((name.into(), value));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn visit() {
+    let mut connection = connect();
+    let mut memory = Memory::default();
+    connection
+        .invoke_visit(0x13333337, "info", &[], &mut memory)
+        .unwrap();
+    assert_eq!(memory.depth, 0);
+    assert_eq!(memory.values, 13);
+    assert_eq!(
+        memory.memory,
+        [
+            ("total".into(), 513433600),
+            ("free".into(), 475082752),
+            ("shared".into(), 65536),
+            ("buffered".into(), 860160),
+            ("available".into(), 454533120),
+            ("cached".into(), 10055680),
+        ]
+    );
+}
+
+#[test]
+fn visit_error() {
+    let (client, mut server) = UnixStream::pair().unwrap();
+    std::thread::spawn(move || {
+        server.write_all(TEST_HELLO).unwrap();
+        // Answer two calls, with the sequence number of each
+        for _ in 0..2 {
+            let mut command = [0u8; TEST_TX.len()];
+            server.read_exact(&mut command).unwrap();
+            for (i, reply) in TEST_RX.iter().enumerate() {
+                let mut reply = reply.to_vec();
+                if i % 2 == 0 {
+                    reply[2..4].copy_from_slice(&command[2..4]);
+                }
+                server.write_all(&reply).unwrap();
+            }
+        }
+    });
+    let mut connection = Connection::new(client).unwrap();
+
+    /// Fails on the first value
+    struct Fail;
+    impl BlobMsgVisitor for Fail {
+        fn enter_table(&mut self, _name: &str) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn enter_array(&mut self, _name: &str) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn leave(&mut self) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn scalar(&mut self, _name: &str, _value: BlobMsgPayload) -> Result<(), UbusError> {
+            Err(UbusError::InvalidData("stop"))
+        }
+    }
+    let err = connection
+        .invoke_visit(0x13333337, "info", &[], &mut Fail)
+        .unwrap_err();
+    let UbusError::Call { source, .. } = err else {
+        panic!("{:?}", err);
+    };
+    assert!(matches!(*source, UbusError::InvalidData("stop")));
+
+    // The rest of the reply was dropped, the next call reads its own
+    let mut memory = Memory::default();
+    connection
+        .invoke_visit(0x13333337, "info", &[], &mut memory)
+        .unwrap();
+    assert_eq!(memory.values, 13);
+}
+
 const TEST_HELLO: &[u8] = &[
     0x00, 0x00, 0x00, 0x00, 0x2e, 0xb8, 0x63, 0xdb, 0x00, 0x00, 0x00, 0x04,
 ];
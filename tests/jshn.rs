@@ -0,0 +1,205 @@
+use serde_json::{Value, json};
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+fn encode(value: &BlobMsgValue) -> Vec<u8> {
+    let BlobMsgValue::Table(table) = value else {
+        panic!("expected a table");
+    };
+    let mut builder = BlobMsgBuilder::new();
+    for (name, value) in table {
+        builder
+            .add(&BlobMsg {
+                name,
+                data: value.as_payload(),
+            })
+            .unwrap();
+    }
+    builder.data().to_vec()
+}
+
+#[test]
+fn output() {
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_bool("up", true).unwrap();
+    builder.add_string("name", "it's \"lan\"").unwrap();
+    builder.add_int64("rx_bytes", 5_000_000_000).unwrap();
+    builder.add_double("load", 0.5).unwrap();
+    builder.open_array("dns").unwrap();
+    builder.add_string("", "1.1.1.1").unwrap();
+    builder.open_table("").unwrap();
+    builder.add_int16("metric", -1).unwrap();
+    builder.close().unwrap();
+    builder.close().unwrap();
+    builder.open_table("data").unwrap();
+    builder.close().unwrap();
+    builder.add_field(42, "future", &[1, 2, 3]).unwrap();
+
+    let mut jshn = JshnWriter::new(String::new());
+    jshn.write_table(BlobIter::new(builder.data())).unwrap();
+    let script = jshn.into_inner();
+    assert_eq!(
+        script,
+        "json_init;\n\
+         json_add_boolean 'up' 1;\n\
+         json_add_string 'name' 'it'\\''s \"lan\"';\n\
+         json_add_int 'rx_bytes' 5000000000;\n\
+         json_add_double 'load' 0.500000;\n\
+         json_add_array 'dns';\n\
+         json_add_string '0' '1.1.1.1';\n\
+         json_add_object '1';\n\
+         json_add_int 'metric' -1;\n\
+         json_close_object;\n\
+         json_close_array;\n\
+         json_add_object 'data';\n\
+         json_close_object;\n\
+         json_add_null 'future';\n"
+    );
+
+    let parsed = parse_jshn(&script).unwrap();
+    assert_eq!(
+        parsed.get("name"),
+        Some(&BlobMsgValue::String("it's \"lan\"".into()))
+    );
+    assert_eq!(
+        parsed.get("rx_bytes"),
+        Some(&BlobMsgValue::Int64(5_000_000_000))
+    );
+    assert_eq!(
+        parsed.get("dns"),
+        Some(&BlobMsgValue::Array(Vec::from([
+            BlobMsgValue::String("1.1.1.1".into()),
+            BlobMsgValue::Table(Vec::from([("metric".into(), BlobMsgValue::Int32(-1))])),
+        ])))
+    );
+    assert_eq!(
+        Value::try_from(BlobIter::<Blob>::new(&encode(&parsed))).unwrap(),
+        json!({"up": true, "name": "it's \"lan\"", "rx_bytes": 5_000_000_000i64, "load": 0.5,
+               "dns": ["1.1.1.1", {"metric": -1}], "data": {}, "future": null})
+    );
+}
+
+#[test]
+fn status_reply() {
+    let raw = include_bytes!("corpus/status_data.bin");
+    let mut buffer = [0u8; 4096];
+    let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+    let data = BlobIter::<UbusMsgAttr>::new(message.blob.data)
+        .find_map(|attr| match attr.unwrap() {
+            UbusMsgAttr::Data(data) => Some(data),
+            _ => None,
+        })
+        .unwrap();
+
+    let mut jshn = JshnWriter::new(String::new());
+    jshn.write_table(BlobIter::new(data)).unwrap();
+    let parsed = parse_jshn(&jshn.into_inner()).unwrap();
+    assert_eq!(
+        Value::try_from(BlobIter::<Blob>::new(&encode(&parsed))).unwrap(),
+        Value::try_from(BlobIter::<Blob>::new(data)).unwrap()
+    );
+
+    let table: BlobMsgTable = BlobIter::<Blob>::new(data)
+        .map(|blob| {
+            let msg: BlobMsg = blob.unwrap().try_into().unwrap();
+            (msg.name, msg.data)
+        })
+        .collect();
+    assert_eq!(parse_jshn(&table.to_jshn()).unwrap(), parsed);
+}
+
+#[test]
+fn shell_syntax() {
+    let script = r#"
+        # written by hand
+        json_init
+        json_add_string name "eth\"0\"" ; json_add_int mtu 1500
+        json_add_array   vlans
+            json_add_int "" 1; json_add_int '' 2
+        json_close_array
+        json_add_string path 'a'\''b'c\ d
+        json_add_boolean up 0
+        json_add_double weight 1e3
+    "#;
+    let parsed = parse_jshn(script).unwrap();
+    assert_eq!(
+        parsed,
+        BlobMsgValue::Table(Vec::from([
+            ("name".into(), BlobMsgValue::String("eth\"0\"".into())),
+            ("mtu".into(), BlobMsgValue::Int32(1500)),
+            (
+                "vlans".into(),
+                BlobMsgValue::Array(Vec::from([BlobMsgValue::Int32(1), BlobMsgValue::Int32(2)]))
+            ),
+            ("path".into(), BlobMsgValue::String("a'bc d".into())),
+            ("up".into(), BlobMsgValue::Bool(false)),
+            ("weight".into(), BlobMsgValue::Double(1000.0)),
+        ]))
+    );
+
+    // A later json_init starts over
+    let parsed = parse_jshn("json_add_int a 1\njson_init\njson_add_int b 2").unwrap();
+    assert_eq!(
+        parsed,
+        BlobMsgValue::Table(Vec::from([("b".into(), BlobMsgValue::Int32(2))]))
+    );
+}
+
+#[test]
+fn errors() {
+    let cases = [
+        ("json_init\njson_add_int mtu 15OO", 2, "invalid integer"),
+        (
+            "json_init\n\njson_add_object a\n",
+            4,
+            "unterminated object or array",
+        ),
+        (
+            "json_add_array a; json_close_object",
+            1,
+            "closes the wrong kind of container",
+        ),
+        ("json_close_array", 1, "nothing to close"),
+        ("json_add_string a", 1, "wrong number of arguments"),
+        ("json_add_int a 1 2", 1, "too many arguments"),
+        ("json_init\njson_get_var x y", 2, "unknown command"),
+        ("json_add_string a 'b", 1, "unterminated quote"),
+        (
+            "json_add_string a \"$HOME\"",
+            1,
+            "shell expansion is not supported",
+        ),
+        (
+            "json_add_string a $(id)",
+            1,
+            "shell syntax is not supported",
+        ),
+    ];
+    for (script, line, problem) in cases {
+        match parse_jshn(script) {
+            Err(UbusError::Jshn {
+                line: found_line,
+                problem: found,
+            }) => assert_eq!((found_line, found), (line, problem), "{}", script),
+            other => panic!("{}: {:?}", script, other),
+        }
+    }
+
+    let deep = "json_add_object a\n".repeat(64);
+    assert!(matches!(
+        parse_jshn(&deep),
+        Err(UbusError::Jshn {
+            problem: "nested too deep",
+            ..
+        })
+    ));
+    assert_eq!(
+        parse_jshn("json_init\njson_close_object")
+            .unwrap_err()
+            .to_string(),
+        "jshn line 2: nothing to close"
+    );
+}
@@ -0,0 +1,150 @@
+use serde_json::Value;
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+fn sample() -> Vec<u8> {
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_bool("up", true).unwrap();
+    builder.add_string("path", "/etc/config\t\"x\"\\").unwrap();
+    builder.open_array("dns").unwrap();
+    builder.add_string("", "1.1.1.1").unwrap();
+    builder.add_int16("", -2).unwrap();
+    builder.close().unwrap();
+    builder.open_table("data").unwrap();
+    builder.close().unwrap();
+    builder.add_double("load", 0.25).unwrap();
+    builder.data().to_vec()
+}
+
+fn render(data: &[u8], style: JsonStyle) -> String {
+    let mut json = JsonWriter::new(String::new(), style);
+    json.write_table(BlobIter::new(data)).unwrap();
+    json.into_inner()
+}
+
+#[test]
+fn styles() {
+    let data = sample();
+    assert_eq!(
+        render(&data, JsonStyle::Indent),
+        "{\n\
+         \t\"up\": true,\n\
+         \t\"path\": \"\\/etc\\/config\\t\\\"x\\\"\\\\\",\n\
+         \t\"dns\": [\n\
+         \t\t\"1.1.1.1\",\n\
+         \t\t-2\n\
+         \t],\n\
+         \t\"data\": {\n\
+         \t\t\n\
+         \t},\n\
+         \t\"load\": 0.250000\n\
+         }"
+    );
+    assert_eq!(
+        render(&data, JsonStyle::Simple),
+        r#"{"up":true,"path":"\/etc\/config\t\"x\"\\","dns":["1.1.1.1",-2],"data":{},"load":0.250000}"#
+    );
+    let compact = render(&data, JsonStyle::Compact);
+    assert_eq!(
+        compact,
+        r#"{"up": true, "path": "\/etc\/config\t\"x\"\\", "dns": ["1.1.1.1", -2], "data": {}, "load": 0.250000}"#
+    );
+    for style in [JsonStyle::Compact, JsonStyle::Indent, JsonStyle::Simple] {
+        let parsed: Value = serde_json::from_str(&render(&data, style)).unwrap();
+        assert_eq!(
+            parsed,
+            Value::try_from(BlobIter::<Blob>::new(&data)).unwrap()
+        );
+    }
+
+    // Display uses the compact form
+    let msg: BlobMsg = BlobIter::<Blob>::new(&data)
+        .nth(2)
+        .unwrap()
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(msg.to_string(), r#""dns": ["1.1.1.1", -2]"#);
+    assert_eq!(msg.data.to_string(), r#"["1.1.1.1", -2]"#);
+}
+
+#[test]
+fn escaping() {
+    let s = "\u{8}\u{c}\n\r\t\u{0}\u{1f} é\u{7f}\"/\\";
+    let json = BlobMsgPayload::String(s).to_json(JsonStyle::Simple);
+    // DEL and non-ASCII are passed through like libubox does
+    assert_eq!(
+        json,
+        "\"\\b\\u000c\\n\\r\\t\\u0000\\u001f é\u{7f}\\\"\\/\\\\\""
+    );
+    assert_eq!(serde_json::from_str::<String>(&json).unwrap(), s);
+
+    // Names are escaped too
+    let msg = BlobMsg {
+        name: "a\"b",
+        data: BlobMsgPayload::Int32(1),
+    };
+    assert_eq!(msg.to_string(), r#""a\"b": 1"#);
+}
+
+#[test]
+fn always_valid() {
+    let mut table = BlobMsgTable::new();
+    table.push("", BlobMsgPayload::Bool(false));
+    table.push("nan", BlobMsgPayload::Double(f64::NAN));
+    table.push("inf", BlobMsgPayload::Double(f64::NEG_INFINITY));
+    table.push("future", BlobMsgPayload::Unknown(42, &[1, 2]));
+    table.push("big", BlobMsgPayload::Double(1e20));
+    let payload = BlobMsgPayload::Table(table);
+    let json = payload.to_json(JsonStyle::Simple);
+    assert_eq!(
+        json,
+        r#"{"":false,"nan":null,"inf":null,"future":null,"big":100000000000000000000.000000}"#
+    );
+    assert!(serde_json::from_str::<Value>(&json).is_ok());
+
+    let mut out = Vec::new();
+    payload.write_json_io(&mut out, JsonStyle::Simple).unwrap();
+    assert_eq!(out, json.as_bytes());
+
+    // I/O errors are passed through
+    let mut full = [0u8; 8];
+    let err = payload
+        .write_json_io(&mut full[..], JsonStyle::Simple)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+}
+
+#[test]
+fn status_reply() {
+    let raw = include_bytes!("corpus/status_data.bin");
+    let mut buffer = [0u8; 4096];
+    let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+    let data = BlobIter::<UbusMsgAttr>::new(message.blob.data)
+        .find_map(|attr| match attr.unwrap() {
+            UbusMsgAttr::Data(data) => Some(data),
+            _ => None,
+        })
+        .unwrap();
+
+    let json = render(data, JsonStyle::Indent);
+    assert!(json.starts_with("{\n\t\"up\": true,\n\t\"pending\": false,\n"));
+    assert!(json.contains("\t\"inactive\": {\n\t\t\"ipv4-address\": [\n\t\t\t\n\t\t],\n"));
+    assert!(json.ends_with("\n\t}\n}"));
+    let parsed: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        parsed,
+        Value::try_from(BlobIter::<Blob>::new(data)).unwrap()
+    );
+
+    // A truncated reply is reported rather than rendered
+    let mut writer = JsonWriter::new(String::new(), JsonStyle::Indent);
+    assert!(
+        writer
+            .write_table(BlobIter::new(&data[..data.len() - 10]))
+            .is_err()
+    );
+}
@@ -0,0 +1,190 @@
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::{Replay, replies};
+
+/// Tables named "t" nested `depth` times around an INT32
+fn nested(depth: usize) -> Vec<u8> {
+    let mut blob = BlobMsgBuilder::new_extended(BlobMsgType::INT32.value(), "n").unwrap();
+    blob.push_int32(1).unwrap();
+    let mut data = blob.data().to_vec();
+    for _ in 0..depth {
+        let mut table = BlobMsgBuilder::new_extended(BlobMsgType::TABLE.value(), "t").unwrap();
+        table.push_bytes(&data).unwrap();
+        data = table.data().to_vec();
+    }
+    data
+}
+
+#[test]
+fn depth() {
+    let data = nested(32);
+    let blob = Blob::from_bytes(&data).unwrap();
+    let limits = DecodeLimits::default();
+    assert!(blob.decode_with(&limits).is_ok());
+
+    let data = nested(33);
+    let blob = Blob::from_bytes(&data).unwrap();
+    assert!(matches!(
+        blob.decode_with(&limits),
+        Err(UbusError::NestingTooDeep(32))
+    ));
+    let result: Result<BlobMsg, _> = blob.try_into();
+    assert!(matches!(result, Err(UbusError::NestingTooDeep(32))));
+
+    let limits = DecodeLimits {
+        max_depth: 64,
+        ..DecodeLimits::default()
+    };
+    assert!(blob.decode_with(&limits).is_ok());
+
+    // Views only descend on demand, but decoding one is still bounded
+    let view = BlobMsgView::try_from(blob).unwrap();
+    assert!(matches!(view.decode(), Err(UbusError::NestingTooDeep(32))));
+}
+
+#[test]
+fn attributes() {
+    let mut list = BlobMsgBuilder::new_extended(BlobMsgType::ARRAY.value(), "list").unwrap();
+    for _ in 0..100 {
+        let mut item = BlobMsgBuilder::new_extended(BlobMsgType::BOOL.value(), "").unwrap();
+        item.push_bool(true).unwrap();
+        list.push_bytes(item.data()).unwrap();
+    }
+    let blob = list.build().unwrap();
+    let limits = DecodeLimits {
+        max_attrs: 100,
+        ..DecodeLimits::default()
+    };
+    assert!(blob.decode_with(&limits).is_ok());
+
+    let limits = DecodeLimits {
+        max_attrs: 99,
+        ..DecodeLimits::default()
+    };
+    assert!(matches!(
+        blob.decode_with(&limits),
+        Err(UbusError::TooManyAttributes(99))
+    ));
+}
+
+#[test]
+fn message_len() {
+    // DATA message announcing a 64 KiB blob
+    let raw = [
+        0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+    ];
+    let mut buffer = [0u8; 128];
+    let limits = DecodeLimits {
+        max_message_len: 1024,
+        ..DecodeLimits::default()
+    };
+    assert!(matches!(
+        UbusMsg::from_io_with(&mut Replay(&raw), &mut buffer, &limits),
+        Err(UbusError::MessageTooLarge {
+            size: 0x10008,
+            max: 1024
+        })
+    ));
+    // Without a limit the receive buffer still bounds the message
+    assert!(matches!(
+        UbusMsg::from_io_with(&mut Replay(&raw), &mut buffer, &DecodeLimits::UNLIMITED),
+        Err(UbusError::InvalidData(_))
+    ));
+}
+
+#[test]
+fn connection() {
+    const HELLO: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x2e, 0xb8, 0x63, 0xdb, 0x00, 0x00, 0x00, 0x04,
+    ];
+    let mut stream = HELLO.to_vec();
+    stream.extend_from_slice(&[
+        0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00,
+    ]);
+    stream.resize(HELLO.len() + 12 + 0x1000 - 4, 0);
+
+    let mut connection = Connection::new(Replay(&stream)).unwrap();
+    assert_eq!(connection.limits(), &DecodeLimits::default());
+    connection.set_limits(DecodeLimits {
+        max_message_len: 0x1000,
+        ..DecodeLimits::default()
+    });
+    assert!(matches!(
+        connection.next_message(),
+        Err(UbusError::MessageTooLarge {
+            size: 0x1008,
+            max: 0x1000
+        })
+    ));
+}
+
+#[test]
+fn lookup() {
+    let mut signature = BlobMsgBuilder::new();
+    signature.open_table("set").unwrap();
+    signature.add_int32("name", 3).unwrap();
+    signature.add_int32("mtu", 5).unwrap();
+    signature.close().unwrap();
+    let stream = replies(&[
+        UbusCmd::LookupReply(LookupReplyMsg {
+            path: "test",
+            id: 0x13333337,
+            ty: 0x42,
+            signature: signature.data(),
+        }),
+        UbusCmd::Status(StatusMsg {
+            code: UbusStatus::OK,
+            obj: None,
+        }),
+    ]);
+    let shallow = DecodeLimits {
+        max_depth: 1,
+        ..DecodeLimits::default()
+    };
+    let few = DecodeLimits {
+        max_attrs: 2,
+        ..DecodeLimits::default()
+    };
+
+    let mut connection = Connection::new(Replay(&stream)).unwrap();
+    connection.lookup("test", |_| {}).unwrap();
+
+    for (limits, expected) in [
+        (shallow, UbusError::NestingTooDeep(1)),
+        (few, UbusError::TooManyAttributes(2)),
+    ] {
+        let mut connection = Connection::new(Replay(&stream)).unwrap();
+        connection.set_limits(limits);
+        let err = connection.lookup("test", |_| unreachable!()).unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+
+        let mut connection = Connection::new(Replay(&stream)).unwrap();
+        connection.set_limits(limits);
+        let err = connection
+            .lookup_cb("test", |_| {}, |_| unreachable!())
+            .unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+
+        let mut connection = Connection::new(Replay(&stream)).unwrap();
+        connection.set_limits(limits);
+        let err = connection
+            .lookup_in(&mut DecodeContext::new(), "test", |_| unreachable!())
+            .unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+
+    // Signatures decoded on their own take the limits too
+    let len = BlobTag::SIZE + signature.data().len();
+    let tag = BlobTag::new(BlobAttrId::SIGNATURE.value(), len, false);
+    let mut attr = tag.unwrap().to_bytes().to_vec();
+    attr.extend_from_slice(signature.data());
+    let blob = Blob::from_bytes(&attr).unwrap();
+    assert!(UbusMsgAttr::decode_with(blob, &DecodeLimits::default()).is_ok());
+    let err = UbusMsgAttr::decode_with(blob, &shallow).unwrap_err();
+    assert!(
+        err.to_string()
+            .ends_with(&UbusError::NestingTooDeep(1).to_string())
+    );
+}
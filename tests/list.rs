@@ -3,8 +3,7 @@ use std::os::unix::net::UnixStream;
 use std::println;
 use ubus::*;
 
-#[test]
-fn test() {
+fn connect() -> Connection<UnixStream> {
     let (client, mut server) = UnixStream::pair().unwrap();
 
     std::thread::spawn(move || {
@@ -17,7 +16,12 @@ fn test() {
         }
     });
 
-    let mut connection = Connection::new(client).unwrap();
+    Connection::new(client).unwrap()
+}
+
+#[test]
+fn test() {
+    let mut connection = connect();
 
     connection
         .lookup(
@@ -36,6 +40,85 @@ fn test() {
         .unwrap();
 }
 
+#[test]
+fn owned() {
+    let mut connection = connect();
+    let mut objs = Vec::new();
+    connection
+        .lookup("", |obj| objs.push(obj.to_owned_value()))
+        .unwrap();
+    // Objects outlive the connection's receive buffer
+    drop(connection);
+    assert_eq!(objs.len(), 26);
+
+    let file = objs.iter().find(|obj| obj.path == "file").unwrap();
+    assert_eq!(file.id, 0x1adb8557);
+    assert_eq!(file.methods.len(), 7);
+    let write = &file.methods["write"];
+    assert_eq!(write.policy["append"], BlobMsgType::BOOL);
+    assert_eq!(write.policy["mode"], BlobMsgType::INT32);
+
+    // And can be borrowed again to build arguments
+    let args = file
+        .as_object()
+        .args_from_json("write", r#"{"path": "/tmp/x", "append": true}"#)
+        .unwrap();
+    assert_eq!(
+        file.methods["write"]
+            .as_method()
+            .parse_args(&args)
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn context() {
+    let mut expected = Vec::new();
+    connect()
+        .lookup("", |obj| expected.push(obj.to_owned_value()))
+        .unwrap();
+
+    let mut context = DecodeContext::new();
+    for _ in 0..2 {
+        let mut objs = Vec::new();
+        connect()
+            .lookup_in(&mut context, "", |obj| objs.push(obj.to_owned_value()))
+            .unwrap();
+        assert_eq!(objs, expected);
+        // Every map and vector went back, ready for the next lookup
+        assert!(context.pooled() > 0);
+    }
+
+    // The vectors of a recycled tree are used for the next one
+    let data = include_bytes!("corpus/list_data_25.bin");
+    let attrs = &data[UbusMsgHeader::SIZE + BlobTag::SIZE..];
+    let signature = BlobIter::<Blob>::new(attrs)
+        .map(Result::unwrap)
+        .find(|blob| BlobAttrId::from(blob.tag.id()) == BlobAttrId::SIGNATURE)
+        .unwrap();
+    let limits = DecodeLimits::default();
+    let decode = |context: &mut DecodeContext| {
+        context.decode(signature.data.into(), BlobMsgType::TABLE, &limits)
+    };
+    let mut context = DecodeContext::new();
+    let tree = decode(&mut context).unwrap();
+    assert_eq!(
+        tree,
+        Payload::from(signature.data)
+            .decode(BlobMsgType::TABLE)
+            .unwrap()
+    );
+    context.recycle(tree);
+    let pooled = context.pooled();
+    assert!(pooled > 1);
+    let tree = decode(&mut context).unwrap();
+    assert_eq!(context.pooled(), 0);
+    context.recycle(tree);
+    assert_eq!(context.pooled(), pooled);
+}
+
 // Data dumped from `ubus list`
 const TEST_HELLO: &[u8] = &[
     0x00, 0x00, 0x00, 0x00, 0x2e, 0xb8, 0x63, 0xdb, 0x00, 0x00, 0x00, 0x04,
@@ -843,3 +926,36 @@ const TEST_RX: &[&[u8]] = &[
     ],
     &[0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00],
 ];
+
+#[test]
+fn order() {
+    let mut file = None;
+    connect()
+        .lookup("", |obj| {
+            if obj.path == "file" {
+                file = Some(obj.to_owned_value())
+            }
+        })
+        .unwrap();
+    let file = file.unwrap();
+
+    // Methods and arguments keep the order of the signature
+    assert!(
+        file.methods
+            .keys()
+            .eq(["read", "write", "list", "stat", "md5", "remove", "exec"])
+    );
+    assert!(file.methods["write"].policy.keys().eq([
+        "path",
+        "data",
+        "append",
+        "mode",
+        "base64",
+        "ubus_rpc_session"
+    ]));
+    let json = serde_json::to_string(&file.as_object().methods["exec"]).unwrap();
+    assert_eq!(
+        json,
+        r#"{"name":"exec","policy":{"command":3,"params":1,"env":2,"ubus_rpc_session":3}}"#
+    );
+}
@@ -0,0 +1,152 @@
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+fn status(data: &[u8]) -> BlobMsgPayload<'_> {
+    BlobMsgPayload::Table(
+        BlobIter::<Blob>::new(data)
+            .map(|blob| {
+                let msg: BlobMsg = blob.unwrap().try_into().unwrap();
+                (msg.name, msg.data)
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn status_reply() {
+    let raw = include_bytes!("corpus/status_data.bin");
+    let mut buffer = [0u8; 4096];
+    let message = UbusMsg::from_io(&mut Replay(raw), &mut buffer).unwrap();
+    let data = BlobIter::<UbusMsgAttr>::new(message.blob.data)
+        .find_map(|attr| match attr.unwrap() {
+            UbusMsgAttr::Data(data) => Some(data),
+            _ => None,
+        })
+        .unwrap();
+    let status = status(data);
+
+    for path in [
+        "ipv4-address[0].address",
+        "@.ipv4-address[0].address",
+        "$['ipv4-address'][-1][\"address\"]",
+        "/ipv4-address/0/address",
+    ] {
+        let found = status.query(path).unwrap();
+        assert!(
+            matches!(found[..], [BlobMsgPayload::String("192.168.1.1")]),
+            "{}",
+            path
+        );
+    }
+    assert!(matches!(
+        status.pointer("@.uptime"),
+        Some(BlobMsgPayload::Int32(86123))
+    ));
+    assert!(matches!(
+        status.pointer("@"),
+        Some(BlobMsgPayload::Table(_))
+    ));
+
+    // Missing keys, out of range indices and steps into scalars select nothing
+    for path in [
+        "@.wan",
+        "ipv4-address[1]",
+        "ipv4-address[-2]",
+        "uptime.x",
+        "route[*]",
+    ] {
+        assert!(status.query(path).unwrap().is_empty(), "{}", path);
+    }
+
+    // Every empty list below "inactive"
+    let inactive = status.query("inactive.*").unwrap();
+    assert_eq!(inactive.len(), 6);
+    assert!(
+        inactive
+            .iter()
+            .all(|value| matches!(value, BlobMsgPayload::Array(list) if list.is_empty()))
+    );
+}
+
+#[test]
+fn wildcards() {
+    let value = blobmsg!({
+        "ports": [
+            { "name": "lan1", "speed": 1000 },
+            { "name": "lan2" },
+            { "name": "wan", "speed": 100 },
+        ],
+        "a/b": { "~": true },
+        "dup": 1,
+        "dup": 2,
+    });
+    let data = value.to_bytes().unwrap();
+    let root = status(&data);
+
+    let names: Vec<_> = root
+        .query("ports[*].name")
+        .unwrap()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect();
+    assert_eq!(names, ["\"lan1\"", "\"lan2\"", "\"wan\""]);
+    let speeds = root.query("/ports/*/speed").unwrap();
+    assert!(matches!(
+        speeds[..],
+        [BlobMsgPayload::Int32(1000), BlobMsgPayload::Int32(100)]
+    ));
+    assert_eq!(root.query("ports[-1].*").unwrap().len(), 2);
+    assert_eq!(root.query("*").unwrap().len(), 4);
+
+    // Keys which need quoting or escaping, the last duplicate wins
+    assert!(matches!(
+        root.query("['a/b']['~']").unwrap()[..],
+        [BlobMsgPayload::Bool(true)]
+    ));
+    assert!(matches!(
+        root.pointer("/a~1b/~0"),
+        Some(BlobMsgPayload::Bool(true))
+    ));
+    assert!(matches!(
+        root.pointer("dup"),
+        Some(BlobMsgPayload::Int32(2))
+    ));
+
+    let path: BlobMsgPath = "ports[1].name".parse().unwrap();
+    assert_eq!(
+        path.steps(),
+        [
+            PathStep::Key("ports".into()),
+            PathStep::Index(1),
+            PathStep::Key("name".into()),
+        ]
+    );
+}
+
+#[test]
+fn errors() {
+    let cases = [
+        ("a..b", 2, "empty key"),
+        ("a[x]", 2, "invalid index"),
+        ("a[1", 3, "expected ']'"),
+        ("a['b]", 2, "unterminated quote"),
+        ("@x", 1, "expected '.' or '['"),
+        ("/a/~2", 3, "'~' must be followed by 0 or 1"),
+    ];
+    for (path, position, problem) in cases {
+        match BlobMsgPath::parse(path) {
+            Err(UbusError::Path {
+                position: found_position,
+                problem: found,
+            }) => assert_eq!((found_position, found), (position, problem), "{}", path),
+            other => panic!("{}: {:?}", path, other),
+        }
+    }
+    assert_eq!(
+        BlobMsgPath::parse("a[x]").unwrap_err().to_string(),
+        "Path position 2: invalid index"
+    );
+}
@@ -0,0 +1,131 @@
+use std::path::Path;
+use std::vec::Vec;
+use std::{format, fs};
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+/// Every message captured from the `list`, `invoke` and `status` fixtures
+fn corpus() -> Vec<(String, Vec<u8>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut corpus: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read(path).unwrap())
+        })
+        .collect();
+    corpus.sort();
+    corpus
+}
+
+/// Decode a list of blobmsg attributes and encode them again
+fn reencode(data: &[u8]) -> Vec<u8> {
+    let mut builder = BlobMsgBuilder::new();
+    for blob in BlobIter::<Blob>::new(data) {
+        let msg: BlobMsg = blob.unwrap().try_into().unwrap();
+        builder.add(&msg).unwrap();
+    }
+    builder.data().to_vec()
+}
+
+#[test]
+fn fixtures() {
+    let corpus = corpus();
+    assert!(corpus.len() > 30);
+    for (name, raw) in corpus {
+        let mut buffer = [0u8; 4096];
+        let message = UbusMsg::from_io(&mut Replay(&raw), &mut buffer).unwrap();
+
+        let mut out = [0u8; 4096];
+        let mut builder = UbusMsgBuilder::new(&mut out, &message.header).unwrap();
+        for attr in BlobIter::<UbusMsgAttr>::new(message.blob.data) {
+            let attr = attr.unwrap();
+            if let UbusMsgAttr::Data(data) = attr {
+                assert_eq!(reencode(data), data, "{}", name);
+            }
+            builder.put(attr).unwrap();
+        }
+        assert_eq!(builder.finish().unwrap(), raw, "{}", name);
+    }
+}
+
+#[test]
+fn unknown() {
+    // Type 42 is not known to libubox, nor is the top level attribute 0x50
+    let mut data = BlobMsgBuilder::new();
+    data.add_field(42, "future", &[0xde, 0xad, 0xbe, 0xef, 0x01])
+        .unwrap();
+    data.open_table("nested").unwrap();
+    data.add_field(42, "", &[]).unwrap();
+    data.close().unwrap();
+    let data = data.data();
+    assert_eq!(reencode(data), data);
+
+    let msg: BlobMsg = Blob::from_bytes(data).unwrap().try_into().unwrap();
+    assert!(matches!(
+        msg.data,
+        BlobMsgPayload::Unknown(42, [0xde, 0xad, 0xbe, 0xef, 0x01])
+    ));
+
+    // Owned copies encode the same
+    let mut builder = BlobMsgBuilder::new();
+    for blob in BlobIter::<Blob>::new(data) {
+        let owned = TryInto::<BlobMsg>::try_into(blob.unwrap())
+            .unwrap()
+            .to_owned_value();
+        builder.add(&owned.as_blobmsg()).unwrap();
+    }
+    assert_eq!(builder.data(), data);
+
+    let header = UbusMsgHeader {
+        version: UbusMsgVersion::CURRENT,
+        cmd_type: UbusCmdType::DATA,
+        sequence: 7.into(),
+        peer: 0.into(),
+    };
+    let mut buffer = [0u8; 128];
+    let mut builder = UbusMsgBuilder::new(&mut buffer, &header).unwrap();
+    builder
+        .put(UbusMsgAttr::Unknown(BlobAttrId::from(0x50), &[1, 2, 3]))
+        .unwrap();
+    builder.put(UbusMsgAttr::Data(data)).unwrap();
+    let raw = builder.finish().unwrap().to_vec();
+
+    let mut buffer = [0u8; 128];
+    let message = UbusMsg::from_io(&mut Replay(&raw), &mut buffer).unwrap();
+    let attrs: Vec<_> = BlobIter::<UbusMsgAttr>::new(message.blob.data)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        format!("{:?}", attrs[0]),
+        format!(
+            "{:?}",
+            UbusMsgAttr::Unknown(BlobAttrId::from(0x50), &[1, 2, 3])
+        )
+    );
+}
+
+#[test]
+fn encode_errors() {
+    // Type ids only have 7 bits
+    assert!(BlobMsgBuilder::new_extended(0x80, "x").is_err());
+    let mut builder = BlobMsgBuilder::new();
+    assert!(builder.add_field(0x80, "x", &[]).is_err());
+    let long = "x".repeat(0x10000);
+    assert!(builder.add_string(&long, "").is_err());
+    assert!(
+        builder
+            .add(&BlobMsg {
+                name: "list",
+                data: BlobMsgPayload::Array(Vec::from([BlobMsg {
+                    name: "",
+                    data: BlobMsgPayload::Unknown(0x80, &[]),
+                }])),
+            })
+            .is_err()
+    );
+    assert!(BlobMsgBuilder::new().build().is_err());
+}
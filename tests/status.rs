@@ -0,0 +1,505 @@
+use serde_json::{Value, json};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use ubus::*;
+
+fn connect() -> Connection<UnixStream> {
+    let (client, mut server) = UnixStream::pair().unwrap();
+
+    std::thread::spawn(move || {
+        server.write_all(TEST_HELLO).unwrap();
+        let mut command = [0u8; TEST_TX.len()];
+        server.read_exact(&mut command).unwrap();
+        assert_eq!(&command[..], TEST_TX);
+        for i in TEST_RX {
+            server.write_all(i).unwrap();
+        }
+    });
+
+    Connection::new(client).unwrap()
+}
+
+#[test]
+fn bool() {
+    let mut connection = connect();
+    let mut replies = 0;
+    connection
+        .invoke(0x5c3a17e2, "status", &[], |x| {
+            for i in x {
+                let msg: BlobMsg = i.unwrap().try_into().unwrap();
+                match msg.name {
+                    "up" | "available" | "autostart" | "delegation" => {
+                        assert!(matches!(msg.data, BlobMsgPayload::Bool(true)));
+                        assert_eq!(format!("{}", msg), format!("\"{}\": true", msg.name));
+                    }
+                    "pending" | "dynamic" => {
+                        assert!(matches!(msg.data, BlobMsgPayload::Bool(false)));
+                        assert_eq!(format!("{}", msg), format!("\"{}\": false", msg.name));
+                    }
+                    "uptime" => assert!(matches!(msg.data, BlobMsgPayload::Int32(86123))),
+                    _ => {}
+                }
+            }
+            replies += 1;
+        })
+        .unwrap();
+    assert_eq!(replies, 1);
+}
+
+#[test]
+fn bool_args() {
+    assert_eq!(BlobMsgType::INT8, BlobMsgType::BOOL);
+    assert_eq!(format!("{:?}", BlobMsgType::from(7)), "BOOL");
+
+    let mut policy = OrderedMap::new();
+    policy.insert("force", BlobMsgType::BOOL);
+    let method = Method {
+        name: "down",
+        policy,
+    };
+    let mut obj = UbusObject {
+        path: "network.interface.lan",
+        id: 0x5c3a17e2,
+        ..Default::default()
+    };
+    obj.methods.insert("down", method.clone());
+
+    let args = obj.args_from_json("down", r#"{"force": true}"#).unwrap();
+    assert_eq!(
+        args,
+        [
+            0x87, 0x00, 0x00, 0x0d, 0x00, 0x05, 0x66, 0x6f, 0x72, 0x63, 0x65, 0x00, 0x01, 0x00,
+            0x00, 0x00,
+        ]
+    );
+    let args = method.parse_args(&args).unwrap();
+    assert!(matches!(args[0].data, BlobMsgPayload::Bool(true)));
+
+    // Type 7 is always a boolean, anything but 0 is true like `blobmsg_get_bool()` says
+    let raw = [
+        0x87, 0x00, 0x00, 0x0d, 0x00, 0x05, 0x66, 0x6f, 0x72, 0x63, 0x65, 0x00, 0x02, 0x00, 0x00,
+        0x00,
+    ];
+    let blob: BlobMsg = Blob::from_bytes(&raw).unwrap().try_into().unwrap();
+    assert!(matches!(blob.data, BlobMsgPayload::Bool(true)));
+    let args = method.parse_args(&raw).unwrap();
+    assert!(matches!(args[0].data, BlobMsgPayload::Bool(true)));
+}
+
+#[test]
+fn json() {
+    let mut connection = connect();
+    let mut reply = Value::Null;
+    connection
+        .invoke(0x5c3a17e2, "status", &[], |x| {
+            reply = Value::try_from(x).unwrap();
+        })
+        .unwrap();
+    assert_eq!(
+        reply,
+        json!({
+            "up": true,
+            "pending": false,
+            "available": true,
+            "autostart": true,
+            "dynamic": false,
+            "uptime": 86123,
+            "l3_device": "br-lan",
+            "proto": "static",
+            "device": "br-lan",
+            "updated": ["addresses"],
+            "metric": 0,
+            "dns_metric": 0,
+            "delegation": true,
+            "ipv4-address": [{"address": "192.168.1.1", "mask": 24}],
+            "ipv6-address": [],
+            "ipv6-prefix": [],
+            "ipv6-prefix-assignment": [],
+            "route": [],
+            "dns-server": [],
+            "dns-search": [],
+            "neighbors": [],
+            "inactive": {
+                "ipv4-address": [],
+                "ipv6-address": [],
+                "route": [],
+                "dns-server": [],
+                "dns-search": [],
+                "neighbors": []
+            },
+            "data": {}
+        })
+    );
+
+    // JSON -> blobmsg -> JSON keeps every value and picks libubox integer widths
+    let value = json!({
+        "name": "eth0",
+        "mtu": 1500,
+        "rx_bytes": 5_000_000_000i64,
+        "load": 0.25,
+        "up": true,
+        "carrier": null,
+        "vlans": [1, 2, [3, {"tagged": false}]],
+        "opts": {"nested": {"deeper": -1}}
+    });
+    let payload = BlobMsgPayload::try_from(&value).unwrap();
+    let BlobMsgPayload::Table(table) = &payload else {
+        panic!("expected a table");
+    };
+    assert!(matches!(table["mtu"], BlobMsgPayload::Int32(1500)));
+    assert!(matches!(
+        table["rx_bytes"],
+        BlobMsgPayload::Int64(5_000_000_000)
+    ));
+    let builder = BlobMsgBuilder::try_from(BlobMsg {
+        name: "",
+        data: payload,
+    })
+    .unwrap();
+    let decoded: BlobMsg = builder.build().unwrap().try_into().unwrap();
+    assert_eq!(Value::from(&decoded), value);
+
+    // Nested arguments are passed through when the policy asks for them
+    let mut policy = OrderedMap::new();
+    policy.insert("vlans", BlobMsgType::ARRAY);
+    policy.insert("opts", BlobMsgType::TABLE);
+    let mut obj = UbusObject::default();
+    obj.methods.insert(
+        "set",
+        Method {
+            name: "set",
+            policy,
+        },
+    );
+    // The other keys of `value` are not in the policy
+    let coercion = Coercion {
+        skip_unknown: true,
+        ..Coercion::STRICT
+    };
+    let args = obj
+        .args_from_json_with("set", &value.to_string(), coercion)
+        .unwrap();
+    let args = Value::try_from(BlobIter::<Blob>::new(&args)).unwrap();
+    assert_eq!(
+        args,
+        json!({"vlans": [1, 2, [3, {"tagged": false}]], "opts": {"nested": {"deeper": -1}}})
+    );
+}
+
+#[test]
+fn owned() {
+    let mut connection = connect();
+    let mut reply = None;
+    connection
+        .invoke(0x5c3a17e2, "status", &[], |x| {
+            reply = Some(BlobMsgValue::try_from(x).unwrap());
+        })
+        .unwrap();
+    drop(connection);
+
+    let reply = reply.unwrap();
+    assert_eq!(reply.get("up"), Some(&BlobMsgValue::Bool(true)));
+    assert_eq!(reply.get("uptime"), Some(&BlobMsgValue::Int32(86123)));
+    let Some(BlobMsgValue::Array(addresses)) = reply.get("ipv4-address") else {
+        panic!("expected an array");
+    };
+    assert_eq!(
+        addresses[0].get("address"),
+        Some(&BlobMsgValue::String("192.168.1.1".to_string()))
+    );
+
+    // Borrowing the owned value again gives back the decoded payload
+    let builder = BlobMsgBuilder::try_from(BlobMsg {
+        name: "status",
+        data: reply.as_payload(),
+    })
+    .unwrap();
+    let decoded: BlobMsg = builder.build().unwrap().try_into().unwrap();
+    assert_eq!(decoded.to_owned_value().data, reply);
+}
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn view() {
+    let mut connection = connect();
+    connection
+        .invoke(0x5c3a17e2, "status", &[], |x| {
+            let before = ALLOCATIONS.with(Cell::get);
+
+            let view = BlobMsgView::from(x);
+            assert_eq!(view.get("up").and_then(|v| v.as_bool()), Some(true));
+            assert_eq!(view.get("uptime").and_then(|v| v.as_i32()), Some(86123));
+            assert_eq!(view.get("uptime").and_then(|v| v.as_int()), Some(86123));
+            assert_eq!(view.get("proto").and_then(|v| v.as_str()), Some("static"));
+            let address = view
+                .get("ipv4-address")
+                .and_then(|v| v.nth(0))
+                .and_then(|v| v.get("address"))
+                .and_then(|v| v.as_str());
+            assert_eq!(address, Some("192.168.1.1"));
+
+            // Wrong types and missing keys are simply absent
+            assert_eq!(view.get("uptime").and_then(|v| v.as_str()), None);
+            assert!(view.get("missing").is_none());
+            assert!(view.get("ipv6-address").is_some_and(|v| v.is_empty()));
+            assert_eq!(view.get("inactive").map(|v| v.len()), Some(6));
+            assert_eq!(view.iter().count(), 23);
+
+            assert_eq!(ALLOCATIONS.with(Cell::get), before);
+
+            let inactive = view.get("inactive").unwrap().decode().unwrap();
+            assert!(matches!(inactive, BlobMsgPayload::Table(t) if t.len() == 6));
+            assert!(ALLOCATIONS.with(Cell::get) > before);
+        })
+        .unwrap();
+}
+
+const STATUS_POLICY: [BlobMsgPolicy; 4] = [
+    BlobMsgPolicy::required("up", BlobMsgType::BOOL),
+    BlobMsgPolicy::new("uptime", BlobMsgType::INT32),
+    BlobMsgPolicy::required("ipv4-address", BlobMsgType::ARRAY),
+    BlobMsgPolicy::new("errors", BlobMsgType::ARRAY),
+];
+
+#[test]
+fn policy() {
+    let mut connection = connect();
+    connection
+        .invoke(0x5c3a17e2, "status", &[], |x| {
+            let data = BlobMsgView::from(x).data();
+
+            let [up, uptime, addresses, errors] =
+                BlobMsgPolicy::parse(&STATUS_POLICY, data).unwrap();
+            assert_eq!(up.and_then(|v| v.as_bool()), Some(true));
+            assert_eq!(uptime.and_then(|v| v.as_i32()), Some(86123));
+            assert_eq!(addresses.map(|v| v.len()), Some(1));
+            assert!(errors.is_none());
+
+            let missing = [BlobMsgPolicy::required("errors", BlobMsgType::ARRAY)];
+            assert!(matches!(
+                BlobMsgPolicy::parse(&missing, data),
+                Err(UbusError::MissingAttribute(name)) if name == "errors"
+            ));
+
+            let wrong = [BlobMsgPolicy::new("uptime", BlobMsgType::STRING)];
+            assert!(matches!(
+                BlobMsgPolicy::parse(&wrong, data),
+                Err(UbusError::AttributeType {
+                    name,
+                    expected: BlobMsgType::STRING,
+                    found: BlobMsgType::INT32,
+                }) if name == "uptime"
+            ));
+
+            // UNSPEC accepts any type, policies built at runtime use parse_into
+            let policy = vec![
+                BlobMsgPolicy::new("device", BlobMsgType::UNSPEC),
+                BlobMsgPolicy::new("metric", BlobMsgType::UNSPEC),
+            ];
+            let mut slots = vec![None; policy.len()];
+            BlobMsgPolicy::parse_into(&policy, data, &mut slots).unwrap();
+            assert_eq!(slots[0].and_then(|v| v.as_str()), Some("br-lan"));
+            assert_eq!(slots[1].and_then(|v| v.as_int()), Some(0));
+        })
+        .unwrap();
+
+    // The last of duplicated attributes wins
+    let [signal] = BlobMsgPolicy::parse(
+        &[BlobMsgPolicy::new("signal", BlobMsgType::INT32)],
+        &DUPLICATE_KEYS[16..],
+    )
+    .unwrap();
+    assert_eq!(signal.and_then(|v| v.as_i32()), Some(-72));
+}
+
+#[test]
+fn order() {
+    let mut connection = connect();
+    let mut names = Vec::new();
+    let mut inactive = String::new();
+    let mut json = Value::Null;
+    connection
+        .invoke(0x5c3a17e2, "status", &[], |x| {
+            let mut table = BlobMsgTable::default();
+            for i in x {
+                let msg: BlobMsg = i.unwrap().try_into().unwrap();
+                table.push(msg.name, msg.data.clone());
+                names.push(msg.name.to_string());
+                if msg.name == "inactive" {
+                    inactive = msg.to_string();
+                }
+            }
+            json = Value::from(BlobMsgPayload::Table(table));
+        })
+        .unwrap();
+    assert_eq!(
+        names,
+        [
+            "up",
+            "pending",
+            "available",
+            "autostart",
+            "dynamic",
+            "uptime",
+            "l3_device",
+            "proto",
+            "device",
+            "updated",
+            "metric",
+            "dns_metric",
+            "delegation",
+            "ipv4-address",
+            "ipv6-address",
+            "ipv6-prefix",
+            "ipv6-prefix-assignment",
+            "route",
+            "dns-server",
+            "dns-search",
+            "neighbors",
+            "inactive",
+            "data",
+        ]
+    );
+    assert!(json.as_object().unwrap().keys().eq(&names));
+    assert_eq!(
+        inactive,
+        r#""inactive": {"ipv4-address": [], "ipv6-address": [], "route": [], "dns-server": [], "dns-search": [], "neighbors": []}"#
+    );
+}
+
+#[test]
+fn duplicate_keys() {
+    let blob = Blob::from_bytes(DUPLICATE_KEYS).unwrap();
+    let msg: BlobMsg = blob.try_into().unwrap();
+    let BlobMsgPayload::Table(table) = &msg.data else {
+        panic!("expected a table");
+    };
+    assert_eq!(table.len(), 4);
+    assert_eq!(
+        table.keys().collect::<Vec<_>>(),
+        ["mac", "signal", "mac", "signal"]
+    );
+    assert!(matches!(
+        table.get("signal"),
+        Some(BlobMsgPayload::Int32(-72))
+    ));
+    assert!(matches!(
+        table.get_all("mac").collect::<Vec<_>>()[..],
+        [
+            BlobMsgPayload::String("00:11:22:33:44:55"),
+            BlobMsgPayload::String("66:77:88:99:aa:bb")
+        ]
+    ));
+
+    // JSON objects keep wire order but one value per key, the last like lookups
+    let value = Value::from(&msg.data);
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"mac":"66:77:88:99:aa:bb","signal":-72}"#
+    );
+
+    // Re-encoding keeps both entries in their original order
+    let builder = BlobMsgBuilder::try_from(msg).unwrap();
+    assert_eq!(builder.data(), DUPLICATE_KEYS);
+}
+
+const DUPLICATE_KEYS: &[u8] = &[
+    0x82, 0x00, 0x00, 0x78, 0x00, 0x07, 0x63, 0x6c, 0x69, 0x65, 0x6e, 0x74, 0x73, 0x00, 0x00, 0x00,
+    0x83, 0x00, 0x00, 0x1e, 0x00, 0x03, 0x6d, 0x61, 0x63, 0x00, 0x00, 0x00, 0x30, 0x30, 0x3a, 0x31,
+    0x31, 0x3a, 0x32, 0x32, 0x3a, 0x33, 0x33, 0x3a, 0x34, 0x34, 0x3a, 0x35, 0x35, 0x00, 0x00, 0x00,
+    0x85, 0x00, 0x00, 0x14, 0x00, 0x06, 0x73, 0x69, 0x67, 0x6e, 0x61, 0x6c, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0xc4, 0x83, 0x00, 0x00, 0x1e, 0x00, 0x03, 0x6d, 0x61, 0x63, 0x00, 0x00, 0x00,
+    0x36, 0x36, 0x3a, 0x37, 0x37, 0x3a, 0x38, 0x38, 0x3a, 0x39, 0x39, 0x3a, 0x61, 0x61, 0x3a, 0x62,
+    0x62, 0x00, 0x00, 0x00, 0x85, 0x00, 0x00, 0x14, 0x00, 0x06, 0x73, 0x69, 0x67, 0x6e, 0x61, 0x6c,
+    0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xb8,
+];
+
+// Data dumped from `ubus call network.interface.lan status`
+const TEST_HELLO: &[u8] = &[
+    0x00, 0x00, 0x00, 0x00, 0x2e, 0xb8, 0x63, 0xdb, 0x00, 0x00, 0x00, 0x04,
+];
+
+const TEST_TX: &[u8] = &[
+    0x00, 0x05, 0x00, 0x01, 0x5c, 0x3a, 0x17, 0xe2, 0x00, 0x00, 0x00, 0x1c, 0x03, 0x00, 0x00, 0x08,
+    0x5c, 0x3a, 0x17, 0xe2, 0x04, 0x00, 0x00, 0x0b, 0x73, 0x74, 0x61, 0x74, 0x75, 0x73, 0x00, 0x00,
+    0x07, 0x00, 0x00, 0x04,
+];
+
+const TEST_RX: &[&[u8]] = &[
+    &[
+        0x00, 0x02, 0x00, 0x01, 0x5c, 0x3a, 0x17, 0xe2, 0x00, 0x00, 0x02, 0x8c,
+    ],
+    &[
+        0x03, 0x00, 0x00, 0x08, 0x13, 0x33, 0x33, 0x77, 0x07, 0x00, 0x02, 0x80, 0x87, 0x00, 0x00,
+        0x0d, 0x00, 0x02, 0x75, 0x70, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x87, 0x00,
+        0x00, 0x11, 0x00, 0x07, 0x70, 0x65, 0x6e, 0x64, 0x69, 0x6e, 0x67, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x87, 0x00, 0x00, 0x11, 0x00, 0x09, 0x61, 0x76, 0x61, 0x69, 0x6c, 0x61,
+        0x62, 0x6c, 0x65, 0x00, 0x01, 0x00, 0x00, 0x00, 0x87, 0x00, 0x00, 0x11, 0x00, 0x09, 0x61,
+        0x75, 0x74, 0x6f, 0x73, 0x74, 0x61, 0x72, 0x74, 0x00, 0x01, 0x00, 0x00, 0x00, 0x87, 0x00,
+        0x00, 0x11, 0x00, 0x07, 0x64, 0x79, 0x6e, 0x61, 0x6d, 0x69, 0x63, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x85, 0x00, 0x00, 0x14, 0x00, 0x06, 0x75, 0x70, 0x74, 0x69, 0x6d, 0x65,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x50, 0x6b, 0x83, 0x00, 0x00, 0x17, 0x00, 0x09, 0x6c,
+        0x33, 0x5f, 0x64, 0x65, 0x76, 0x69, 0x63, 0x65, 0x00, 0x62, 0x72, 0x2d, 0x6c, 0x61, 0x6e,
+        0x00, 0x00, 0x83, 0x00, 0x00, 0x13, 0x00, 0x05, 0x70, 0x72, 0x6f, 0x74, 0x6f, 0x00, 0x73,
+        0x74, 0x61, 0x74, 0x69, 0x63, 0x00, 0x00, 0x83, 0x00, 0x00, 0x17, 0x00, 0x06, 0x64, 0x65,
+        0x76, 0x69, 0x63, 0x65, 0x00, 0x00, 0x00, 0x00, 0x62, 0x72, 0x2d, 0x6c, 0x61, 0x6e, 0x00,
+        0x00, 0x81, 0x00, 0x00, 0x24, 0x00, 0x07, 0x75, 0x70, 0x64, 0x61, 0x74, 0x65, 0x64, 0x00,
+        0x00, 0x00, 0x83, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x00, 0x61, 0x64, 0x64, 0x72, 0x65,
+        0x73, 0x73, 0x65, 0x73, 0x00, 0x00, 0x00, 0x85, 0x00, 0x00, 0x14, 0x00, 0x06, 0x6d, 0x65,
+        0x74, 0x72, 0x69, 0x63, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x85, 0x00, 0x00,
+        0x18, 0x00, 0x0a, 0x64, 0x6e, 0x73, 0x5f, 0x6d, 0x65, 0x74, 0x72, 0x69, 0x63, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x87, 0x00, 0x00, 0x15, 0x00, 0x0a, 0x64, 0x65, 0x6c,
+        0x65, 0x67, 0x61, 0x74, 0x69, 0x6f, 0x6e, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x81, 0x00, 0x00, 0x48, 0x00, 0x0c, 0x69, 0x70, 0x76, 0x34, 0x2d, 0x61, 0x64, 0x64, 0x72,
+        0x65, 0x73, 0x73, 0x00, 0x00, 0x82, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00, 0x00, 0x83, 0x00,
+        0x00, 0x1c, 0x00, 0x07, 0x61, 0x64, 0x64, 0x72, 0x65, 0x73, 0x73, 0x00, 0x00, 0x00, 0x31,
+        0x39, 0x32, 0x2e, 0x31, 0x36, 0x38, 0x2e, 0x31, 0x2e, 0x31, 0x00, 0x85, 0x00, 0x00, 0x10,
+        0x00, 0x04, 0x6d, 0x61, 0x73, 0x6b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x81, 0x00, 0x00,
+        0x14, 0x00, 0x0c, 0x69, 0x70, 0x76, 0x36, 0x2d, 0x61, 0x64, 0x64, 0x72, 0x65, 0x73, 0x73,
+        0x00, 0x00, 0x81, 0x00, 0x00, 0x14, 0x00, 0x0b, 0x69, 0x70, 0x76, 0x36, 0x2d, 0x70, 0x72,
+        0x65, 0x66, 0x69, 0x78, 0x00, 0x00, 0x00, 0x81, 0x00, 0x00, 0x20, 0x00, 0x16, 0x69, 0x70,
+        0x76, 0x36, 0x2d, 0x70, 0x72, 0x65, 0x66, 0x69, 0x78, 0x2d, 0x61, 0x73, 0x73, 0x69, 0x67,
+        0x6e, 0x6d, 0x65, 0x6e, 0x74, 0x00, 0x00, 0x00, 0x00, 0x81, 0x00, 0x00, 0x0c, 0x00, 0x05,
+        0x72, 0x6f, 0x75, 0x74, 0x65, 0x00, 0x81, 0x00, 0x00, 0x14, 0x00, 0x0a, 0x64, 0x6e, 0x73,
+        0x2d, 0x73, 0x65, 0x72, 0x76, 0x65, 0x72, 0x00, 0x00, 0x00, 0x00, 0x81, 0x00, 0x00, 0x14,
+        0x00, 0x0a, 0x64, 0x6e, 0x73, 0x2d, 0x73, 0x65, 0x61, 0x72, 0x63, 0x68, 0x00, 0x00, 0x00,
+        0x00, 0x81, 0x00, 0x00, 0x10, 0x00, 0x09, 0x6e, 0x65, 0x69, 0x67, 0x68, 0x62, 0x6f, 0x72,
+        0x73, 0x00, 0x82, 0x00, 0x00, 0x7c, 0x00, 0x08, 0x69, 0x6e, 0x61, 0x63, 0x74, 0x69, 0x76,
+        0x65, 0x00, 0x00, 0x81, 0x00, 0x00, 0x14, 0x00, 0x0c, 0x69, 0x70, 0x76, 0x34, 0x2d, 0x61,
+        0x64, 0x64, 0x72, 0x65, 0x73, 0x73, 0x00, 0x00, 0x81, 0x00, 0x00, 0x14, 0x00, 0x0c, 0x69,
+        0x70, 0x76, 0x36, 0x2d, 0x61, 0x64, 0x64, 0x72, 0x65, 0x73, 0x73, 0x00, 0x00, 0x81, 0x00,
+        0x00, 0x0c, 0x00, 0x05, 0x72, 0x6f, 0x75, 0x74, 0x65, 0x00, 0x81, 0x00, 0x00, 0x14, 0x00,
+        0x0a, 0x64, 0x6e, 0x73, 0x2d, 0x73, 0x65, 0x72, 0x76, 0x65, 0x72, 0x00, 0x00, 0x00, 0x00,
+        0x81, 0x00, 0x00, 0x14, 0x00, 0x0a, 0x64, 0x6e, 0x73, 0x2d, 0x73, 0x65, 0x61, 0x72, 0x63,
+        0x68, 0x00, 0x00, 0x00, 0x00, 0x81, 0x00, 0x00, 0x10, 0x00, 0x09, 0x6e, 0x65, 0x69, 0x67,
+        0x68, 0x62, 0x6f, 0x72, 0x73, 0x00, 0x82, 0x00, 0x00, 0x0c, 0x00, 0x04, 0x64, 0x61, 0x74,
+        0x61, 0x00, 0x00,
+    ],
+    &[
+        0x00, 0x01, 0x00, 0x01, 0x5c, 0x3a, 0x17, 0xe2, 0x00, 0x00, 0x00, 0x14,
+    ],
+    &[
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x08, 0x13, 0x33, 0x33,
+        0x77,
+    ],
+];
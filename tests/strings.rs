@@ -0,0 +1,126 @@
+use serde_json::{Value, json};
+use std::vec::Vec;
+use ubus::*;
+
+/// `iwinfo scan` result with a Latin-1 SSID next to a UTF-8 one
+fn scan() -> Vec<u8> {
+    let mut builder = BlobMsgBuilder::new();
+    builder.open_array("results").unwrap();
+    builder.open_table("").unwrap();
+    builder
+        .add_string_bytes("ssid", b"Caf\xe9 \"free\"")
+        .unwrap();
+    builder.add_int32("signal", -71).unwrap();
+    builder.close().unwrap();
+    builder.open_table("").unwrap();
+    builder.add_string("ssid", "Café").unwrap();
+    builder.add_int32("signal", -50).unwrap();
+    builder.close().unwrap();
+    builder.close().unwrap();
+    builder.data().to_vec()
+}
+
+#[test]
+fn decode() {
+    let data = scan();
+    let msg: BlobMsg = Blob::from_bytes(&data).unwrap().try_into().unwrap();
+    let BlobMsgPayload::Array(results) = &msg.data else {
+        panic!("expected an array");
+    };
+    let BlobMsgPayload::Table(first) = &results[0].data else {
+        panic!("expected a table");
+    };
+    assert!(matches!(
+        first.get("ssid"),
+        Some(BlobMsgPayload::Bytes(b"Caf\xe9 \"free\""))
+    ));
+    assert_eq!(
+        first.get("ssid").unwrap().as_string_bytes(),
+        Some(&b"Caf\xe9 \"free\""[..])
+    );
+    let BlobMsgPayload::Table(second) = &results[1].data else {
+        panic!("expected a table");
+    };
+    assert!(matches!(
+        second.get("ssid"),
+        Some(BlobMsgPayload::String("Café"))
+    ));
+
+    // Lossless: the bytes encode the same and survive an owned copy
+    let mut builder = BlobMsgBuilder::new();
+    builder.add(&msg).unwrap();
+    assert_eq!(builder.data(), data);
+    let mut builder = BlobMsgBuilder::new();
+    builder.add(&msg.to_owned_value().as_blobmsg()).unwrap();
+    assert_eq!(builder.data(), data);
+
+    let view = BlobMsgView::table(&data);
+    let ssid = view
+        .get("results")
+        .unwrap()
+        .nth(0)
+        .unwrap()
+        .get("ssid")
+        .unwrap();
+    assert_eq!(ssid.as_str(), None);
+    assert_eq!(ssid.as_bytes(), Some(&b"Caf\xe9 \"free\""[..]));
+}
+
+#[test]
+fn render() {
+    let data = scan();
+    let mut json = JsonWriter::new(String::new(), JsonStyle::Simple);
+    json.write_table(BlobIter::new(&data)).unwrap();
+    let json = json.into_inner();
+    assert_eq!(
+        json,
+        r#"{"results":[{"ssid":"Caf\udce9 \"free\"","signal":-71},{"ssid":"Café","signal":-50}]}"#
+    );
+
+    // A real U+00E9 is not confused with the byte 0xe9
+    let mut json = JsonWriter::new(String::new(), JsonStyle::Simple);
+    json.write_bytes("\u{e9}\u{dc}".as_bytes()).unwrap();
+    json.write_bytes(b"\xe9\xdc").unwrap();
+    assert_eq!(json.into_inner(), r#""éÜ""\udce9\udcdc""#);
+
+    // JSON values are UTF-8, invalid bytes are replaced
+    let value = Value::try_from(BlobIter::<Blob>::new(&data)).unwrap();
+    assert_eq!(value["results"][0]["ssid"], json!("Caf\u{fffd} \"free\""));
+    assert_eq!(value["results"][1]["ssid"], json!("Café"));
+
+    // jshn gets the raw bytes and parses them back
+    let mut jshn = JshnWriter::new(String::new());
+    jshn.write_table(BlobIter::new(&data)).unwrap();
+    let jshn = jshn.into_inner();
+    assert!(jshn.contains("json_add_string 'ssid' $'Caf\\xe9 \"free\"';\n"));
+    assert!(jshn.contains("json_add_string 'ssid' 'Café';\n"));
+    let parsed = parse_jshn(&jshn).unwrap();
+    assert_eq!(
+        parsed,
+        BlobMsgValue::Table(std::vec![(
+            "results".into(),
+            BlobMsgValue::Array(std::vec![
+                BlobMsgValue::Table(std::vec![
+                    (
+                        "ssid".into(),
+                        BlobMsgValue::Bytes(b"Caf\xe9 \"free\"".to_vec())
+                    ),
+                    ("signal".into(), BlobMsgValue::Int32(-71)),
+                ]),
+                BlobMsgValue::Table(std::vec![
+                    ("ssid".into(), BlobMsgValue::String("Café".into())),
+                    ("signal".into(), BlobMsgValue::Int32(-50)),
+                ]),
+            ])
+        )])
+    );
+    let escaped = parse_jshn(r"json_add_string s $'a\'\\\x41\n'").unwrap();
+    assert_eq!(
+        escaped,
+        BlobMsgValue::Table(std::vec![(
+            "s".into(),
+            BlobMsgValue::String("a'\\A\n".into())
+        )])
+    );
+    assert!(parse_jshn(r"json_add_string s $'\xzz'").is_err());
+}
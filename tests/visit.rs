@@ -0,0 +1,197 @@
+use std::string::String;
+use std::vec::Vec;
+use ubus::*;
+
+mod common;
+use common::Replay;
+
+/// Writes every event as one line
+#[derive(Default)]
+struct Recorder(Vec<String>);
+impl BlobMsgVisitor for Recorder {
+    fn enter_table(&mut self, name: &str) -> Result<(), UbusError> {
+        self.0.push(format!("{} {{", name));
+        Ok(())
+    }
+    fn enter_array(&mut self, name: &str) -> Result<(), UbusError> {
+        self.0.push(format!("{} [", name));
+        Ok(())
+    }
+    fn leave(&mut self) -> Result<(), UbusError> {
+        self.0.push("end".into());
+        Ok(())
+    }
+    fn scalar(&mut self, name: &str, value: BlobMsgPayload) -> Result<(), UbusError> {
+        self.0.push(format!("{} {:?}", name, value));
+        Ok(())
+    }
+}
+
+fn sample() -> Vec<u8> {
+    let mut builder = BlobMsgBuilder::new();
+    builder.add_string("name", "br-lan").unwrap();
+    builder.open_array("ipv4-address").unwrap();
+    builder.open_table("").unwrap();
+    builder.add_string("address", "192.168.1.1").unwrap();
+    builder.add_int16("mask", 24).unwrap();
+    builder.close().unwrap();
+    builder.close().unwrap();
+    builder.open_table("data").unwrap();
+    builder.close().unwrap();
+    builder
+        .add_field(BlobMsgType::UNSPEC.value(), "x", &[])
+        .unwrap();
+    builder.add_int64("rx_bytes", 5_000_000_000).unwrap();
+    builder.add_double("load", 0.5).unwrap();
+    builder.data().to_vec()
+}
+
+#[test]
+fn events() {
+    let data = sample();
+    let mut stream = Replay(&data);
+    let mut recorder = Recorder::default();
+    let mut scratch = [0u8; 32];
+    visit_blobmsg(
+        &mut stream,
+        data.len(),
+        &mut scratch,
+        &DecodeLimits::default(),
+        &mut recorder,
+    )
+    .unwrap();
+    assert!(stream.0.is_empty());
+    assert_eq!(
+        recorder.0,
+        [
+            "name String(\"br-lan\")",
+            "ipv4-address [",
+            " {",
+            "address String(\"192.168.1.1\")",
+            "mask Int16(24)",
+            "end",
+            "end",
+            "data {",
+            "end",
+            "x Unknown(0, [])",
+            "rx_bytes Int64(5000000000)",
+            "load Double(0.5)",
+        ]
+    );
+}
+
+#[test]
+fn limits() {
+    let data = sample();
+    let visit = |scratch: &mut [u8], limits: &DecodeLimits| {
+        visit_blobmsg(
+            &mut Replay(&data),
+            data.len(),
+            scratch,
+            limits,
+            &mut Recorder::default(),
+        )
+    };
+
+    // "address" with its value needs 22 bytes, containers only their name
+    assert!(matches!(
+        visit(&mut [0u8; 21], &DecodeLimits::default()),
+        Err(UbusError::InvalidData(
+            "Attribute larger than scratch buffer"
+        ))
+    ));
+    assert!(visit(&mut [0u8; 22], &DecodeLimits::default()).is_ok());
+    let shallow = DecodeLimits {
+        max_depth: 1,
+        ..DecodeLimits::default()
+    };
+    assert!(matches!(
+        visit(&mut [0u8; 64], &shallow),
+        Err(UbusError::NestingTooDeep(1))
+    ));
+    let few = DecodeLimits {
+        max_attrs: 3,
+        ..DecodeLimits::default()
+    };
+    assert!(matches!(
+        visit(&mut [0u8; 64], &few),
+        Err(UbusError::TooManyAttributes(3))
+    ));
+
+    // Running out of data, and a child larger than its table
+    assert!(matches!(
+        visit_blobmsg(
+            &mut Replay(&data[..20]),
+            data.len(),
+            &mut [0u8; 64],
+            &DecodeLimits::default(),
+            &mut Recorder::default(),
+        ),
+        Err(UbusError::InvalidData("Replay exhausted"))
+    ));
+    assert!(matches!(
+        visit_blobmsg(
+            &mut Replay(&data),
+            12,
+            &mut [0u8; 64],
+            &DecodeLimits::default(),
+            &mut Recorder::default(),
+        ),
+        Err(UbusError::InvalidData("Blob too short"))
+    ));
+}
+
+#[test]
+fn stop() {
+    /// Gives up once it has seen enough
+    struct First(Option<i64>);
+    impl BlobMsgVisitor for First {
+        fn enter_table(&mut self, _name: &str) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn enter_array(&mut self, _name: &str) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn leave(&mut self) -> Result<(), UbusError> {
+            Ok(())
+        }
+        fn scalar(&mut self, _name: &str, value: BlobMsgPayload) -> Result<(), UbusError> {
+            if let BlobMsgPayload::Int16(num) = value {
+                self.0 = Some(num.into());
+                return Err(UbusError::InvalidData("done"));
+            }
+            Ok(())
+        }
+    }
+
+    let data = sample();
+    let mut stream = data.clone();
+    stream.extend_from_slice(b"next");
+    let mut stream = Replay(&stream);
+    let mut first = First(None);
+    let result = visit_blobmsg(
+        &mut stream,
+        data.len(),
+        &mut [0u8; 64],
+        &DecodeLimits::default(),
+        &mut first,
+    );
+    assert!(matches!(result, Err(UbusError::InvalidData("done"))));
+    assert_eq!(first.0, Some(24));
+    // The rest of the data is dropped, up to what follows it
+    assert_eq!(stream.0, b"next");
+
+    // The same when a value does not fit the scratch buffer
+    let mut stream = data.clone();
+    stream.extend_from_slice(b"next");
+    let mut stream = Replay(&stream);
+    let result = visit_blobmsg(
+        &mut stream,
+        data.len(),
+        &mut [0u8; 21],
+        &DecodeLimits::default(),
+        &mut First(None),
+    );
+    assert!(result.is_err());
+    assert_eq!(stream.0, b"next");
+}